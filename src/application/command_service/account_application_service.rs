@@ -1,24 +1,47 @@
-use anyhow::Ok;
 use chrono::Local;
-use sea_orm::{ActiveModelTrait, ActiveValue::Set};
 
-use crate::{domain::{model::entity::account, repository::account_repository::find_by_pubkey}, infrastructure::database_connection::get_db, interface::rest::dto::logon::SignUpPayload};
+use crate::{domain::model::entity::account, error::ApiError, interface::rest::dto::logon::SignUpPayload, state::AppState};
 
 /// 注册账户
-pub async fn register_account(payload: &SignUpPayload) -> Result<String, anyhow::Error> {
-    let account = account::ActiveModel {
-        id: Set(uuid::Uuid::new_v4()),
-        nick_name: Set(if !payload.nick_name.is_empty() {Some(payload.nick_name.clone())} else {Some("Papi".to_owned())}),
-        avatar: Set("/favicon.svg".to_owned()),
-        pub_key: Set(Some(payload.pub_key.clone())),
-        created_time: Set(Local::now().naive_utc()),
-        status: Set(Some(1)),
-        ..Default::default()
+pub async fn register_account(state: &AppState, payload: &SignUpPayload) -> Result<String, ApiError> {
+    let account = account::Model {
+        id: uuid::Uuid::new_v4(),
+        nick_name: if !payload.nick_name.is_empty() {Some(payload.nick_name.clone())} else {Some("Papi".to_owned())},
+        avatar: "/favicon.svg".to_owned(),
+        pub_key: Some(payload.pub_key.clone()),
+        wallet_address: None,
+        created_time: Local::now().naive_utc(),
+        status: Some(1),
     };
-    let exist_accounts = find_by_pubkey(&payload.pub_key).await;
-    if exist_accounts.is_empty() {
-        let account = account.insert(get_db().as_ref()).await?;
-        return Ok(account.id.to_string());
+    if state.account_repository.get_account_by(&payload.pub_key).await?.is_some() {
+        return Err(ApiError::AccountExists)
     }
-    anyhow::bail!("账号已存在")  
-}
\ No newline at end of file
+    let account_id = account.id.to_string();
+    state.account_repository.add_account(account).await?;
+    Ok(account_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{error::ApiError, interface::rest::dto::logon::SignUpPayload, settings::AppConfig, state::{fakes::InMemory, AppState}};
+
+    use super::register_account;
+
+    #[tokio::test]
+    async fn test_register_account_once() {
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        let payload = SignUpPayload { request_id: "r".to_owned(), pub_key: "dd27".to_owned(), nick_name: String::new(), sig: String::new() };
+
+        let account_id = register_account(&state, &payload).await.unwrap();
+        let accounts = store.accounts.lock().unwrap().clone();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].id.to_string(), account_id);
+        assert_eq!(accounts[0].nick_name.as_deref(), Some("Papi"));
+
+        assert!(matches!(register_account(&state, &payload).await, Err(ApiError::AccountExists)));
+        assert_eq!(store.accounts.lock().unwrap().len(), 1);
+    }
+}
@@ -1,144 +1,166 @@
 
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use chrono::Local;
-use sea_orm::ActiveValue::Set;
-use tokio::fs;
 
-use crate::{domain::{command::collection_command::{AddVideoCommand, CreateArticleCommand, CreateCollectionCommand}, model::entity::{collection, collection_item}, repository::{account_repository::{self}, collection_repository::{self}}}, infrastructure::image_util::{image_type, make_thumbnail}};
+use crate::{domain::{command::collection_command::{AddVideoCommand, CreateArticleCommand, CreateCollectionCommand, SetCollectionTagsCommand}, model::entity::{collection, collection_item}, service::tagging}, error::ApiError, infrastructure::{image_util::{image_type, store_thumbnail}, storage::{self, Volume}}, state::AppState};
 
-/// 创建专辑
-pub async fn create_collection(command: CreateCollectionCommand, icon_file_path: &PathBuf, assets_path: &String) -> Result<String, anyhow::Error> {
+/// 创建专辑,封面从icons/复制到专辑目录
+pub async fn create_collection(state: &AppState, command: CreateCollectionCommand) -> Result<String, ApiError> {
     // TODO 参数校验
     let id = uuid::Uuid::new_v4();
     let collection_id = id.to_string();
-    let exist_accounts = account_repository::find_by_pubkey(&command.pub_key).await;
-    if exist_accounts.is_empty() {
-        anyhow::bail!("未知账户");
-    }
-    let collections_by_title = collection_repository::search_collection_by(&command.title, &command.pub_key).await?;
+    let account_id = state.account_repository.get_account_by(&command.pub_key).await?.ok_or(ApiError::UnknownAccount)?.id;
+    let collections_by_title = state.collection_repository.search_collection_by(&command.title, &account_id).await?;
     if collections_by_title.len() > 0 {
-        anyhow::bail!("专辑名称重复");
+        return Err(ApiError::DuplicateCollectionTitle);
     }
-    let account_id = exist_accounts.get(0).unwrap().id.to_string();
+    let category_id = match &command.category_id {
+        Some(category_id) => {
+            let category_id = uuid::Uuid::parse_str(category_id).map_err(|_| ApiError::UnknownCategory)?;
+            if state.category_repository.get_category_by_id(&category_id).await?.is_none() {
+                return Err(ApiError::UnknownCategory);
+            }
+            Some(category_id)
+        },
+        None => None,
+    };
+    let tags = tagging::normalize_tags(&command.tags)?;
     // 复制文件
-    let collection_dir = std::path::Path::new(&assets_path).join(&collection_id);
-    if !collection_dir.exists() {
-        let _ = fs::create_dir(collection_dir).await;
-    }else {
-        if collection_dir.is_file() {
-            anyhow::bail!("无法创建专辑图片文件")
-        }
+    let extension = Path::new(&command.icon_path).extension().and_then(|extension| extension.to_str()).ok_or(ApiError::InvalidIcon)?;
+    if image_type(extension).is_none() {
+        return Err(ApiError::InvalidIcon)
     }
-    let target_path = std::path::Path::new(&assets_path).join(&collection_id).join(&command.icon_path);
-    let extension = target_path.extension();
-    if extension.is_none() {
-        anyhow::bail!("请上传专辑图片文件")
+    let icon_key = format!("{}/{}", collection_id, command.icon_path);
+    state.storage.copy(Volume::Assets, &format!("icons/{}", command.icon_path), &icon_key).await?;
+    // 创建缩略图,失败时在第一次访问缩略图时重新生成
+    if let Err(err) = store_thumbnail(state.storage.as_ref(), &icon_key).await {
+        tracing::warn!("failed to create thumbnail for {}: {:#}", icon_key, err);
     }
-    let image_type = image_type(extension.unwrap().to_str().unwrap());
-    if image_type.is_none() {
-        anyhow::bail!("无效图片格式")
-    }
-    let _ = fs::copy(icon_file_path, &target_path).await?;
-    // 创建缩略图
-    let _ = make_thumbnail(&target_path).await;
-    let collect = collection::ActiveModel {
-        id: Set(id),
-        title: Set(command.title),
-        description: Set(command.description),
-        is_public: Set(command.is_public.try_into().unwrap()),
-        author: Set(account_id),
-        seq: Set(1),
-        status: Set(1),
-        listing: Set(Some(0)),
-        created_time: Set(Local::now().naive_utc()),
-        icon_url: Set(Some("/".to_owned() + &collection_id + "/" + &command.icon_path)),
-        ..Default::default()
+    let collect = collection::Model {
+        id,
+        title: command.title,
+        description: command.description,
+        is_public: i32::try_from(command.is_public).map_err(|_| ApiError::InvalidParameter)?,
+        author: account_id,
+        seq: 1,
+        status: 1,
+        listing: Some(0),
+        created_time: Local::now().naive_utc(),
+        icon_url: Some("/".to_owned() + &collection_id + "/" + &command.icon_path),
+        package_id: None,
+        category_id,
     };
-    let _ = collection_repository::create_collection(collect).await?;
+    state.collection_repository.create_collection(collect, &tags).await?;
     Ok(collection_id)
 }
 
+/// 设置专辑标签
+pub async fn set_collection_tags(state: &AppState, command: SetCollectionTagsCommand) -> Result<Vec<String>, ApiError> {
+    let account_id = state.account_repository.get_account_by(&command.pub_key).await?.ok_or(ApiError::UnknownAccount)?.id;
+    let collection = state.collection_repository.get_my_collection_by_id(&command.collection_id, &account_id).await?.ok_or(ApiError::CollectionNotFound)?;
+    let tags = tagging::normalize_tags(&command.tags)?;
+    state.tag_repository.set_collection_tags(&collection.id, &tags).await?;
+    Ok(tags)
+}
+
 /// 创建文章
-pub async fn create_article(command: CreateArticleCommand) -> Result<String, anyhow::Error> {
+pub async fn create_article(state: &AppState, command: CreateArticleCommand) -> Result<String, ApiError> {
     // TODO 参数校验
     let id = uuid::Uuid::new_v4();
     let article_id = id.to_string();
-    let exist_accounts = account_repository::find_by_pubkey(&command.pub_key).await;
-    if exist_accounts.is_empty() {
-        anyhow::bail!("未知账户");
-    }
-    let account_id = exist_accounts.get(0).unwrap().id.to_string();
-    let collection = collection_repository::get_my_collection_by_id(&command.collection_id, &account_id).await;
-    if collection.is_none() {
-        anyhow::bail!("未知专辑");
-    }
+    let account_id = state.account_repository.get_account_by(&command.pub_key).await?.ok_or(ApiError::UnknownAccount)?.id;
+    let collection = state.collection_repository.get_my_collection_by_id(&command.collection_id, &account_id).await?.ok_or(ApiError::CollectionNotFound)?;
     
-    let article = collection_item::ActiveModel {
-        id: Set(id),
-        collection_id: Set(command.collection_id),
-        seq: Set(1),
-        title: Set(Some(command.title)),
-        description: Set(Some(command.description)),
-        created_time: Set(Local::now().naive_utc()),
-        is_public: Set(command.is_public.try_into().unwrap()),
-        author: Set(account_id),
-        content: Set(Some(command.content)),
-        status: Set(Some(1)),
-        category: Set("article".to_owned()),
-        ..Default::default()
+    let article = collection_item::Model {
+        id,
+        collection_id: collection.id,
+        seq: 1,
+        title: Some(command.title),
+        description: Some(command.description),
+        created_time: Local::now().naive_utc(),
+        is_public: i32::try_from(command.is_public).map_err(|_| ApiError::InvalidParameter)?,
+        author: account_id.to_string(),
+        content: Some(command.content),
+        path: None,
+        hash: None,
+        ipfs: None,
+        status: Some(1),
+        category: "article".to_owned(),
     };
-    let _ = collection_repository::create_collection_item(article).await?;
+    let _ = state.collection_repository.create_collection_item(article).await?;
     Ok(article_id)
 }
 
-/// 添加视频
-pub async fn add_video(command: &AddVideoCommand, medias_path: &String) -> Result<String, anyhow::Error> {
+/// 添加视频,合并后的文件复制到专辑目录
+pub async fn add_video(state: &AppState, command: &AddVideoCommand) -> Result<String, ApiError> {
     // TODO 参数校验
     let id = uuid::Uuid::new_v4();
     let video_id = id.to_string();
-    let exist_accounts = account_repository::find_by_pubkey(&command.pub_key).await;
-    if exist_accounts.is_empty() {
-        anyhow::bail!("未知账户");
-    }
-    let account_id = exist_accounts.get(0).unwrap().id.to_string();
-    let collection = collection_repository::get_my_collection_by_id(&command.collection_id, &account_id).await;
-    if collection.is_none() {
-        anyhow::bail!("未知专辑");
-    }
+    let account_id = state.account_repository.get_account_by(&command.pub_key).await?.ok_or(ApiError::UnknownAccount)?.id;
+    let collection = state.collection_repository.get_my_collection_by_id(&command.collection_id, &account_id).await?.ok_or(ApiError::CollectionNotFound)?;
 
-    let temp_file = format!("{}/{}", medias_path, &command.video_path);
-    let original = std::path::Path::new(&temp_file);
-    let extension = original.extension().unwrap().to_str().unwrap();
+    let extension = Path::new(&command.video_path).extension().and_then(|extension| extension.to_str()).ok_or(ApiError::VideoNotFound)?;
     let target_file_name = uuid::Uuid::new_v4().to_string() + "." + extension;
-    let path = format!("{}/{}", &command.collection_id, &target_file_name);
-    if original.exists() && original.is_file() {
-        let link = Path::new(medias_path).join(&command.collection_id);
-        if !link.exists() {
-            fs::create_dir_all(&link).await?;
-        }
-        let link = link.join(&target_file_name);
-        std::fs::hard_link(original, link)?;
-    }else {
-        anyhow::bail!("未知视频");
+    let path = format!("{}/{}", &collection.id, &target_file_name);
+    if !storage::is_valid_key(&command.video_path) || state.storage.size(Volume::Medias, &command.video_path).await?.is_none() {
+        return Err(ApiError::VideoNotFound);
     }
+    state.storage.copy(Volume::Medias, &command.video_path, &path).await?;
     
-    let video = collection_item::ActiveModel {
-        id: Set(id),
-        collection_id: Set(command.collection_id.clone()),
-        seq: Set(1),
-        title: Set(Some(command.title.clone())),
-        description: Set(Some(command.description.clone())),
-        created_time: Set(Local::now().naive_utc()),
-        is_public: Set(command.is_public.try_into().unwrap()),
-        author: Set(account_id),
-        content: Set(None),
-        path: Set(Some(path)),
-        hash: Set(Some(command.hash.clone())),
-        status: Set(Some(1)),
-        category: Set("video".to_owned()),
-        ..Default::default()
+    let video = collection_item::Model {
+        id,
+        collection_id: collection.id,
+        seq: 1,
+        title: Some(command.title.clone()),
+        description: Some(command.description.clone()),
+        created_time: Local::now().naive_utc(),
+        is_public: i32::try_from(command.is_public).map_err(|_| ApiError::InvalidParameter)?,
+        author: account_id.to_string(),
+        content: None,
+        path: Some(path),
+        hash: Some(command.hash.clone()),
+        ipfs: None,
+        status: Some(1),
+        category: "video".to_owned(),
     };
-    let _ = collection_repository::create_collection_item(video).await?;
+    let _ = state.collection_repository.create_collection_item(video).await?;
     Ok(video_id)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use crate::{domain::{command::collection_command::SetCollectionTagsCommand, model::entity::collection}, error::ApiError, settings::AppConfig, state::{fakes::{self, InMemory}, AppState}};
+
+    use super::set_collection_tags;
+
+    fn command(collection_id: &Uuid, pub_key: &str) -> SetCollectionTagsCommand {
+        SetCollectionTagsCommand {
+            collection_id: collection_id.to_string(),
+            tags: vec![" #Rust".to_owned(), "rust".to_owned(), "Sui".to_owned()],
+            pub_key: pub_key.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_collection_tags_only_by_author() {
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        let author = fakes::account("author");
+        let collection_id = Uuid::new_v4();
+        store.collections.lock().unwrap().push(collection::Model { id: collection_id, is_public: 1, ..fakes::collection(author.id) });
+        store.accounts.lock().unwrap().extend([author, fakes::account("other")]);
+
+        let tags = set_collection_tags(&state, command(&collection_id, "author")).await.unwrap();
+        assert_eq!(tags, vec!["rust", "sui"]);
+        assert_eq!(store.tags.lock().unwrap().get(&collection_id), Some(&tags));
+
+        assert!(matches!(set_collection_tags(&state, command(&collection_id, "other")).await, Err(ApiError::CollectionNotFound)));
+        assert!(matches!(set_collection_tags(&state, command(&Uuid::new_v4(), "author")).await, Err(ApiError::CollectionNotFound)));
+        assert!(matches!(set_collection_tags(&state, command(&collection_id, "nobody")).await, Err(ApiError::UnknownAccount)));
+    }
+}
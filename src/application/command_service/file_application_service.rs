@@ -1,23 +1,23 @@
-use sea_orm::ActiveValue::Set;
-
-use crate::{domain::{command::file_command::AddFileCommand, model::entity::file_entity, repository::file_repository}, interface::rest::dto::file_entity::FileEntityDTO};
+use crate::{domain::{command::file_command::AddFileCommand, model::entity::file_entity}, error::ApiError, interface::rest::dto::file_entity::FileEntityDTO, state::AppState};
 
 /// 添加文件
-pub async fn add_file(command: AddFileCommand) -> Result<FileEntityDTO, anyhow::Error> {
+pub async fn add_file(state: &AppState, command: AddFileCommand) -> Result<FileEntityDTO, ApiError> {
     let uuid = uuid::Uuid::new_v4();
 
-    let file_entity = file_entity::ActiveModel {
-        id: Set(uuid),
-        name: Set(command.file_name.clone()),
-        mime: Set(command.mime.clone()),
+    let file_entity = file_entity::Model {
+        id: uuid,
+        item_id: None,
+        name: command.file_name.clone(),
+        mime: command.mime.clone(),
         // length: todo!(),
-        path: Set(Some(command.path.clone())),
-        hash: Set(command.hash),
+        length: None,
+        path: Some(command.path.clone()),
+        hash: command.hash,
         // ipfs: todo!(),
-        status: Set(Some(1)),
-        ..Default::default()
+        ipfs: None,
+        status: Some(1),
     };
-    let _ = file_repository::add_file(file_entity).await?;
+    let _ = state.file_repository.add_file(file_entity).await?;
 
     let dto = FileEntityDTO{
         id: uuid.to_string(),
@@ -29,4 +29,4 @@ pub async fn add_file(command: AddFileCommand) -> Result<FileEntityDTO, anyhow::
         url: Option::None,
     };
     Ok(dto)
-}
\ No newline at end of file
+}
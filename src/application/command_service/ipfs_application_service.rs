@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::{application::query_service::nft_metadata_query_service, domain::{model::entity::{collection, file_entity}, service::access_policy::{self, Access, Viewer}}, infrastructure::{image_util::image_type, storage::{ByteStream, Volume}}, state::AppState};
+
+/// supervisor中的任务名
+pub const TASK: &str = "ipfs_pinner";
+
+/// 固定到IPFS的内容
+enum Content {
+    // 存储中的文件
+    Stored(Volume, String),
+    // 数据库中的图文
+    Inline(Bytes),
+}
+
+/// 把已上架专辑的封面、图文、视频和NFT元数据固定到IPFS,记录CID
+/// 固定的内容可以从任意网关获取,需要持有NFT才能查看的专辑项只在ipfs.pin_gated_items时固定
+/// 已记录CID的内容检查是否仍被固定,未固定时从存储中重新添加,返回新记录的CID数量
+pub async fn pin_collection(state: &AppState, collection: &collection::Model) -> Result<usize, anyhow::Error> {
+    let mut recorded = 0;
+    // 封面icon_url为/{collection_id}/{file_name},上传时的文件记录路径为{file_name}
+    if let Some(icon_key) = collection.icon_url.as_deref().map(|icon_url| icon_url.trim_start_matches('/')) {
+        let file_name = icon_key.rsplit('/').next().unwrap_or(icon_key);
+        let file = state.file_repository.get_file_by_path(file_name).await?;
+        let pinned = file.as_ref().and_then(|file| file.ipfs.as_deref());
+        if let Some(cid) = pin(state, pinned, Content::Stored(Volume::Assets, icon_key.to_owned())).await? {
+            match file {
+                Some(file) => state.file_repository.set_file_ipfs(&file.id, &cid).await?,
+                // 没有上传记录时补充一条
+                None => state.file_repository.add_file(file_entity::Model {
+                    id: uuid::Uuid::new_v4(),
+                    item_id: Some(collection.id.to_string()),
+                    name: file_name.to_owned(),
+                    mime: file_name.rsplit_once('.').and_then(|(_, extension)| image_type(extension)).unwrap_or("application/octet-stream").to_owned(),
+                    length: None,
+                    path: Some(file_name.to_owned()),
+                    hash: None,
+                    ipfs: Some(cid),
+                    status: Some(1),
+                }).await?,
+            }
+            recorded += 1;
+        }
+    }
+    for item in state.collection_repository.get_items_by(&collection.id).await? {
+        if !state.config.ipfs.pin_gated_items && access_policy::item_access(&Viewer::anonymous(), collection, &item) != Access::Granted {
+            continue;
+        }
+        let content = match (item.category.as_str(), &item.path, &item.content) {
+            ("video", Some(path), _) => Content::Stored(Volume::Medias, path.clone()),
+            ("article", _, Some(content)) => Content::Inline(Bytes::from(content.clone())),
+            _ => continue,
+        };
+        if let Some(cid) = pin(state, item.ipfs.as_deref(), content).await? {
+            state.collection_repository.set_item_ipfs(&item.id, &cid).await?;
+            recorded += 1;
+        }
+    }
+    // 元数据引用封面的CID,内容可能变化,每次重新添加并比较CID
+    if let Some(nft) = state.bassinet_nft_repository.get_nft_by_collection_id(&collection.id).await? {
+        let metadata = nft_metadata_query_service::build_metadata(state, collection, &nft).await?;
+        let content = Content::Inline(Bytes::from(serde_json::to_vec(&metadata)?));
+        if let Some(cid) = pin(state, None, content).await?.filter(|cid| nft.metadata_ipfs.as_ref() != Some(cid)) {
+            state.bassinet_nft_repository.set_metadata_ipfs(&nft.id, &cid).await?;
+            recorded += 1;
+        }
+    }
+    Ok(recorded)
+}
+
+/// 内容仍被固定时返回None,否则重新添加并返回需要记录的CID
+async fn pin(state: &AppState, pinned: Option<&str>, content: Content) -> Result<Option<String>, anyhow::Error> {
+    if let Some(cid) = pinned {
+        if state.pinner.is_pinned(cid).await? {
+            return Ok(None)
+        }
+        tracing::warn!("{} is no longer pinned, adding it again", cid);
+    }
+    let body: ByteStream<'static> = match content {
+        Content::Stored(volume, key) => match state.storage.get(volume, &key, None).await? {
+            Some(object) => object.body,
+            None => {
+                tracing::warn!("cannot pin {}/{}: not found in storage", volume.name(), key);
+                return Ok(None)
+            }
+        },
+        Content::Inline(data) => stream::once(async { Ok(data) }).boxed(),
+    };
+    let cid = state.pinner.add(body).await?;
+    match pinned {
+        Some(pinned) if pinned == cid => Ok(None),
+        Some(pinned) => {
+            tracing::warn!("content pinned as {} now has CID {}", pinned, cid);
+            Ok(Some(cid))
+        }
+        None => Ok(Some(cid)),
+    }
+}
+
+/// 固定所有已上架专辑的内容,单个专辑失败时继续处理其余专辑
+pub async fn pin_listed_collections(state: &AppState) -> Result<(), anyhow::Error> {
+    for collection in state.collection_repository.get_listed_collections().await? {
+        match pin_collection(state, &collection).await {
+            Ok(0) => {}
+            Ok(recorded) => tracing::info!("pinned {} new objects of collection {}", recorded, collection.id),
+            Err(err) => tracing::error!("failed to pin collection {}: {:#}", collection.id, err),
+        }
+    }
+    Ok(())
+}
+
+/// 按ipfs.verify_interval_secs定期检查和补充固定,有专辑上架时立即处理,直到收到停止信号
+pub async fn verify_pins(state: AppState, shutdown: CancellationToken) -> Result<(), anyhow::Error> {
+    let interval = Duration::from_secs(state.config.ipfs.verify_interval_secs);
+    loop {
+        pin_listed_collections(&state).await?;
+        tokio::select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = state.pin_requests.notified() => {}
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use uuid::Uuid;
+
+    use crate::{domain::model::entity::{collection, collection_item, file_entity}, infrastructure::storage::Volume, settings::AppConfig, state::{fakes::{self, InMemory}, AppState}};
+
+    use super::pin_collection;
+
+    #[tokio::test]
+    async fn test_pin_collection() {
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        let mut collection = collection::Model { is_public: 1, listing: Some(1), ..fakes::collection(Uuid::new_v4()) };
+        let collection_id = collection.id;
+        collection.icon_url = Some(format!("/{}/cover.png", collection_id));
+        store.files.lock().unwrap().push(file_entity::Model {
+            id: Uuid::new_v4(),
+            item_id: None,
+            name: "cover.png".to_owned(),
+            mime: "image/png".to_owned(),
+            length: None,
+            path: Some("cover.png".to_owned()),
+            hash: None,
+            ipfs: None,
+            status: Some(1),
+        });
+        store.items.lock().unwrap().extend([
+            collection_item::Model { is_public: 1, content: Some("# papi".to_owned()), ..fakes::item(&collection) },
+            // 需要持有NFT才能查看
+            collection_item::Model { category: "video".to_owned(), path: Some(format!("{}/clip.mp4", collection_id)), ..fakes::item(&collection) },
+        ]);
+        let mut objects = store.objects.lock().unwrap();
+        objects.insert((Volume::Assets, format!("{}/cover.png", collection_id)), Bytes::from_static(b"png"));
+        objects.insert((Volume::Medias, format!("{}/clip.mp4", collection_id)), Bytes::from_static(b"mp4"));
+        drop(objects);
+
+        store.nfts.lock().unwrap().push(fakes::nft(collection_id));
+
+        assert_eq!(pin_collection(&state, &collection).await.unwrap(), 3);
+        assert!(store.files.lock().unwrap()[0].ipfs.is_some());
+        assert!(store.items.lock().unwrap()[0].ipfs.is_some());
+        assert_eq!(store.items.lock().unwrap()[1].ipfs, None);
+        assert_eq!(store.pins.lock().unwrap().len(), 3);
+        // 元数据中的封面使用IPFS地址
+        let metadata_cid = store.nfts.lock().unwrap()[0].metadata_ipfs.clone().unwrap();
+        let metadata: serde_json::Value = serde_json::from_slice(&store.pins.lock().unwrap()[&metadata_cid]).unwrap();
+        assert_eq!(metadata["image"], format!("ipfs://{}", store.files.lock().unwrap()[0].ipfs.as_deref().unwrap()));
+        // 已固定的内容不再添加
+        assert_eq!(pin_collection(&state, &collection).await.unwrap(), 0);
+
+        // 节点上丢失的内容重新添加,CID不变
+        let article_cid = store.items.lock().unwrap()[0].ipfs.clone().unwrap();
+        store.pins.lock().unwrap().remove(&article_cid);
+        assert_eq!(pin_collection(&state, &collection).await.unwrap(), 0);
+        assert!(store.pins.lock().unwrap().contains_key(&article_cid));
+
+        // 配置后固定需要持有NFT的专辑项
+        let mut config = AppConfig::for_test();
+        config.ipfs.pin_gated_items = true;
+        let state = AppState::in_memory(config, store.clone());
+        assert_eq!(pin_collection(&state, &collection).await.unwrap(), 1);
+        assert!(store.items.lock().unwrap()[1].ipfs.is_some());
+    }
+}
@@ -2,4 +2,6 @@ pub(crate) mod account_application_service;
 pub(crate) mod collection_application_service;
 pub(crate) mod file_application_service;
 pub(crate) mod sui_application_service;
-pub(crate) mod chunk_list_application_service;
\ No newline at end of file
+pub(crate) mod chunk_list_application_service;
+pub(crate) mod ipfs_application_service;
+pub(crate) mod nft_index_application_service;
\ No newline at end of file
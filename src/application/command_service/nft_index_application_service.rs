@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{domain::{model::{entity::bassinet_nft, valueobject::nft_event::EventStream}, service::nft_ownership}, state::AppState};
+
+/// supervisor中的任务名
+pub const TASK: &str = "nft_indexer";
+
+/// 按游标依次读取package的各类事件直到最新,更新NFT持有者,返回更新的NFT数量
+/// 每页事件和游标在同一个事务中保存,中断后从上次的游标继续;事件先后无法确定的NFT再通过RPC确认持有者
+pub async fn index_package(state: &AppState, nft: &bassinet_nft::Model) -> Result<usize, anyhow::Error> {
+    let mut updated = 0;
+    for stream in EventStream::ALL {
+        let mut cursor = state.nft_token_repository.get_event_cursor(&nft.package_id, stream).await?;
+        loop {
+            let page = state.nft_events.query_nft_events(&nft.package_id, stream, cursor.clone(), state.config.indexer.page_size).await?;
+            let Some(next_cursor) = page.next_cursor.filter(|next_cursor| cursor.as_ref() != Some(next_cursor)) else {
+                break
+            };
+            updated += state.nft_token_repository.apply_nft_events(&nft.package_id, &nft.collection_id, stream, &page.events, &next_cursor).await?;
+            cursor = Some(next_cursor);
+            if !page.has_next_page {
+                break
+            }
+        }
+    }
+    for token in state.nft_token_repository.get_unresolved_tokens(&nft.package_id).await? {
+        let owner = state.nft_query.get_nft_owner(&token.object_id).await?;
+        state.nft_token_repository.update_token(nft_ownership::resolve(token, owner)).await?;
+    }
+    Ok(updated)
+}
+
+/// 索引所有已发布的NFT,单个package失败时继续处理其余package
+pub async fn index_all(state: &AppState) -> Result<(), anyhow::Error> {
+    for nft in state.bassinet_nft_repository.get_all_nfts().await? {
+        match index_package(state, &nft).await {
+            Ok(0) => {}
+            Ok(updated) => tracing::info!("indexed {} tokens of package {}", updated, nft.package_id),
+            Err(err) => tracing::error!("failed to index package {}: {:#}", nft.package_id, err),
+        }
+    }
+    Ok(())
+}
+
+/// 按indexer.poll_interval_secs轮询新事件,直到收到停止信号
+pub async fn run_indexer(state: AppState, shutdown: CancellationToken) -> Result<(), anyhow::Error> {
+    let interval = Duration::from_secs(state.config.indexer.poll_interval_secs);
+    loop {
+        index_all(&state).await?;
+        tokio::select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::Ordering, Arc};
+
+    use uuid::Uuid;
+
+    use crate::{domain::{model::{entity::bassinet_nft, valueobject::nft_event::{EventCursor, EventStream, NftEvent, NftEventKind}}, repository::nft_token_repository::NftTokenRepository}, infrastructure::sui::nft_events::{self, tests::{sample_events, ALICE, BOB, CAROL, PACKAGE_ID}}, settings::AppConfig, state::{fakes::{self, InMemory}, AppState}};
+
+    use super::index_package;
+
+    fn nft(collection_id: Uuid) -> bassinet_nft::Model {
+        bassinet_nft::Model { package_id: PACKAGE_ID.to_owned(), ..fakes::nft(collection_id) }
+    }
+
+    #[tokio::test]
+    async fn test_index_sample_events() {
+        let store = Arc::new(InMemory::default());
+        let mut config = AppConfig::for_test();
+        config.indexer.page_size = 1;
+        let state = AppState::in_memory(config, store.clone());
+        let nft = nft(Uuid::new_v4());
+        // 样例事件按各自的来源返回
+        store.nft_events.lock().unwrap().extend(sample_events().iter().map(|event| {
+            let event = nft_events::decode(event).unwrap();
+            let stream = match event.kind {
+                NftEventKind::Listed { .. } => EventStream::KioskListed,
+                NftEventKind::Delisted { .. } => EventStream::KioskDelisted,
+                NftEventKind::Purchased { .. } => EventStream::KioskPurchased,
+                _ => EventStream::Module,
+            };
+            (PACKAGE_ID.to_owned(), stream, event)
+        }));
+
+        assert!(index_package(&state, &nft).await.unwrap() > 0);
+        let tokens = store.tokens.lock().unwrap().clone();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!((tokens[0].owner.as_deref(), tokens[0].minted_by.as_deref(), tokens[0].status), (Some(BOB), Some(ALICE), 1));
+        assert_eq!((tokens[1].owner.as_deref(), tokens[1].status), (None, 0));
+        assert!(store.owns_token(BOB, PACKAGE_ID).await.unwrap());
+        assert!(!store.owns_token(ALICE, PACKAGE_ID).await.unwrap());
+        assert!(!store.owns_token(CAROL, PACKAGE_ID).await.unwrap());
+        assert_eq!(store.event_cursors.lock().unwrap().len(), 3);
+
+        // 从游标继续,没有新事件时不再更新
+        assert_eq!(index_package(&state, &nft).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_owner_of_same_checkpoint() {
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        let nft = nft(Uuid::new_v4());
+        let event = |tx_digest: &str, kind| NftEvent {
+            id: EventCursor { tx_digest: tx_digest.to_owned(), event_seq: 0 },
+            object_id: "0xa1".to_owned(),
+            kind,
+            timestamp_ms: 1_000,
+        };
+        // 同一checkpoint中BOB购买后转移给CAROL,按来源分别索引时无法判断先后,按索引顺序持有者为BOB
+        store.nft_events.lock().unwrap().extend([
+            (PACKAGE_ID.to_owned(), EventStream::Module, event("tx1", NftEventKind::Transferred { to: CAROL.to_owned() })),
+            (PACKAGE_ID.to_owned(), EventStream::KioskPurchased, event("tx2", NftEventKind::Purchased { buyer: BOB.to_owned() })),
+        ]);
+        store.unavailable.store(true, Ordering::SeqCst);
+        assert!(index_package(&state, &nft).await.is_err());
+        let token = store.tokens.lock().unwrap()[0].clone();
+        assert_eq!((token.owner.as_deref(), token.owner_unresolved), (Some(BOB), 1));
+
+        // RPC恢复后以链上对象的持有者为准
+        store.unavailable.store(false, Ordering::SeqCst);
+        store.nft_owners.lock().unwrap().insert("0xa1".to_owned(), CAROL.to_owned());
+        index_package(&state, &nft).await.unwrap();
+        let token = store.tokens.lock().unwrap()[0].clone();
+        assert_eq!((token.owner.as_deref(), token.owner_unresolved), (Some(CAROL), 0));
+    }
+}
@@ -1,65 +1,126 @@
-use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use crate::{application::query_service::nft_metadata_query_service, domain::model::entity::{bassinet_coin, bassinet_nft}, error::ApiError, infrastructure::messaging::{coin_published_consumer::CoinPublishedMessage, nft_published_consumer::NftPublishedMessage}, state::AppState};
 
-use crate::{domain::{model::entity::{account, bassinet_coin, bassinet_nft, collection}, repository::{account_repository, bassinet_coin_repository, collection_repository}}, infrastructure::{database_connection::{self, get_db}, messaging::{coin_published_consumer::CoinPublishedMessage, nft_published_consumer::NftPublishedMessage}}};
-
-pub async fn add_bassinet_coin(coin_info: &CoinPublishedMessage) -> Result<(), anyhow::Error> {
-    let accounts = account_repository::find_by_pubkey(&coin_info.account).await;
-    let account = accounts.get(0);
-    let account_id = account.unwrap().id;
+pub async fn add_bassinet_coin(state: &AppState, coin_info: &CoinPublishedMessage) -> Result<(), ApiError> {
+    let account = state.account_repository.get_account_by(&coin_info.account).await?.ok_or(ApiError::UnknownAccount)?;
+    let account_id = account.id;
     let id = uuid::Uuid::new_v4();
-    let bassinet_coin = bassinet_coin::ActiveModel {
-        id: Set(id),
-        package_id: Set(coin_info.package_id.clone()),
-        symbol: Set(coin_info.symbol.clone()),
-        name: Set(coin_info.name.clone()),
-        description: Set(Some(coin_info.description.clone())),
-        icon_url: Set(Some(coin_info.icon_url.clone())),
-        treasury_lock_id: Set(coin_info.treasury_lock_id.clone()),
-        admin_cap_id: Set(coin_info.admin_cap_id.clone()),
-        account_id: Set(Some(account_id.to_string())),
+    let bassinet_coin = bassinet_coin::Model {
+        id,
+        package_id: coin_info.package_id.clone(),
+        symbol: coin_info.symbol.clone(),
+        name: coin_info.name.clone(),
+        description: Some(coin_info.description.clone()),
+        icon_url: Some(coin_info.icon_url.clone()),
+        treasury_lock_id: coin_info.treasury_lock_id.clone(),
+        admin_cap_id: coin_info.admin_cap_id.clone(),
+        account_id: Some(account_id),
     };
-    bassinet_coin.insert(database_connection::get_db().as_ref()).await?;
+    state.bassinet_coin_repository.add_coin(bassinet_coin).await?;
     Ok(())
 }
 
 /// 确认绑定钱包
-pub async fn confirm_account_bound(pub_key: &String, wallet_address: String) {
-    let accounts = account_repository::find_by_pubkey(pub_key).await;
-    if accounts.len() > 0 {
-        let account = accounts.get(0).unwrap().clone();
-        let mut sui_account:account::ActiveModel = account.into();
-        sui_account.wallet_address = Set(Some(wallet_address));
-        sui_account.update(get_db().as_ref()).await.expect("Database error");
-    }
+pub async fn confirm_account_bound(state: &AppState, pub_key: &String, wallet_address: String) -> Result<(), ApiError> {
+    let account = state.account_repository.get_account_by(pub_key).await?.ok_or(ApiError::UnknownAccount)?;
+    state.account_repository.bind_wallet(&account.id, wallet_address).await?;
+    Ok(())
 }
 
-pub async fn add_bassinet_nft(nft_info: &NftPublishedMessage) -> Result<(), anyhow::Error> {
-    let coin = bassinet_coin_repository::get_coin_by_package_id(&nft_info.coin_package_id).await;
+/// NFT已发布,消息中没有collection_url时使用本服务的元数据地址
+pub async fn add_bassinet_nft(state: &AppState, nft_info: &NftPublishedMessage) -> Result<(), ApiError> {
+    let collection_id = uuid::Uuid::parse_str(&nft_info.collection_id).map_err(|_| ApiError::CollectionNotFound)?;
+    let coin = state.bassinet_coin_repository.get_coin_by_package_id(&nft_info.coin_package_id).await?
+    .ok_or_else(|| anyhow::anyhow!("未知代币: {}", nft_info.coin_package_id))?;
     let id = uuid::Uuid::new_v4();
-    let bassinet_nft = bassinet_nft::ActiveModel {
-        id: Set(id),
-        package_id: Set(nft_info.package_id.clone()),
-        collection_id: Set(nft_info.collection_id.clone()),
-        description: Set(Some(nft_info.description.clone())),
-        collection_url: Set(Some(nft_info.collection_url.clone())),
-        limit: Set(Some(nft_info.limit.try_into().unwrap())),
-        minting_price: Set(nft_info.minting_price.try_into().unwrap()),
-        rewards_quantity: Set(Some(nft_info.rewards_quantity.try_into().unwrap())),
-        mint_id: Set(Some(nft_info.mint_id.clone())),
-        policy_id: Set(Some(nft_info.policy_id.clone())),
-        policy_cap_id: Set(Some(nft_info.policy_cap_id.clone().clone())),
-        coin_id: Set(Some(coin.unwrap().id.to_string())),
-        coin_package_id: Set(Some(nft_info.coin_package_id.clone())),
-        coin_treasury_lock_id: Set(Some(nft_info.treasury_lock_id.clone())),
-        coin_admin_cap_id: Set(Some(nft_info.admin_cap_id.clone())),
+    let bassinet_nft = bassinet_nft::Model {
+        id,
+        package_id: nft_info.package_id.clone(),
+        collection_id,
+        description: Some(nft_info.description.clone()),
+        collection_url: Some(match nft_info.collection_url.as_str() {
+            "" => nft_metadata_query_service::metadata_url(&state.config.nft, &collection_id),
+            collection_url => collection_url.to_owned(),
+        }),
+        limit: Some(nft_info.limit.try_into().map_err(|_| ApiError::InvalidParameter)?),
+        minting_price: nft_info.minting_price.try_into().map_err(|_| ApiError::InvalidParameter)?,
+        rewards_quantity: Some(nft_info.rewards_quantity.try_into().map_err(|_| ApiError::InvalidParameter)?),
+        mint_id: Some(nft_info.mint_id.clone()),
+        policy_id: Some(nft_info.policy_id.clone()),
+        policy_cap_id: Some(nft_info.policy_cap_id.clone()),
+        coin_id: Some(coin.id.to_string()),
+        coin_package_id: Some(nft_info.coin_package_id.clone()),
+        coin_treasury_lock_id: Some(nft_info.treasury_lock_id.clone()),
+        coin_admin_cap_id: Some(nft_info.admin_cap_id.clone()),
+        metadata_ipfs: None,
     };
-    bassinet_nft.insert(database_connection::get_db().as_ref()).await?;
+    state.bassinet_nft_repository.add_nft(bassinet_nft).await?;
+    state.collection_repository.mark_listed(&collection_id).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use crate::{domain::model::entity::bassinet_coin, error::ApiError, infrastructure::messaging::nft_published_consumer::NftPublishedMessage, settings::AppConfig, state::{fakes::{self, InMemory}, AppState}};
+
+    use super::{add_bassinet_nft, confirm_account_bound};
 
-    let collection = collection_repository::get_by_id(&nft_info.collection_id).await;
-    if collection.is_some() {
-        let mut updated : collection::ActiveModel = collection.unwrap().into();
-        updated.listing = Set(Some(1));
-        let _ = updated.update(get_db().as_ref()).await;
+    fn nft_message(collection_id: &str) -> NftPublishedMessage {
+        NftPublishedMessage {
+            collection_id: collection_id.to_owned(),
+            package_id: "0xnft".to_owned(),
+            mint_id: String::new(),
+            policy_id: String::new(),
+            policy_cap_id: String::new(),
+            coin_package_id: "0xcoin".to_owned(),
+            treasury_lock_id: String::new(),
+            admin_cap_id: String::new(),
+            description: String::new(),
+            collection_url: String::new(),
+            limit: 100,
+            rewards_quantity: 10,
+            minting_price: 1_000,
+        }
     }
-    Ok(())
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_confirm_account_bound() {
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        store.accounts.lock().unwrap().push(fakes::account("dd27"));
+
+        confirm_account_bound(&state, &"dd27".to_owned(), "0x87e4".to_owned()).await.unwrap();
+        assert_eq!(store.accounts.lock().unwrap()[0].wallet_address.as_deref(), Some("0x87e4"));
+        assert!(matches!(confirm_account_bound(&state, &"ffff".to_owned(), "0x87e4".to_owned()).await, Err(ApiError::UnknownAccount)));
+    }
+
+    #[tokio::test]
+    async fn test_add_bassinet_nft_lists_collection() {
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        let collection = fakes::collection(Uuid::new_v4());
+        let collection_id = collection.id;
+        store.collections.lock().unwrap().push(collection);
+        store.coins.lock().unwrap().push(bassinet_coin::Model {
+            id: Uuid::new_v4(),
+            package_id: "0xcoin".to_owned(),
+            symbol: "PAPI".to_owned(),
+            name: "papi".to_owned(),
+            description: None,
+            icon_url: None,
+            treasury_lock_id: String::new(),
+            admin_cap_id: String::new(),
+            account_id: None,
+        });
+
+        add_bassinet_nft(&state, &nft_message(&collection_id.to_string())).await.unwrap();
+        assert_eq!(store.nfts.lock().unwrap().len(), 1);
+        assert_eq!(store.collections.lock().unwrap()[0].listing, Some(1));
+        assert_eq!(store.nfts.lock().unwrap()[0].collection_url, Some(format!("http://localhost:6142/api/v1/collections/{}/metadata", collection_id)));
+
+        assert!(matches!(add_bassinet_nft(&state, &nft_message("not-a-uuid")).await, Err(ApiError::CollectionNotFound)));
+    }
+}
@@ -1,49 +1,38 @@
-use anyhow::Ok;
-use sea_orm::EntityTrait;
-use std::{collections::HashSet, str::FromStr};
-
-use crate::{domain::{model::entity::prelude::Account, repository::{account_repository, bassinet_coin_repository, collection_repository}}, infrastructure::database_connection::get_db, interface::rest::dto::account::AccountInfo};
+use crate::{domain::service::access_policy::Viewer, error::ApiError, interface::rest::dto::account::AccountInfo, state::AppState};
 
 /// 获取账户信息
-pub async fn get_account_info(pub_key: &String) -> Result<AccountInfo, anyhow::Error> {
-    let account = account_repository::get_account_by(pub_key).await?;
-    let account_id = account.id.to_string();
-
-    let coin = bassinet_coin_repository::get_coin_by_account_id(&account_id).await?;
+pub async fn get_account_info(state: &AppState, pub_key: &String) -> Result<AccountInfo, ApiError> {
+    let account = state.account_repository.get_account_by(pub_key).await?.ok_or(ApiError::UnknownAccount)?;
+    let coin = state.bassinet_coin_repository.get_coin_by_account_id(&account.id).await?;
 
     Ok(AccountInfo {
-        account_id: account_id,
-        nick_name: account.nick_name.unwrap(),
+        account_id: account.id.to_string(),
+        nick_name: account.nick_name.unwrap_or_default(),
         avatar: account.avatar,
         wallet_address: account.wallet_address,
-        package_id: if coin.is_some() {Some(coin.unwrap().package_id)} else {None},
+        package_id: coin.map(|coin| coin.package_id),
     })
 }
 
-/// 获取所有有专辑的账户,测试用
-pub async fn get_authors() -> Result<Vec<AccountInfo>, anyhow::Error> {
-    let collections = collection_repository::get_all_collections().await?;
-    if collections.is_empty() {
-        return Ok(Vec::new())
-    }
-    let mut author_ids = HashSet::new();
-    for item in collections.iter() {
-        author_ids.insert(item.author.clone());
+/// 获取访问者,未登录或未知账户为匿名访问者
+pub async fn get_viewer(state: &AppState, pub_key: Option<&String>) -> Viewer {
+    let Some(pub_key) = pub_key else {
+        return Viewer::anonymous()
+    };
+    match state.account_repository.get_account_by(pub_key).await {
+        Ok(Some(account)) => Viewer::new(account.id.to_string(), account.wallet_address),
+        _ => Viewer::anonymous(),
     }
-    let mut results = Vec::new();
-    for author_id in author_ids.iter() {
-        let author = Account::find_by_id(uuid::Uuid::from_str(author_id).unwrap()).one(get_db().as_ref()).await?;
-        if author.is_some() {
-            let author = author.unwrap();
-            results.push(AccountInfo {
-                account_id: author.id.to_string(),
-                nick_name: author.nick_name.unwrap(),
-                avatar: author.avatar,
-                wallet_address: None,
-                package_id: None
-            });
-        }
-    }
-
-    Ok(results)
 }
+
+/// 获取所有有专辑的账户,测试用
+pub async fn get_authors(state: &AppState) -> Result<Vec<AccountInfo>, ApiError> {
+    let authors = state.account_repository.get_authors().await?;
+    Ok(authors.into_iter().map(|author| AccountInfo {
+        account_id: author.id.to_string(),
+        nick_name: author.nick_name.unwrap_or_default(),
+        avatar: author.avatar,
+        wallet_address: None,
+        package_id: None
+    }).collect())
+}
\ No newline at end of file
@@ -1,11 +1,8 @@
-use crate::{domain::repository::chunk_list_repository, interface::rest::dto::media::ChunkListDTO};
+use crate::{error::ApiError, interface::rest::dto::media::ChunkListDTO, state::AppState};
 
 /// 获取指定md5的所有分片
-pub async fn query_chunk_list(md5: &String) -> Vec<ChunkListDTO> {
-    let chunks = chunk_list_repository::query_chunk_list(md5).await;
-    if chunks.is_empty() {
-        return Vec::new()
-    }
+pub async fn query_chunk_list(state: &AppState, md5: &String) -> Result<Vec<ChunkListDTO>, ApiError> {
+    let chunks = state.chunk_list_repository.query_chunk_list(md5).await?;
     let mut results = Vec::new();
     for chunk in chunks {
         results.push(ChunkListDTO {
@@ -16,5 +13,5 @@ pub async fn query_chunk_list(md5: &String) -> Vec<ChunkListDTO> {
             total_chunks: chunk.total_chunks,
         });
     }
-    results
+    Ok(results)
 }
\ No newline at end of file
@@ -1,285 +1,246 @@
+use uuid::Uuid;
 
-use anyhow::Ok;
-use sea_orm::{ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use crate::{domain::{model::{entity::{bassinet_nft, collection}, valueobject::page::{PageRequest, PageResult}}, repository::search_repository::{self, CollectionSearchCriteria}, service::{access_policy::{self, Access, Viewer}, article_preview}}, error::ApiError, interface::rest::dto::collection::{ArticleInfoDTO, CollectionInfoDTO, CollectionItemInfoDTO, CollectionPageDTO, CollectionSimpleInfoDTO, FacetCount, IpfsLinkDTO, NftInfo, SearchFacets, SearchHighlight}, state::AppState};
 
-use crate::{domain::{model::entity::collection, repository::{bassinet_nft_repository, collection_repository::{self}}}, infrastructure::database_connection, interface::rest::dto::{account::AccountInfo, collection::{ArticleInfoDTO, CollectionInfoDTO, CollectionItemInfoDTO, CollectionPageDTO, CollectionSimpleInfoDTO, NftInfo}}};
-
-use super::media_query_service;
+use super::{media_query_service, nft_metadata_query_service};
 
 /// 专辑详情(公开专辑)
-pub async fn get_collection_by_id(collection_id: &String, assets_web_addr: &String, medias_web_addr: &String) -> Result<CollectionInfoDTO, anyhow::Error> {
-    let collection = collection_repository::get_by_id(collection_id).await;
-    if collection.is_none() {
-        anyhow::bail!("未知专辑");
-    }
-    let collection = collection.unwrap();
-    if collection.is_public != 1 && collection.listing.unwrap() != 1 {
-        anyhow::bail!("未知专辑");
-    }
-    let items = collection_repository::get_items_by(collection_id).await;
-    if items.is_err() {
-        return Err(items.err().unwrap());
+/// 不可见的专辑项不返回,需持有NFT的专辑项在未持有时只返回图文试读内容
+pub async fn get_collection_by_id(state: &AppState, collection_id: &String, viewer: &Viewer, assets_web_addr: &String, medias_web_addr: &String) -> Result<CollectionInfoDTO, ApiError> {
+    let collection = state.collection_repository.get_by_id(collection_id).await?.ok_or(ApiError::CollectionNotFound)?;
+    if access_policy::collection_access(viewer, &collection) == Access::Denied {
+        return Err(ApiError::CollectionNotFound);
     }
-    let dtos = items.unwrap().into_iter().map(|item|{
-        CollectionItemInfoDTO{
+    let items = state.collection_repository.get_items_by(&collection.id).await?;
+    let mut owns_nft = Option::None;
+    let mut dtos = Vec::new();
+    for item in items.into_iter() {
+        let access = access_policy::item_access(viewer, &collection, &item);
+        if access == Access::Denied {
+            continue;
+        }
+        if access == Access::NftRequired && owns_nft.is_none() {
+            owns_nft = Some(access_policy::owns_collection_nft(state, viewer, &collection).await);
+        }
+        let locked = !access_policy::is_permitted(access, owns_nft.unwrap_or(false));
+        dtos.push(CollectionItemInfoDTO{
             id: item.id.to_string(),
-            title: item.title.unwrap(),
-            collection_id: item.collection_id,
-            description: if item.description.is_none() {"".to_owned()} else { item.description.unwrap()},
-            content: if item.content.is_none() {"".to_owned()} else {item.content.unwrap()},
+            title: item.title.unwrap_or_default(),
+            collection_id: item.collection_id.to_string(),
+            description: item.description.unwrap_or_default(),
+            content: locked_content(item.content, locked),
             category: item.category,
-            url_path: if item.path.is_none() {"".to_owned()} else {format!("{}/{}", medias_web_addr, item.path.unwrap())},
+            url_path: match item.path {
+                Some(path) if !locked => format!("{}/{}", medias_web_addr, path),
+                _ => "".to_owned(),
+            },
             content_type: "".to_owned(),
             created_time: item.created_time.and_utc().timestamp() as u64,
-        }
-    }).collect();
-
-    let nft = bassinet_nft_repository::get_nft_by_collection_id(&collection_id).await;
-    let mut nft_dto = Option::None;
-    if nft.is_some() {
-        let nft = nft.unwrap();
-        nft_dto = Some(NftInfo {
-            id: nft.id.to_string(),
-            package_id: nft.package_id,
-            collection_url: nft.collection_url.unwrap(),
-            limit: nft.limit.unwrap() as u64,
-            minting_price: nft.minting_price as u64,
-            rewards_quantity: nft.rewards_quantity.unwrap() as u64,
-            mint_id: nft.mint_id.unwrap(),
-            policy_id: nft.policy_id.unwrap(),
-            policy_cap_id: nft.policy_cap_id.unwrap(),
-            coin_id: nft.coin_id.unwrap(),
-            coin_package_id: nft.coin_package_id.unwrap(),
-            coin_treasury_lock_id: nft.coin_treasury_lock_id.unwrap(),
-            coin_admin_cap_id: nft.coin_admin_cap_id.unwrap()
+            locked: locked,
+            ipfs: item.ipfs.filter(|_| !locked).map(|cid| IpfsLinkDTO::new(cid, &state.config.ipfs.gateway_url)),
         });
     }
 
+    let nft_dto = state.bassinet_nft_repository.get_nft_by_collection_id(&collection.id).await?.map(to_nft_info);
+
+    let tags = state.tag_repository.get_tags_by_collection(&collection.id).await?;
+    let icon_ipfs = icon_ipfs(state, collection.icon_url.as_deref()).await?;
+
     Ok(CollectionInfoDTO {
         id: collection_id.clone(),
         title: collection.title,
         description: collection.description,
         is_public: collection.is_public as u8,
-        listing: collection.listing.unwrap() as u8,
+        listing: collection.listing.unwrap_or(0) as u8,
         created_time: collection.created_time.and_utc().timestamp() as u64,
-        icon_url: Some(assets_web_addr.clone() + &collection.icon_url.unwrap()),
+        icon_url: Some(assets_web_addr.clone() + &collection.icon_url.unwrap_or_default()),
+        icon_ipfs,
         nft: nft_dto,
+        category_id: collection.category_id.map(|category_id| category_id.to_string()),
+        tags: tags,
         items: dtos
         // articles: article_dtos,
     })
 }
 
 /// 某创作者的专辑分页查询(公开的)
-pub async fn get_author_collections(author_id: String, page: u64, limit: u64, assets_path: &String) -> (Vec<CollectionPageDTO>, u64) {
-    let db = database_connection::get_db();
-    let collection_pages = collection::Entity::find()
-    .filter(
-        Condition::any()
-                .add(collection::Column::IsPublic.eq(1))
-                .add(collection::Column::Listing.eq(1))
-    )
-    .filter(collection::Column::Author.eq(author_id))
-    .order_by_desc(collection::Column::CreatedTime)
-    .paginate(db.as_ref(), limit);
-    
-    let collections = collection_pages.fetch_page(page - 1).await.unwrap();
-    if collections.is_empty() {
-        return (vec![], 0)
-    }
-    let total = collection_pages.num_items().await.unwrap();
-    let mut values = Vec::new();
-    for item in collections.into_iter() {
-        let nft = bassinet_nft_repository::get_nft_by_collection_id(&item.id.to_string()).await;
-        let mut nft_dto = Option::None;
-        if nft.is_some() {
-            let nft = nft.unwrap();
-            nft_dto = Some(NftInfo {
-                id: nft.id.to_string(),
-                package_id: nft.package_id,
-                collection_url: nft.collection_url.unwrap(),
-                limit: nft.limit.unwrap() as u64,
-                minting_price: nft.minting_price as u64,
-                rewards_quantity: nft.rewards_quantity.unwrap() as u64,
-                mint_id: nft.mint_id.unwrap(),
-                policy_id: nft.policy_id.unwrap(),
-                policy_cap_id: nft.policy_cap_id.unwrap(),
-                coin_id: nft.coin_id.unwrap(),
-                coin_package_id: nft.coin_package_id.unwrap(),
-                coin_treasury_lock_id: nft.coin_treasury_lock_id.unwrap(),
-                coin_admin_cap_id: nft.coin_admin_cap_id.unwrap()
-            });
-        }
-        values.push(
-            CollectionPageDTO{
-                id: item.id.to_string(),
-                title: item.title,
-                description: item.description,
-                is_public: item.is_public as u8,
-                listing: item.listing.unwrap() as u8,
-                created_time: item.created_time.and_utc().timestamp() as u64,
-                icon_url: Some(assets_path.clone() + &item.icon_url.unwrap()),
-                nft: nft_dto
-        });
-    }
-    (values, total)
+pub async fn get_author_collections(state: &AppState, author_id: Uuid, request: &PageRequest, assets_path: &String) -> Result<PageResult<CollectionPageDTO>, ApiError> {
+    let mut page = state.collection_repository.page_collections(&author_id, true, request).await?;
+    let values = to_page_dtos(state, std::mem::take(&mut page.items), assets_path).await?;
+    Ok(page.with_items(values))
 }
 
 /// 条件搜索专辑,分页查询(公开的)
-pub async fn search_collections(keyword: Option<String>, author: Option<String>, page: u64, limit: u64, assets_path: &String) -> (Vec<CollectionPageDTO>, u64) {
-    let db = database_connection::get_db();
+/// 有关键字时按相关度排序,并返回高亮片段
+pub async fn search_collections(state: &AppState, criteria: &CollectionSearchCriteria, request: &PageRequest, assets_path: &String) -> Result<PageResult<CollectionPageDTO>, ApiError> {
+    let mut page = state.search_repository.search_collections(criteria, request).await?;
+    let has_keyword = criteria.keyword.as_ref().is_some_and(|keyword| !keyword.trim().is_empty());
+    let rows = std::mem::take(&mut page.items);
+    let collection_ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+    let mut nfts = state.bassinet_nft_repository.get_nft_by_collection_ids(&collection_ids).await?;
+    let values = rows.into_iter().map(|item| {
+        let highlight = if has_keyword {
+            Some(SearchHighlight {
+                rank: item.rank,
+                title: item.title_highlight,
+                snippet: item.snippet,
+                item_id: item.item_id.map(|item_id| item_id.to_string()),
+            })
+        } else {
+            None
+        };
+        CollectionPageDTO{
+            id: item.id.to_string(),
+            title: item.title,
+            description: item.description,
+            is_public: item.is_public as u8,
+            listing: item.listing.unwrap_or(0) as u8,
+            created_time: item.created_time.and_utc().timestamp() as u64,
+            icon_url: Some(assets_path.clone() + &item.icon_url.unwrap_or_default()),
+            nft: nfts.remove(&item.id).map(to_nft_info),
+            highlight: highlight,
+        }
+    }).collect();
+    Ok(page.with_items(values))
+}
+
+/// 专辑列表项,NFT信息一次批量查询
+pub async fn to_page_dtos(state: &AppState, collections: Vec<collection::Model>, assets_path: &String) -> Result<Vec<CollectionPageDTO>, ApiError> {
+    let collection_ids: Vec<Uuid> = collections.iter().map(|collection| collection.id).collect();
+    let mut nfts = state.bassinet_nft_repository.get_nft_by_collection_ids(&collection_ids).await?;
+    Ok(collections.into_iter().map(|item| CollectionPageDTO{
+        id: item.id.to_string(),
+        nft: nfts.remove(&item.id).map(to_nft_info),
+        title: item.title,
+        description: item.description,
+        is_public: item.is_public as u8,
+        listing: item.listing.unwrap_or(0) as u8,
+        created_time: item.created_time.and_utc().timestamp() as u64,
+        icon_url: Some(assets_path.clone() + &item.icon_url.unwrap_or_default()),
+        highlight: None,
+    }).collect())
+}
 
-    let collection_pages = if keyword.is_some() && author.is_some() {
-        let mut like_condition = String::new();
-        like_condition.push_str("%");
-        like_condition.push_str(keyword.unwrap().as_str());
-        like_condition.push_str("%");
-        collection::Entity::find()
-        .filter(
-            Condition::all().add(
-                Condition::any()
-                .add(collection::Column::IsPublic.eq(1))
-                .add(collection::Column::Listing.eq(1))
-            ).add(collection::Column::Title.like(like_condition))
-            .add(collection::Column::Author.eq(author.unwrap()))   
-        )
-        .order_by_desc(collection::Column::CreatedTime)
-        .paginate(db.as_ref(), limit)
-    }else if keyword.is_some() {
-        let mut like_condition = String::new();
-        like_condition.push_str("%");
-        like_condition.push_str(keyword.unwrap().as_str());
-        like_condition.push_str("%");
-        collection::Entity::find()
-        .filter(
-            Condition::all().add(
-                Condition::any()
-            .add(collection::Column::IsPublic.eq(1))
-            .add(collection::Column::Listing.eq(1))
-            ).add(collection::Column::Title.like(like_condition))
-        )
-        .order_by_desc(collection::Column::CreatedTime)
-        .paginate(db.as_ref(), limit)
-    }else if author.is_some() {
-        collection::Entity::find()
-        .filter(
-            Condition::all()
-            .add(Condition::any().add(collection::Column::IsPublic.eq(1)).add(collection::Column::Listing.eq(1)))
-            .add(collection::Column::Author.eq(author.unwrap()))
-        )
-        .order_by_desc(collection::Column::CreatedTime)
-        .paginate(db.as_ref(), limit)
-    }else {
-        collection::Entity::find()
-        .filter(Condition::any().add(collection::Column::IsPublic.eq(1)).add(collection::Column::Listing.eq(1)))
-        .order_by_desc(collection::Column::CreatedTime)
-        .paginate(db.as_ref(), limit)
+/// 搜索结果的分类及标签统计
+pub async fn search_facets(state: &AppState, criteria: &CollectionSearchCriteria) -> Result<SearchFacets, ApiError> {
+    let (categories, tags) = state.search_repository.search_facets(criteria, 20).await?;
+    let to_facet = |row: search_repository::FacetRow| FacetCount {
+        id: row.id.map(|id| id.to_string()),
+        name: row.name,
+        count: row.count as u64,
     };
-    
-    let collections = collection_pages.fetch_page(page - 1).await.unwrap();
-    if collections.is_empty() {
-        return (vec![], 0)
-    }
-    let total = collection_pages.num_items().await.unwrap();
-    let mut values = Vec::new();
-    for item in collections.into_iter() {
-        let nft = bassinet_nft_repository::get_nft_by_collection_id(&item.id.to_string()).await;
-        let mut nft_dto = Option::None;
-        if nft.is_some() {
-            let nft = nft.unwrap();
-            nft_dto = Some(NftInfo {
-                id: nft.id.to_string(),
-                package_id: nft.package_id,
-                collection_url: nft.collection_url.unwrap(),
-                limit: nft.limit.unwrap() as u64,
-                minting_price: nft.minting_price as u64,
-                rewards_quantity: nft.rewards_quantity.unwrap() as u64,
-                mint_id: nft.mint_id.unwrap(),
-                policy_id: nft.policy_id.unwrap(),
-                policy_cap_id: nft.policy_cap_id.unwrap(),
-                coin_id: nft.coin_id.unwrap(),
-                coin_package_id: nft.coin_package_id.unwrap(),
-                coin_treasury_lock_id: nft.coin_treasury_lock_id.unwrap(),
-                coin_admin_cap_id: nft.coin_admin_cap_id.unwrap()
-            });
-        }
-        values.push(
-            CollectionPageDTO{
-                id: item.id.to_string(),
-                title: item.title,
-                description: item.description,
-                is_public: item.is_public as u8,
-                listing: item.listing.unwrap() as u8,
-                created_time: item.created_time.and_utc().timestamp() as u64,
-                icon_url: Some(assets_path.clone() + &item.icon_url.unwrap()),
-                nft: nft_dto
-        });
-    }
-    (values, total)
+    Ok(SearchFacets {
+        categories: categories.into_iter().map(to_facet).collect(),
+        tags: tags.into_iter().map(to_facet).collect(),
+    })
 }
 
-/// 获取公开的图文
-/// TODO 是否公开应该从专辑是否公开来判断
-pub async fn get_article_by_id(article_id: String) -> Result<ArticleInfoDTO, anyhow::Error> {
-    let article = collection_repository::get_article_by_id(&article_id).await;
-    if article.is_none(){
-        anyhow::bail!("未知图文".to_owned());
-    }
-    let article = article.unwrap();
-    if article.is_public != 1 {
-        anyhow::bail!("未知图文".to_owned());
+/// 获取图文
+/// 已上架专辑的图文,作者或持有NFT者可查看全文,其他人只能查看试读部分
+pub async fn get_article_by_id(state: &AppState, article_id: String, viewer: &Viewer) -> Result<ArticleInfoDTO, ApiError> {
+    let article = state.collection_repository.get_article_by_id(&article_id).await?.ok_or(ApiError::ArticleNotFound)?;
+    let collection = state.collection_repository.get_by_id(&article.collection_id.to_string()).await?.ok_or(ApiError::ArticleNotFound)?;
+    let access = access_policy::item_access(viewer, &collection, &article);
+    if access == Access::Denied {
+        return Err(ApiError::ArticleNotFound);
     }
+    let locked = access == Access::NftRequired && !access_policy::owns_collection_nft(state, viewer, &collection).await;
     Ok(ArticleInfoDTO{
         id: article.id.to_string(),
-        title: article.title.unwrap(),
-        collection_id: article.collection_id,
-        description: if article.description.is_none() {"".to_owned()} else {article.description.unwrap()},
-        content: if article.content.is_none() {"".to_owned()} else {article.content.unwrap()},
+        title: article.title.unwrap_or_default(),
+        collection_id: article.collection_id.to_string(),
+        description: article.description.unwrap_or_default(),
+        content: locked_content(article.content, locked),
         content_type: "Markdown".to_owned(),
         created_time: article.created_time.and_utc().timestamp() as u64,
+        locked: locked,
     })
 }
 
-pub async fn get_video_by_id(video_id: String, medias_web_addr: &String, account: AccountInfo) -> Result<CollectionItemInfoDTO, anyhow::Error> {
-    let item = collection_repository::get_item_by(&video_id).await;
-    if item.is_none() {
-        anyhow::bail!("未知视频");
+/// 封面固定到IPFS的地址,icon_url为/{collection_id}/{file_name},上传记录的路径为{file_name}
+pub(crate) async fn icon_ipfs(state: &AppState, icon_url: Option<&str>) -> Result<Option<IpfsLinkDTO>, ApiError> {
+    let Some(file_name) = icon_url.and_then(|icon_url| icon_url.rsplit('/').next()).filter(|file_name| !file_name.is_empty()) else {
+        return Ok(None)
+    };
+    let file = state.file_repository.get_file_by_path(file_name).await?;
+    Ok(file.and_then(|file| file.ipfs).map(|cid| IpfsLinkDTO::new(cid, &state.config.ipfs.gateway_url)))
+}
+
+/// 未解锁时只返回试读部分
+fn locked_content(content: Option<String>, locked: bool) -> String {
+    let content = content.unwrap_or_default();
+    if locked {
+        return article_preview::preview(&content).to_owned()
     }
-    let video = item.unwrap();
+    content
+}
+
+pub async fn get_video_by_id(state: &AppState, video_id: String, medias_web_addr: &String, viewer: &Viewer) -> Result<CollectionItemInfoDTO, ApiError> {
+    let video = state.collection_repository.get_item_by(&video_id).await?.ok_or(ApiError::VideoNotFound)?;
     if video.category != "video" {
-        anyhow::bail!("未知视频");
-    }
-    let collection = collection_repository::get_by_id(&video.collection_id).await;
-    if collection.is_none() {
-        anyhow::bail!("未知视频");
+        return Err(ApiError::VideoNotFound);
     }
+    let collection = state.collection_repository.get_by_id(&video.collection_id.to_string()).await?.ok_or(ApiError::VideoNotFound)?;
 
-    let viewing_key = media_query_service::viewing_key(account.account_id, account.wallet_address, &collection.unwrap()).await;
-    if viewing_key.is_none() {
-        anyhow::bail!("无法访问该视频");
-    }
+    let viewing_key = media_query_service::viewing_key(state, viewer, &collection, &video).await.ok_or(ApiError::VideoForbidden)?;
     Ok(CollectionItemInfoDTO { 
         id: video_id, 
-        title: video.title.unwrap(), 
-        collection_id: video.collection_id, 
-        description: video.description.unwrap(), 
+        title: video.title.unwrap_or_default(), 
+        collection_id: video.collection_id.to_string(), 
+        description: video.description.unwrap_or_default(), 
         content: "".to_owned(), 
         category: video.category, 
-        url_path: format!("{}/{}?viewingKey={}", medias_web_addr, video.path.unwrap(), viewing_key.unwrap()), 
+        url_path: format!("{}/{}?viewingKey={}", medias_web_addr, video.path.unwrap_or_default(), viewing_key), 
         content_type: "".to_owned(), 
-        created_time: video.created_time.and_utc().timestamp() as u64 })
+        created_time: video.created_time.and_utc().timestamp() as u64,
+        locked: false,
+        ipfs: video.ipfs.map(|cid| IpfsLinkDTO::new(cid, &state.config.ipfs.gateway_url)) })
+}
+
+/// 专辑封面的地址,访问者无权查看专辑时返回CollectionNotFound
+pub async fn get_collection_icon_url(state: &AppState, collection_id: &String, viewer: &Viewer) -> Result<Option<String>, ApiError> {
+    let collection = state.collection_repository.get_by_id(collection_id).await?.ok_or(ApiError::CollectionNotFound)?;
+    if access_policy::collection_access(viewer, &collection) == Access::Denied {
+        return Err(ApiError::CollectionNotFound);
+    }
+    Ok(collection.icon_url)
 }
 
-pub async fn get_collection_simple_info_by_id(collection_id: &String, assets_path: &String) -> Result<CollectionSimpleInfoDTO, anyhow::Error> {
-    let collection = collection_repository::get_by_id(collection_id).await;
-    if collection.is_none() {
-        anyhow::bail!("未知专辑");
+pub async fn get_collection_simple_info_by_id(state: &AppState, collection_id: &String, viewer: &Viewer, assets_path: &String) -> Result<CollectionSimpleInfoDTO, ApiError> {
+    let collection = state.collection_repository.get_by_id(collection_id).await?.ok_or(ApiError::CollectionNotFound)?;
+    if access_policy::collection_access(viewer, &collection) == Access::Denied {
+        return Err(ApiError::CollectionNotFound);
     }
-    let collection = collection.unwrap();
     let collection_url = assets_path.to_owned() + "/" + &collection_id;
+    let metadata_ipfs = state.bassinet_nft_repository.get_nft_by_collection_id(&collection.id).await?
+    .and_then(|nft| nft.metadata_ipfs)
+    .map(|cid| IpfsLinkDTO::new(cid, &state.config.ipfs.gateway_url));
     Ok(CollectionSimpleInfoDTO {
         id: collection_id.clone(),
         title: collection.title,
         description: collection.description,
         collection_url: collection_url,
+        metadata_url: nft_metadata_query_service::metadata_url(&state.config.nft, &collection.id),
+        token_metadata_url_template: nft_metadata_query_service::token_metadata_url_template(&state.config.nft, &collection.id),
+        metadata_ipfs,
     })
+}
+
+/// NFT信息
+pub fn to_nft_info(nft: bassinet_nft::Model) -> NftInfo {
+    NftInfo {
+        id: nft.id.to_string(),
+        package_id: nft.package_id,
+        collection_url: nft.collection_url.unwrap_or_default(),
+        limit: nft.limit.unwrap_or(0) as u64,
+        minting_price: nft.minting_price as u64,
+        rewards_quantity: nft.rewards_quantity.unwrap_or(0) as u64,
+        mint_id: nft.mint_id.unwrap_or_default(),
+        policy_id: nft.policy_id.unwrap_or_default(),
+        policy_cap_id: nft.policy_cap_id.unwrap_or_default(),
+        coin_id: nft.coin_id.unwrap_or_default(),
+        coin_package_id: nft.coin_package_id.unwrap_or_default(),
+        coin_treasury_lock_id: nft.coin_treasury_lock_id.unwrap_or_default(),
+        coin_admin_cap_id: nft.coin_admin_cap_id.unwrap_or_default()
+    }
 }
\ No newline at end of file
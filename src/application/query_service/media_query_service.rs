@@ -1,37 +1,96 @@
-use redis::AsyncCommands;
 use uuid::Uuid;
 
-use crate::{domain::{model::entity::collection::Model as CollectionModel, repository::{bassinet_nft_repository}}, infrastructure::{redis_connection, sui::nft_query}};
+use crate::{domain::{model::entity::{collection::Model as CollectionModel, collection_item::Model as CollectionItemModel}, service::access_policy::{self, Viewer}}, state::AppState};
 
-/// 获取ViewingKey
-/// 是否可查看: 
+/// viewing key有效期
+const VIEWING_KEY_SECONDS: u64 = 2 * 60 * 60;
+
+/// 获取ViewingKey,只能访问这个视频的文件
+/// 是否可查看由access_policy判定:
 /// 1. 视频所属专辑是公开的且视频是公开的
-/// 2. 视频所属专辑非公开但已经Minting相应NFT
-pub async fn viewing_key(author_id: String, wallet_address: Option<String>, collection: &CollectionModel)-> Option<String> {
-    // 专辑公开
-    if collection.is_public == 1 || author_id == collection.author{
-        let mut redis_connection = redis_connection::get_redis_connection().await;
-        let viewing_key = Uuid::new_v4().to_string();
-        let key: &str = &("viewing_key_".to_owned() + viewing_key.as_str());
-        let _value = redis_connection.set_ex::<&str, u64, u64>(key, 1u64, 2 * 60 * 60).await;
-        return Some(viewing_key)
-    } else if collection.listing.is_some() && collection.listing.unwrap() == 1 && wallet_address.is_some(){//已经上架
-        let nft = bassinet_nft_repository::get_nft_by_collection_id(&collection.id.to_string()).await;
-        if nft.is_some() {
-            let nft = nft.unwrap();
-            let package_id = nft.package_id;
-            let result = nft_query::get_any_bassinet_nft_by(&wallet_address.unwrap(), &package_id).await;
-            if result.is_ok() {
-                let object = result.unwrap();
-                if object.is_some() {
-                    let mut redis_connection = redis_connection::get_redis_connection().await;
-                    let viewing_key = Uuid::new_v4().to_string();
-                    let key: &str = &("viewing_key_".to_owned() + viewing_key.as_str());
-                    let _value = redis_connection.set_ex::<&str, u64, u64>(key, 1u64, 2 * 60 * 60).await;
-                    return Some(viewing_key)
-                }
-            }
-        }
+/// 2. 视频所属专辑已上架且持有相应NFT
+pub async fn viewing_key(state: &AppState, viewer: &Viewer, collection: &CollectionModel, video: &CollectionItemModel)-> Option<String> {
+    if !access_policy::can_access_item(state, viewer, collection, video).await {
+        return None
+    }
+    let viewing_key = Uuid::new_v4().to_string();
+    let media_key = video.path.as_deref().unwrap_or_default();
+    if let Err(err) = state.cache.set_ex(&viewing_key_of(&viewing_key), media_key, VIEWING_KEY_SECONDS).await {
+        tracing::error!("save viewing key error:{}", err);
+    }
+    Some(viewing_key)
+}
+
+/// viewing key在缓存中的key
+fn viewing_key_of(viewing_key: &str) -> String {
+    "viewing_key_".to_owned() + viewing_key
+}
+
+/// viewing key是否有效且是为media_key对应的视频签发的,媒体服务校验请求时使用
+pub async fn is_valid_viewing_key(state: &AppState, viewing_key: &str, media_key: &str) -> bool {
+    matches!(state.cache.get(&viewing_key_of(viewing_key)).await, Ok(Some(issued_for)) if !issued_for.is_empty() && issued_for == media_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::Ordering, Arc};
+
+    use uuid::Uuid;
+
+    use crate::{domain::{model::entity::{collection, collection_item, nft_token}, service::access_policy::Viewer}, settings::AppConfig, state::{fakes::{self, InMemory}, AppState}};
+
+    use super::{is_valid_viewing_key, viewing_key};
+
+    #[tokio::test]
+    async fn test_viewing_key_for_nft_holder() {
+        let store = Arc::new(InMemory::default());
+        // 内存缓存不会过期,先不缓存持有情况
+        let mut config = AppConfig::for_test();
+        config.sui.ownership_cache_secs = 0;
+        let state = AppState::in_memory(config, store.clone());
+        let collection = collection::Model { listing: Some(1), ..fakes::collection(Uuid::new_v4()) };
+        let video = collection_item::Model { category: "video".to_owned(), path: Some("clip.mp4".to_owned()), ..fakes::item(&collection) };
+        store.nfts.lock().unwrap().push(fakes::nft(collection.id));
+        let holder = Viewer::new(Uuid::new_v4().to_string(), Some("0xB0B".to_owned()));
+        assert_eq!(viewing_key(&state, &holder, &collection, &video).await, None);
+
+        // 链上索引中的持有者以RPC为准,转移后索引可能仍是之前的持有者
+        store.tokens.lock().unwrap().push(nft_token::Model {
+            object_id: "0xa1".to_owned(),
+            package_id: "0xnft".to_owned(),
+            collection_id: collection.id,
+            owner: Some("0xb0b".to_owned()),
+            kiosk_id: None,
+            listed_price: None,
+            minted_by: None,
+            minted_time: None,
+            event_time: 0,
+            event_seq: 0,
+            status: 1,
+            event_tx: String::new(),
+            owner_unresolved: 0,
+        });
+        assert_eq!(viewing_key(&state, &holder, &collection, &video).await, None);
+
+        store.owned_nfts.lock().unwrap().push(("0xB0B".to_owned(), "0xnft".to_owned()));
+        let key = viewing_key(&state, &holder, &collection, &video).await.unwrap();
+        assert!(is_valid_viewing_key(&state, &key, "clip.mp4").await);
+        assert!(!is_valid_viewing_key(&state, &key, "other.mp4").await);
+
+        // RPC不可用时按索引判断
+        store.owned_nfts.lock().unwrap().clear();
+        store.unavailable.store(true, Ordering::SeqCst);
+        assert!(viewing_key(&state, &holder, &collection, &video).await.is_some());
+        store.tokens.lock().unwrap().clear();
+        assert_eq!(viewing_key(&state, &holder, &collection, &video).await, None);
+        store.unavailable.store(false, Ordering::SeqCst);
+        store.owned_nfts.lock().unwrap().push(("0xB0B".to_owned(), "0xnft".to_owned()));
+
+        // 缓存有效期内不再查询
+        let cached = AppState::in_memory(AppConfig::for_test(), store.clone());
+        assert!(viewing_key(&cached, &holder, &collection, &video).await.is_some());
+        store.owned_nfts.lock().unwrap().clear();
+        assert!(viewing_key(&cached, &holder, &collection, &video).await.is_some());
+        assert_eq!(viewing_key(&state, &holder, &collection, &video).await, None);
     }
-    None
-}
\ No newline at end of file
+}
@@ -2,4 +2,6 @@ pub(crate) mod collection_query_service;
 pub(crate) mod my_collection_query_service;
 pub(crate) mod account_query_service;
 pub(crate) mod media_query_service;
-pub(crate) mod chunk_list_query_service;
\ No newline at end of file
+pub(crate) mod chunk_list_query_service;
+pub(crate) mod tag_query_service;
+pub(crate) mod nft_metadata_query_service;
\ No newline at end of file
@@ -1,129 +1,68 @@
-use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use uuid::Uuid;
 
-use crate::{domain::{model::entity::collection, repository::{bassinet_nft_repository, collection_repository}}, infrastructure::database_connection, interface::rest::dto::collection::{CollectionInfoDTO, CollectionItemInfoDTO, CollectionListDTO, CollectionPageDTO, CollectionSimpleDTO, NftInfo}};
+use crate::{domain::{model::valueobject::page::{PageRequest, PageResult}, service::access_policy::{self, Access, Viewer}}, error::ApiError, interface::rest::dto::collection::{CollectionInfoDTO, CollectionItemInfoDTO, CollectionListDTO, IpfsLinkDTO, CollectionPageDTO, CollectionSimpleDTO}, state::AppState};
+
+use super::collection_query_service;
 
 /// 根据作者获取专辑列表(简要信息)
-pub async fn get_collections_by(author_id: &String) -> CollectionListDTO{
-    let list = collection_repository::get_by_author(author_id).await;
-    if list.is_empty() {
-        return CollectionListDTO {
-            collections: vec![],
-        };
-    }
+pub async fn get_collections_by(state: &AppState, author_id: &Uuid) -> Result<CollectionListDTO, ApiError> {
+    let list = state.collection_repository.get_by_author(author_id).await?;
     let vec = list.iter().map(|element|{ CollectionSimpleDTO{
         id: element.id.to_string(),
         title: element.title.clone(),
     }}).collect();
-    CollectionListDTO{
+    Ok(CollectionListDTO{
         collections: vec,
-    }
+    })
 }
 
 /// 我的专辑分页查询
-pub async fn my_collections(author_id: String, page: u64, limit: u64, assets_path: &String) -> (Vec<CollectionPageDTO>, u64) {
-    let mut values = Vec::new();
-
-    let db = database_connection::get_db();
-    let collection_pages = collection::Entity::find()
-    .filter(collection::Column::Author.eq(author_id)).order_by_desc(collection::Column::CreatedTime)
-    .paginate(db.as_ref(), limit);
-    
-    let collections = collection_pages.fetch_page(page - 1).await.unwrap();
-    if collections.is_empty() {
-        return (vec![], 0)
-    }
-    let total = collection_pages.num_items().await.unwrap();
-    for item in collections.into_iter() {
-        let nft = bassinet_nft_repository::get_nft_by_collection_id(&item.id.to_string()).await;
-        let mut nft_dto = Option::None;
-        if nft.is_some() {
-            let nft = nft.unwrap();
-            nft_dto = Some(NftInfo {
-                id: nft.id.to_string(),
-                package_id: nft.package_id,
-                collection_url: nft.collection_url.unwrap(),
-                limit: nft.limit.unwrap() as u64,
-                minting_price: nft.minting_price as u64,
-                rewards_quantity: nft.rewards_quantity.unwrap() as u64,
-                mint_id: nft.mint_id.unwrap(),
-                policy_id: nft.policy_id.unwrap(),
-                policy_cap_id: nft.policy_cap_id.unwrap(),
-                coin_id: nft.coin_id.unwrap(),
-                coin_package_id: nft.coin_package_id.unwrap(),
-                coin_treasury_lock_id: nft.coin_treasury_lock_id.unwrap(),
-                coin_admin_cap_id: nft.coin_admin_cap_id.unwrap()
-            });
-        }
-        values.push(
-            CollectionPageDTO{
-                id: item.id.to_string(),
-                title: item.title,
-                description: item.description,
-                is_public: item.is_public as u8,
-                listing: item.listing.unwrap() as u8,
-                created_time: item.created_time.and_utc().timestamp() as u64,
-                icon_url: Some(assets_path.clone() + &item.icon_url.unwrap()),
-                nft: nft_dto
-        });
-    }
-    (values, total)
+pub async fn my_collections(state: &AppState, author_id: Uuid, request: &PageRequest, assets_path: &String) -> Result<PageResult<CollectionPageDTO>, ApiError> {
+    let mut page = state.collection_repository.page_collections(&author_id, false, request).await?;
+    let values = collection_query_service::to_page_dtos(state, std::mem::take(&mut page.items), assets_path).await?;
+    Ok(page.with_items(values))
 }
 
 /// 专辑详情(我的专辑)
-pub async fn get_my_collection_by(collection_id: &String, author_id: &String, assets_web_addr: &String, medias_web_addr: &String) -> Result<CollectionInfoDTO, anyhow::Error> {
-    let collection = collection_repository::get_my_collection_by_id(collection_id, author_id).await;
-    if collection.is_none() {
-        anyhow::bail!("未知专辑");
-    }
-    let collection = collection.unwrap();
-    let items = collection_repository::get_items_by(collection_id).await;
-    if items.is_err() {
-        return Err(items.err().unwrap());
-    }
-    let dtos = items.unwrap().into_iter().map(|item|{
+pub async fn get_my_collection_by(state: &AppState, collection_id: &String, author_id: &Uuid, assets_web_addr: &String, medias_web_addr: &String) -> Result<CollectionInfoDTO, ApiError> {
+    let collection = state.collection_repository.get_my_collection_by_id(collection_id, author_id).await?.ok_or(ApiError::CollectionNotFound)?;
+    let viewer = Viewer::new(author_id.to_string(), None);
+    let items = state.collection_repository.get_items_by(&collection.id).await?;
+    let dtos = items.into_iter()
+    .filter(|item| access_policy::item_access(&viewer, &collection, item) == Access::Granted)
+    .map(|item|{
         CollectionItemInfoDTO{
             id: item.id.to_string(),
-            title: item.title.unwrap(),
-            collection_id: item.collection_id,
-            description: if item.description.is_none() {"".to_owned()} else { item.description.unwrap()},
-            content: if item.content.is_none() {"".to_owned()} else {item.content.unwrap()},
+            title: item.title.unwrap_or_default(),
+            collection_id: item.collection_id.to_string(),
+            description: item.description.unwrap_or_default(),
+            content: item.content.unwrap_or_default(),
             category: item.category,
-            url_path: if item.path.is_none() {"".to_owned()} else {format!("{}/{}", medias_web_addr, item.path.unwrap())},
+            url_path: item.path.map(|path| format!("{}/{}", medias_web_addr, path)).unwrap_or_default(),
             content_type: "".to_owned(),
             created_time: item.created_time.and_utc().timestamp() as u64,
+            locked: false,
+            ipfs: item.ipfs.map(|cid| IpfsLinkDTO::new(cid, &state.config.ipfs.gateway_url)),
         }
     }).collect();
 
-    let nft = bassinet_nft_repository::get_nft_by_collection_id(collection_id).await;
-    let mut nft_dto = Option::None;
-    if nft.is_some() {
-        let nft = nft.unwrap();
-        nft_dto = Some(NftInfo {
-            id: nft.id.to_string(),
-            package_id: nft.package_id,
-            collection_url: nft.collection_url.unwrap(),
-            limit: nft.limit.unwrap() as u64,
-            minting_price: nft.minting_price as u64,
-            rewards_quantity: nft.rewards_quantity.unwrap() as u64,
-            mint_id: nft.mint_id.unwrap(),
-            policy_id: nft.policy_id.unwrap(),
-            policy_cap_id: nft.policy_cap_id.unwrap(),
-            coin_id: nft.coin_id.unwrap(),
-            coin_package_id: nft.coin_package_id.unwrap(),
-            coin_treasury_lock_id: nft.coin_treasury_lock_id.unwrap(),
-            coin_admin_cap_id: nft.coin_admin_cap_id.unwrap()
-        });
-    }
+    let nft_dto = state.bassinet_nft_repository.get_nft_by_collection_id(&collection.id).await?.map(collection_query_service::to_nft_info);
+
+    let tags = state.tag_repository.get_tags_by_collection(&collection.id).await?;
+    let icon_ipfs = collection_query_service::icon_ipfs(state, collection.icon_url.as_deref()).await?;
 
     Ok(CollectionInfoDTO {
         id: collection_id.clone(),
         title: collection.title,
         description: collection.description,
         is_public: collection.is_public as u8,
-        listing: collection.listing.unwrap() as u8,
+        listing: collection.listing.unwrap_or(0) as u8,
         created_time: collection.created_time.and_utc().timestamp() as u64,
-        icon_url: Some(assets_web_addr.clone() + &collection.icon_url.unwrap()),
+        icon_url: Some(assets_web_addr.clone() + &collection.icon_url.unwrap_or_default()),
+        icon_ipfs,
         nft: nft_dto,
+        category_id: collection.category_id.map(|category_id| category_id.to_string()),
+        tags: tags,
         items: dtos
         // articles: article_dtos,
     })
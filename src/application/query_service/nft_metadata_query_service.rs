@@ -0,0 +1,120 @@
+use uuid::Uuid;
+
+use crate::{domain::model::entity::{bassinet_nft, collection}, error::ApiError, interface::rest::dto::{collection::IpfsLinkDTO, nft::{NftAttributeDTO, NftMetadataDTO}}, settings::NftConfig, state::AppState};
+
+use super::collection_query_service;
+
+/// 专辑的元数据地址,NFT发布时作为collection_url
+pub fn metadata_url(config: &NftConfig, collection_id: &Uuid) -> String {
+    format!("{}/collections/{}/metadata", config.metadata_base_url.trim_end_matches('/'), collection_id)
+}
+
+/// 每个NFT的元数据地址模板,{edition}为从1开始的编号
+pub fn token_metadata_url_template(config: &NftConfig, collection_id: &Uuid) -> String {
+    format!("{}/collections/{}/tokens/{{edition}}/metadata", config.metadata_base_url.trim_end_matches('/'), collection_id)
+}
+
+/// 已上架专辑的元数据
+pub async fn collection_metadata(state: &AppState, collection_id: &String) -> Result<NftMetadataDTO, ApiError> {
+    let (collection, nft) = listed_nft(state, collection_id).await?;
+    build_metadata(state, &collection, &nft).await
+}
+
+/// 第edition个NFT的元数据,edition在1..=limit之间
+pub async fn token_metadata(state: &AppState, collection_id: &String, edition: u64) -> Result<NftMetadataDTO, ApiError> {
+    let (collection, nft) = listed_nft(state, collection_id).await?;
+    if !(1..=nft.limit.unwrap_or(0) as u64).contains(&edition) {
+        return Err(ApiError::TokenNotFound)
+    }
+    let mut metadata = build_metadata(state, &collection, &nft).await?;
+    metadata.name = format!("{} #{}", metadata.name, edition);
+    metadata.attributes.push(NftAttributeDTO::new("edition", edition));
+    Ok(metadata)
+}
+
+/// 专辑及其NFT,未上架时返回NftNotListed
+async fn listed_nft(state: &AppState, collection_id: &String) -> Result<(collection::Model, bassinet_nft::Model), ApiError> {
+    let collection = state.collection_repository.get_by_id(collection_id).await?
+    .filter(|collection| collection.status == 1)
+    .ok_or(ApiError::CollectionNotFound)?;
+    if collection.listing != Some(1) {
+        return Err(ApiError::NftNotListed)
+    }
+    let nft = state.bassinet_nft_repository.get_nft_by_collection_id(&collection.id).await?.ok_or(ApiError::NftNotListed)?;
+    Ok((collection, nft))
+}
+
+pub(crate) async fn build_metadata(state: &AppState, collection: &collection::Model, nft: &bassinet_nft::Model) -> Result<NftMetadataDTO, ApiError> {
+    let config = &state.config.nft;
+    // 优先使用IPFS上不可变的封面
+    let image = match collection_query_service::icon_ipfs(state, collection.icon_url.as_deref()).await? {
+        Some(IpfsLinkDTO { uri, .. }) => uri,
+        None => format!("{}{}", state.config.server.assets_http_addr, collection.icon_url.as_deref().unwrap_or_default()),
+    };
+    let link = config.web_url.as_ref().map(|web_url| format!("{}/collections/{}", web_url.trim_end_matches('/'), collection.id));
+    let mut attributes = vec![
+        NftAttributeDTO::new("limit", nft.limit.unwrap_or(0) as u64),
+        NftAttributeDTO::new("minting_price", nft.minting_price as u64),
+        NftAttributeDTO::new("rewards_quantity", nft.rewards_quantity.unwrap_or(0) as u64),
+    ];
+    if let Some(package_id) = &nft.coin_package_id {
+        if let Some(coin) = state.bassinet_coin_repository.get_coin_by_package_id(package_id).await? {
+            attributes.push(NftAttributeDTO::new("rewards_coin", coin.symbol));
+        }
+    }
+    Ok(NftMetadataDTO {
+        name: collection.title.clone(),
+        description: nft.description.clone().filter(|description| !description.is_empty()).unwrap_or_else(|| collection.description.clone()),
+        image_url: image.clone(),
+        image,
+        external_url: link.clone(),
+        attributes,
+        link,
+        project_url: config.web_url.clone(),
+        creator: config.creator.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use crate::{error::ApiError, interface::rest::dto::nft::{NftAttributeDTO, NftAttributeValue}, settings::AppConfig, state::{fakes::{self, InMemory}, AppState}};
+
+    use super::{collection_metadata, token_metadata};
+
+    #[tokio::test]
+    async fn test_metadata() {
+        let store = Arc::new(InMemory::default());
+        let mut config = AppConfig::for_test();
+        config.nft.web_url = Some("https://bassinet.app".to_owned());
+        let state = AppState::in_memory(config, store.clone());
+        let mut collection = fakes::collection(Uuid::new_v4());
+        let collection_id = collection.id;
+        collection.description = "papi's songs".to_owned();
+        collection.icon_url = Some(format!("/{}/cover.png", collection_id));
+        store.collections.lock().unwrap().push(collection);
+        let id = collection_id.to_string();
+        assert!(matches!(collection_metadata(&state, &id).await, Err(ApiError::NftNotListed)));
+        assert!(matches!(collection_metadata(&state, &Uuid::new_v4().to_string()).await, Err(ApiError::CollectionNotFound)));
+
+        store.collections.lock().unwrap()[0].listing = Some(1);
+        store.nfts.lock().unwrap().push(fakes::nft(collection_id));
+        let metadata = collection_metadata(&state, &id).await.unwrap();
+        assert_eq!(metadata.name, "papi");
+        assert_eq!(metadata.description, "papi's songs");
+        assert_eq!(metadata.image, format!("{}/{}/cover.png", state.config.server.assets_http_addr, collection_id));
+        assert_eq!(metadata.image_url, metadata.image);
+        assert_eq!(metadata.link, Some(format!("https://bassinet.app/collections/{}", collection_id)));
+        assert_eq!(metadata.attributes[0], NftAttributeDTO::new("limit", 100_u64));
+
+        let token = token_metadata(&state, &id, 100).await.unwrap();
+        assert_eq!(token.name, "papi #100");
+        assert_eq!(token.attributes.last().map(|attribute| &attribute.value), Some(&NftAttributeValue::Number(100)));
+        for edition in [0, 101] {
+            assert!(matches!(token_metadata(&state, &id, edition).await, Err(ApiError::TokenNotFound)));
+        }
+    }
+}
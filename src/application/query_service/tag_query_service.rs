@@ -0,0 +1,31 @@
+use uuid::Uuid;
+
+use crate::{error::ApiError, interface::rest::dto::{collection::CollectionPageDTO, tag::{CategoryDTO, TagCountDTO}}, state::AppState};
+
+use super::collection_query_service;
+
+/// 所有分类
+pub async fn get_categories(state: &AppState) -> Result<Vec<CategoryDTO>, ApiError> {
+    let categories = state.category_repository.get_categories().await?;
+    Ok(categories.into_iter().map(|category| CategoryDTO {
+        id: category.id.to_string(),
+        slug: category.slug,
+        name: category.name,
+    }).collect())
+}
+
+/// 常用标签
+pub async fn get_popular_tags(state: &AppState, prefix: Option<String>, limit: u64) -> Result<Vec<TagCountDTO>, ApiError> {
+    let tags = state.tag_repository.popular_tags(prefix, limit).await?;
+    Ok(tags.into_iter().map(|tag| TagCountDTO {
+        name: tag.name,
+        count: tag.count as u64,
+    }).collect())
+}
+
+/// 相关专辑(共享标签)
+pub async fn get_related_collections(state: &AppState, collection_id: &String, limit: u64, assets_path: &String) -> Result<Vec<CollectionPageDTO>, ApiError> {
+    let collection_id = Uuid::parse_str(collection_id).map_err(|_| ApiError::CollectionNotFound)?;
+    let collections = state.tag_repository.related_collections(&collection_id, limit).await?;
+    collection_query_service::to_page_dtos(state, collections, assets_path).await
+}
\ No newline at end of file
@@ -5,6 +5,8 @@ pub struct CreateCollectionCommand {
     pub is_public: u32,
     pub pub_key: String,
     pub icon_path: String,
+    pub category_id: Option<String>,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -26,4 +28,11 @@ pub struct AddVideoCommand {
     pub video_path: String,
     pub hash: String,
     pub pub_key: String,
+}
+
+#[derive(Debug)]
+pub struct SetCollectionTagsCommand {
+    pub collection_id: String,
+    pub tags: Vec<String>,
+    pub pub_key: String,
 }
\ No newline at end of file
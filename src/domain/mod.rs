@@ -1,4 +1,5 @@
 pub mod model;
 pub mod event;
 pub mod command;
-pub mod repository;
\ No newline at end of file
+pub mod repository;
+pub mod service;
\ No newline at end of file
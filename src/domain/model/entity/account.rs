@@ -16,6 +16,23 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::collection::Entity")]
+    Collection,
+    #[sea_orm(has_many = "super::bassinet_coin::Entity")]
+    BassinetCoin,
+}
+
+impl Related<super::collection::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Collection.def()
+    }
+}
+
+impl Related<super::bassinet_coin::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BassinetCoin.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
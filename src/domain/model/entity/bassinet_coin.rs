@@ -15,10 +15,31 @@ pub struct Model {
     pub icon_url: Option<String>,
     pub treasury_lock_id: String,
     pub admin_cap_id: String,
-    pub account_id: Option<String>,
+    pub account_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::account::Entity",
+        from = "Column::AccountId",
+        to = "super::account::Column::Id"
+    )]
+    Account,
+    #[sea_orm(has_many = "super::bassinet_nft::Entity")]
+    BassinetNft,
+}
+
+impl Related<super::account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Account.def()
+    }
+}
+
+impl Related<super::bassinet_nft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BassinetNft.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -10,7 +10,7 @@ pub struct Model {
     #[sea_orm(unique)]
     pub package_id: String,
     #[sea_orm(unique)]
-    pub collection_id: String,
+    pub collection_id: Uuid,
     pub description: Option<String>,
     pub collection_url: Option<String>,
     pub limit: Option<i64>,
@@ -23,9 +23,35 @@ pub struct Model {
     pub coin_package_id: Option<String>,
     pub coin_treasury_lock_id: Option<String>,
     pub coin_admin_cap_id: Option<String>,
+    pub metadata_ipfs: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::collection::Entity",
+        from = "Column::CollectionId",
+        to = "super::collection::Column::Id"
+    )]
+    Collection,
+    #[sea_orm(
+        belongs_to = "super::bassinet_coin::Entity",
+        from = "Column::CoinPackageId",
+        to = "super::bassinet_coin::Column::PackageId"
+    )]
+    BassinetCoin,
+}
+
+impl Related<super::collection::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Collection.def()
+    }
+}
+
+impl Related<super::bassinet_coin::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BassinetCoin.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
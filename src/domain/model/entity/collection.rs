@@ -11,15 +11,53 @@ pub struct Model {
     pub description: String,
     pub created_time: DateTime,
     pub is_public: i32,
-    pub author: String,
+    pub author: Uuid,
     pub listing: Option<i32>,
     pub icon_url: Option<String>,
     pub package_id: Option<String>,
     pub seq: i32,
     pub status: i32,
+    pub category_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::account::Entity",
+        from = "Column::Author",
+        to = "super::account::Column::Id"
+    )]
+    Account,
+    #[sea_orm(has_one = "super::bassinet_nft::Entity")]
+    BassinetNft,
+    #[sea_orm(has_many = "super::collection_item::Entity")]
+    CollectionItem,
+    #[sea_orm(has_many = "super::nft_token::Entity")]
+    NftToken,
+}
+
+impl Related<super::account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Account.def()
+    }
+}
+
+impl Related<super::bassinet_nft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BassinetNft.def()
+    }
+}
+
+impl Related<super::collection_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CollectionItem.def()
+    }
+}
+
+impl Related<super::nft_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NftToken.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
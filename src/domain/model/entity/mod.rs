@@ -5,7 +5,12 @@ pub mod prelude;
 pub mod account;
 pub mod bassinet_coin;
 pub mod bassinet_nft;
+pub mod category;
 pub mod chunk_list;
 pub mod collection;
 pub mod collection_item;
+pub mod collection_tag;
 pub mod file_entity;
+pub mod nft_event_cursor;
+pub mod nft_token;
+pub mod tag;
@@ -3,7 +3,12 @@
 pub use super::account::Entity as Account;
 pub use super::bassinet_coin::Entity as BassinetCoin;
 pub use super::bassinet_nft::Entity as BassinetNft;
+pub use super::category::Entity as Category;
 pub use super::chunk_list::Entity as ChunkList;
 pub use super::collection::Entity as Collection;
 pub use super::collection_item::Entity as CollectionItem;
-// pub use super::file_entity::Entity as FileEntity;
+pub use super::collection_tag::Entity as CollectionTag;
+pub use super::file_entity::Entity as FileEntity;
+pub use super::nft_event_cursor::Entity as NftEventCursor;
+pub use super::nft_token::Entity as NftToken;
+pub use super::tag::Entity as Tag;
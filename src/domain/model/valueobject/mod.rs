@@ -0,0 +1,2 @@
+pub mod nft_event;
+pub mod page;
\ No newline at end of file
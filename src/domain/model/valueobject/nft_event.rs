@@ -0,0 +1,65 @@
+/// 链上事件的位置,Sui的EventID(交易digest + 交易内序号)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventCursor {
+    pub tx_digest: String,
+    pub event_seq: u64,
+}
+
+/// 索引的事件来源,每个来源单独记录游标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventStream {
+    // {package_id}::bassinet_nft模块的铸造、转移、销毁事件
+    Module,
+    // 0x2::kiosk::ItemListed<{package_id}::bassinet_nft::BassinetNFT>
+    KioskListed,
+    // 0x2::kiosk::ItemDelisted<..>
+    KioskDelisted,
+    // 0x2::kiosk::ItemPurchased<..>
+    KioskPurchased,
+}
+
+impl EventStream {
+    pub const ALL: [EventStream; 4] = [EventStream::Module, EventStream::KioskListed, EventStream::KioskDelisted, EventStream::KioskPurchased];
+
+    /// 游标表中的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventStream::Module => "bassinet_nft",
+            EventStream::KioskListed => "kiosk_listed",
+            EventStream::KioskDelisted => "kiosk_delisted",
+            EventStream::KioskPurchased => "kiosk_purchased",
+        }
+    }
+}
+
+/// 影响NFT归属的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NftEventKind {
+    Minted { owner: String },
+    Transferred { to: String },
+    // 放入kiosk并标价,seller为kiosk所有者
+    Listed { kiosk_id: String, seller: String, price: u64 },
+    Delisted { kiosk_id: String },
+    // buyer为购买交易的发送者
+    Purchased { buyer: String },
+    Burned,
+}
+
+/// 解析后的NFT事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftEvent {
+    pub id: EventCursor,
+    // BassinetNFT对象ID
+    pub object_id: String,
+    pub kind: NftEventKind,
+    // 事件所在checkpoint的时间
+    pub timestamp_ms: u64,
+}
+
+/// 一页事件,next_cursor为这一页最后一个事件的位置
+#[derive(Debug, Clone, Default)]
+pub struct NftEventPage {
+    pub events: Vec<NftEvent>,
+    pub next_cursor: Option<EventCursor>,
+    pub has_next_page: bool,
+}
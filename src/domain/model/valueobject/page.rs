@@ -0,0 +1,167 @@
+use anyhow::anyhow;
+use chrono::DateTime as ChronoDateTime;
+use sea_orm::prelude::DateTime;
+use uuid::Uuid;
+
+/// 默认每页数量
+pub const DEFAULT_PAGE_SIZE: u64 = 10;
+/// 每页最大数量
+pub const MAX_PAGE_SIZE: u64 = 50;
+
+const CURSOR_VERSION: &str = "v1";
+
+/// 分页游标(相关度 + created_time + id),对外为不透明字符串
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageCursor {
+    // 按相关度排序时才有
+    pub rank: Option<f64>,
+    pub created_time: DateTime,
+    pub id: Uuid,
+}
+
+impl PageCursor {
+    pub fn new(rank: Option<f64>, created_time: DateTime, id: Uuid) -> Self {
+        Self { rank, created_time, id }
+    }
+
+    pub fn encode(&self) -> String {
+        let rank = self.rank.map(|rank| rank.to_string()).unwrap_or_default();
+        let value = format!("{}|{}|{}|{}", CURSOR_VERSION, rank, self.created_time.and_utc().timestamp_micros(), self.id);
+        hex::encode(value)
+    }
+
+    pub fn decode(value: &str) -> Result<Self, anyhow::Error> {
+        let bytes = hex::decode(value).map_err(|_| anyhow!("无效游标"))?;
+        let value = String::from_utf8(bytes).map_err(|_| anyhow!("无效游标"))?;
+        let parts: Vec<&str> = value.split('|').collect();
+        if parts.len() != 4 || parts[0] != CURSOR_VERSION {
+            anyhow::bail!("无效游标");
+        }
+        let rank = if parts[1].is_empty() {
+            None
+        } else {
+            Some(parts[1].parse::<f64>().map_err(|_| anyhow!("无效游标"))?)
+        };
+        let micros = parts[2].parse::<i64>().map_err(|_| anyhow!("无效游标"))?;
+        let created_time = ChronoDateTime::from_timestamp_micros(micros).ok_or(anyhow!("无效游标"))?.naive_utc();
+        let id = Uuid::parse_str(parts[3]).map_err(|_| anyhow!("无效游标"))?;
+        Ok(Self { rank, created_time, id })
+    }
+}
+
+/// 分页请求,有游标时忽略页码
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub page: u64,
+    pub limit: u64,
+    pub cursor: Option<PageCursor>,
+    // 是否统计总数
+    pub with_total: bool,
+}
+
+impl PageRequest {
+    pub fn new(page: Option<u32>, limit: Option<u32>, cursor: Option<PageCursor>, with_total: bool) -> Self {
+        let page = page.filter(|page| *page >= 1).unwrap_or(1) as u64;
+        let limit = limit.filter(|limit| *limit >= 1).map(|limit| (limit as u64).min(MAX_PAGE_SIZE)).unwrap_or(DEFAULT_PAGE_SIZE);
+        Self { page, limit, cursor, with_total }
+    }
+
+    /// 无游标时的偏移量
+    pub fn offset(&self) -> u64 {
+        if self.cursor.is_some() {
+            return 0
+        }
+        (self.page - 1) * self.limit
+    }
+}
+
+/// 分页结果
+#[derive(Debug)]
+pub struct PageResult<T> {
+    pub items: Vec<T>,
+    pub total: Option<u64>,
+    pub next_cursor: Option<PageCursor>,
+    pub has_more: bool,
+}
+
+impl<T> PageResult<T> {
+    /// 根据多查询一条的结果构造分页
+    pub fn from_rows(mut rows: Vec<T>, request: &PageRequest, total: Option<u64>, cursor_of: impl Fn(&T) -> PageCursor) -> Self {
+        let has_more = rows.len() as u64 > request.limit;
+        rows.truncate(request.limit as usize);
+        let next_cursor = if has_more { rows.last().map(cursor_of) } else { None };
+        Self { items: rows, total, next_cursor, has_more }
+    }
+
+    /// 替换分页内容
+    pub fn with_items<U>(self, items: Vec<U>) -> PageResult<U> {
+        PageResult { items, total: self.total, next_cursor: self.next_cursor, has_more: self.has_more }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use proptest::prelude::*;
+    use uuid::Uuid;
+
+    use super::{PageCursor, PageRequest, PageResult, MAX_PAGE_SIZE};
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let created_time = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap().naive_utc();
+        let cursor = PageCursor::new(Some(0.123456789), created_time, Uuid::new_v4());
+        assert_eq!(PageCursor::decode(&cursor.encode()).unwrap(), cursor);
+
+        let cursor = PageCursor::new(None, created_time, Uuid::new_v4());
+        assert_eq!(PageCursor::decode(&cursor.encode()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_cursor_invalid() {
+        assert!(PageCursor::decode("not hex").is_err());
+        assert!(PageCursor::decode(&hex::encode("v2||0|00000000-0000-0000-0000-000000000000")).is_err());
+        assert!(PageCursor::decode(&hex::encode("v1|x|0|00000000-0000-0000-0000-000000000000")).is_err());
+        assert!(PageCursor::decode(&hex::encode("v1||0|not-uuid")).is_err());
+    }
+
+    #[test]
+    fn test_page_request() {
+        let request = PageRequest::new(None, None, None, false);
+        assert_eq!((request.page, request.limit, request.offset()), (1, 10, 0));
+        let request = PageRequest::new(Some(3), Some(1000), None, false);
+        assert_eq!((request.limit, request.offset()), (MAX_PAGE_SIZE, 2 * MAX_PAGE_SIZE));
+        let request = PageRequest::new(Some(0), Some(0), None, false);
+        assert_eq!((request.page, request.limit), (1, 10));
+    }
+
+    #[test]
+    fn test_page_result() {
+        let request = PageRequest::new(None, Some(2), None, false);
+        let created_time = DateTime::from_timestamp(0, 0).unwrap().naive_utc();
+        let ids = vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let page = PageResult::from_rows(ids.clone(), &request, None, |id| PageCursor::new(None, created_time, *id));
+        assert!(page.has_more);
+        assert_eq!(page.items, ids[..2].to_vec());
+        assert_eq!(page.next_cursor.unwrap().id, ids[1]);
+
+        let page = PageResult::from_rows(ids[..2].to_vec(), &request, Some(2), |id| PageCursor::new(None, created_time, *id));
+        assert!(!page.has_more);
+        assert!(page.next_cursor.is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_cursor_decode_never_panics(value in ".*", fields in "v1\\|[^|]*\\|[^|]*\\|[^|]*") {
+            let _ = PageCursor::decode(&value);
+            let _ = PageCursor::decode(&hex::encode(&fields));
+        }
+
+        #[test]
+        fn prop_page_request_offset(page in any::<Option<u32>>(), limit in any::<Option<u32>>()) {
+            let request = PageRequest::new(page, limit, None, false);
+            prop_assert!(request.limit >= 1 && request.limit <= MAX_PAGE_SIZE);
+            prop_assert_eq!(request.offset(), (request.page - 1) * request.limit);
+        }
+    }
+}
\ No newline at end of file
@@ -1,31 +1,67 @@
-// /// 根据id获取专辑 
+// /// 根据id获取专辑
 // pub fn get_by_id(account_id: String) -> Option<crate::domain::model::entity::> {
 //     Option::None
 // }
 
-use anyhow::anyhow;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use async_trait::async_trait;
+use sea_orm::{ActiveModelTrait, ActiveValue::{Set, Unchanged}, ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait};
+use uuid::Uuid;
 
-use crate::{domain::model::entity::{account, prelude::Account}, infrastructure::database_connection::get_db};
+use crate::domain::{model::entity::{account, prelude::Account}, service::access_policy};
 
-/// 根据pub_key获取账户信息
-pub async fn find_by_pubkey(pub_key: &String) -> Vec<account::Model> {
-    Account::find().filter(account::Column::PubKey.eq(pub_key))
-    .all(get_db().as_ref()).await.unwrap()
+/// 账户存储
+#[async_trait]
+pub trait AccountRepository: Send + Sync {
+    /// 根据pub_key获取账户,不存在时为None
+    async fn get_account_by(&self, pub_key: &String) -> Result<Option<account::Model>, anyhow::Error>;
+
+    /// 有公开或已上架专辑的账户
+    async fn get_authors(&self) -> Result<Vec<account::Model>, anyhow::Error>;
+
+    /// 新增账户
+    async fn add_account(&self, account: account::Model) -> Result<(), anyhow::Error>;
+
+    /// 绑定钱包地址
+    async fn bind_wallet(&self, account_id: &Uuid, wallet_address: String) -> Result<(), anyhow::Error>;
 }
 
-pub async fn get_account_by(pub_key: &String) -> Result<account::Model, anyhow::Error> {
-    let accounts = Account::find().filter(account::Column::PubKey.eq(pub_key))
-    .all(get_db().as_ref()).await?;
-    if accounts.len() == 0 {
-        return Err(anyhow!("No Account"))
+#[async_trait]
+impl AccountRepository for DatabaseConnection {
+    async fn get_account_by(&self, pub_key: &String) -> Result<Option<account::Model>, anyhow::Error> {
+        let account = Account::find().filter(account::Column::PubKey.eq(pub_key))
+        .one(self).await?;
+        Ok(account)
+    }
+
+    async fn get_authors(&self) -> Result<Vec<account::Model>, anyhow::Error> {
+        let authors = Account::find()
+        .join(JoinType::InnerJoin, account::Relation::Collection.def())
+        .filter(access_policy::public_collection_condition())
+        .distinct()
+        .order_by_asc(account::Column::CreatedTime)
+        .all(self)
+        .await?;
+        Ok(authors)
+    }
+
+    async fn add_account(&self, account: account::Model) -> Result<(), anyhow::Error> {
+        account::ActiveModel::from(account).reset_all().insert(self).await?;
+        Ok(())
+    }
+
+    async fn bind_wallet(&self, account_id: &Uuid, wallet_address: String) -> Result<(), anyhow::Error> {
+        let account = account::ActiveModel {
+            id: Unchanged(*account_id),
+            wallet_address: Set(Some(wallet_address)),
+            ..Default::default()
+        };
+        account.update(self).await?;
+        Ok(())
     }
-    let account = accounts.get(0);
-    Ok(account.unwrap().clone())
 }
 
 // /// 根据wallet_address获取账户信息
 // pub async fn find_by_wallet_address(wallet_address: &String) -> Vec<account::Model> {
 //     Account::find().filter(account::Column::WalletAddress.eq(wallet_address))
 //     .all(get_db().as_ref()).await.unwrap()
-// }
\ No newline at end of file
+// }
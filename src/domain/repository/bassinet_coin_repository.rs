@@ -1,28 +1,38 @@
-use anyhow::Ok;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use async_trait::async_trait;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use uuid::Uuid;
 
-use crate::{domain::model::entity::{bassinet_coin, prelude::BassinetCoin}, infrastructure::database_connection::get_db};
+use crate::domain::model::entity::{bassinet_coin, prelude::BassinetCoin};
 
-/// 根据package_id获取BassinetCoin
-pub async fn get_coin_by_package_id(package_id: &String) -> Option<bassinet_coin::Model> {
-    let coins = BassinetCoin::find().filter(bassinet_coin::Column::PackageId.eq(package_id))
-    .all(get_db().as_ref()).await.unwrap();
+/// 代币存储
+#[async_trait]
+pub trait BassinetCoinRepository: Send + Sync {
+    /// 根据package_id获取BassinetCoin
+    async fn get_coin_by_package_id(&self, package_id: &String) -> Result<Option<bassinet_coin::Model>, anyhow::Error>;
 
-    if coins.is_empty() {
-        return Option::None
-    }
-    let coin = coins.get(0).unwrap().clone();
-    Some(coin)
+    /// 根据account_id获取Bassinet Coin
+    async fn get_coin_by_account_id(&self, account_id: &Uuid) -> Result<Option<bassinet_coin::Model>, anyhow::Error>;
+
+    /// 新增代币
+    async fn add_coin(&self, coin: bassinet_coin::Model) -> Result<(), anyhow::Error>;
 }
 
-/// 根据account_id获取Bassinet Coin
-pub async  fn get_coin_by_account_id(account_id: &String) -> Result<Option<bassinet_coin::Model>, anyhow::Error> {
-    let coins = BassinetCoin::find().filter(bassinet_coin::Column::AccountId.eq(account_id))
-    .all(get_db().as_ref()).await?;
+#[async_trait]
+impl BassinetCoinRepository for DatabaseConnection {
+    async fn get_coin_by_package_id(&self, package_id: &String) -> Result<Option<bassinet_coin::Model>, anyhow::Error> {
+        let coin = BassinetCoin::find().filter(bassinet_coin::Column::PackageId.eq(package_id))
+        .one(self).await?;
+        Ok(coin)
+    }
 
-    if coins.is_empty() {
-        return Ok(Option::None)
+    async fn get_coin_by_account_id(&self, account_id: &Uuid) -> Result<Option<bassinet_coin::Model>, anyhow::Error> {
+        let coin = BassinetCoin::find().filter(bassinet_coin::Column::AccountId.eq(*account_id))
+        .one(self).await?;
+        Ok(coin)
     }
 
-    Ok(Some(coins.get(0).unwrap().clone()))
-}
\ No newline at end of file
+    async fn add_coin(&self, coin: bassinet_coin::Model) -> Result<(), anyhow::Error> {
+        bassinet_coin::ActiveModel::from(coin).reset_all().insert(self).await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use std::collections::HashMap;
 
-use crate::{domain::model::entity::{bassinet_nft, prelude::BassinetNft}, infrastructure::database_connection::get_db};
+use async_trait::async_trait;
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::domain::model::entity::{bassinet_nft, prelude::BassinetNft};
 
 // /// 根据package_id获取NFT信息
 // pub async fn get_nft_by_package_id(package_id: &String) -> Option<bassinet_nft::Model> {
@@ -14,26 +18,58 @@ use crate::{domain::model::entity::{bassinet_nft, prelude::BassinetNft}, infrast
 //     Some(nft)
 // }
 
-/// 根据package_id获取NFT信息
-pub async fn get_nft_by_collection_id(collection_id: &String) -> Option<bassinet_nft::Model> {
-    let nfts = BassinetNft::find().filter(bassinet_nft::Column::CollectionId.eq(collection_id))
-    .all(get_db().as_ref()).await.unwrap();
+/// 专辑NFT存储
+#[async_trait]
+pub trait BassinetNftRepository: Send + Sync {
+    /// 根据collection_id获取NFT信息
+    async fn get_nft_by_collection_id(&self, collection_id: &Uuid) -> Result<Option<bassinet_nft::Model>, anyhow::Error>;
 
-    if nfts.is_empty() {
-        return Option::None
-    }
-    let nft = nfts.get(0).unwrap().clone();
-    Some(nft)
+    /// 根据collection_id集获取NFT信息,按collection_id索引
+    async fn get_nft_by_collection_ids(&self, collection_ids: &[Uuid]) -> Result<HashMap<Uuid, bassinet_nft::Model>, anyhow::Error>;
+
+    /// 所有已发布的NFT,链上索引使用
+    async fn get_all_nfts(&self) -> Result<Vec<bassinet_nft::Model>, anyhow::Error>;
+
+    /// 新增NFT
+    async fn add_nft(&self, nft: bassinet_nft::Model) -> Result<(), anyhow::Error>;
+
+    /// 记录元数据在IPFS上的CID
+    async fn set_metadata_ipfs(&self, nft_id: &Uuid, cid: &str) -> Result<(), anyhow::Error>;
 }
 
-// /// 根据collection_id集获取nft集
-// pub async fn get_nft_by_collection_ids(collection_ids: &Vec<String>) -> Result<Vec<bassinet_nft::Model>, anyhow::Error> {
-//     let collections = bassinet_nft::Model::find_by_statement(
-//         Statement::from_sql_and_values(
-//             DbBackend::Postgres, 
-//             r#"SELECT * FROM "bassinet_nft WHERE "collection_id" in ($1)"#, 
-//             [collection_ids.clone().into()],
-//         )
-//     ).all(get_db().as_ref()).await?;
-//     Ok(collections)
-// }
+#[async_trait]
+impl BassinetNftRepository for DatabaseConnection {
+    async fn get_nft_by_collection_id(&self, collection_id: &Uuid) -> Result<Option<bassinet_nft::Model>, anyhow::Error> {
+        let nft = BassinetNft::find().filter(bassinet_nft::Column::CollectionId.eq(*collection_id))
+        .one(self).await?;
+        Ok(nft)
+    }
+
+    async fn get_nft_by_collection_ids(&self, collection_ids: &[Uuid]) -> Result<HashMap<Uuid, bassinet_nft::Model>, anyhow::Error> {
+        if collection_ids.is_empty() {
+            return Ok(HashMap::new())
+        }
+        let nfts = BassinetNft::find().filter(bassinet_nft::Column::CollectionId.is_in(collection_ids.iter().copied()))
+        .all(self).await?;
+        Ok(nfts.into_iter().map(|nft| (nft.collection_id, nft)).collect())
+    }
+
+    async fn get_all_nfts(&self) -> Result<Vec<bassinet_nft::Model>, anyhow::Error> {
+        let nfts = BassinetNft::find().all(self).await?;
+        Ok(nfts)
+    }
+
+    async fn add_nft(&self, nft: bassinet_nft::Model) -> Result<(), anyhow::Error> {
+        bassinet_nft::ActiveModel::from(nft).reset_all().insert(self).await?;
+        Ok(())
+    }
+
+    async fn set_metadata_ipfs(&self, nft_id: &Uuid, cid: &str) -> Result<(), anyhow::Error> {
+        BassinetNft::update_many()
+        .col_expr(bassinet_nft::Column::MetadataIpfs, Expr::value(cid))
+        .filter(bassinet_nft::Column::Id.eq(*nft_id))
+        .exec(self)
+        .await?;
+        Ok(())
+    }
+}
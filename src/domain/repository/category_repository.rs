@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use uuid::Uuid;
+
+use crate::domain::model::entity::{category, prelude::Category};
+
+/// 平台分类存储
+#[async_trait]
+pub trait CategoryRepository: Send + Sync {
+    /// 所有有效分类
+    async fn get_categories(&self) -> Result<Vec<category::Model>, anyhow::Error>;
+
+    /// 根据id获取有效分类
+    async fn get_category_by_id(&self, category_id: &Uuid) -> Result<Option<category::Model>, anyhow::Error>;
+}
+
+#[async_trait]
+impl CategoryRepository for DatabaseConnection {
+    async fn get_categories(&self) -> Result<Vec<category::Model>, anyhow::Error> {
+        let categories = Category::find().filter(category::Column::Status.eq(1))
+        .order_by_asc(category::Column::Seq)
+        .all(self)
+        .await?;
+        Ok(categories)
+    }
+
+    async fn get_category_by_id(&self, category_id: &Uuid) -> Result<Option<category::Model>, anyhow::Error> {
+        let category = Category::find_by_id(*category_id).filter(category::Column::Status.eq(1))
+        .one(self)
+        .await?;
+        Ok(category)
+    }
+}
@@ -1,26 +1,39 @@
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use async_trait::async_trait;
+use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
 
-use crate::{domain::model::entity::{chunk_list::{self}, prelude::ChunkList}, infrastructure::database_connection::{self, get_db}};
+use crate::domain::model::entity::{chunk_list::{self}, prelude::ChunkList};
 
-/// 新增chunk_list
-pub async fn add_chunk_list(chunk_list: chunk_list::ActiveModel) -> Result<(), anyhow::Error> {
-    chunk_list.insert(database_connection::get_db().as_ref()).await?;
-    Ok(())
-}
+/// 视频分片存储
+#[async_trait]
+pub trait ChunkListRepository: Send + Sync {
+    /// 新增chunk_list,id由数据库生成
+    async fn add_chunk_list(&self, chunk_list: chunk_list::Model) -> Result<(), anyhow::Error>;
+
+    /// 某md5的chunk list
+    async fn query_chunk_list(&self, md5: &String) -> Result<Vec<chunk_list::Model>, anyhow::Error>;
 
-/// 某md5的chunk list
-pub async fn query_chunk_list(md5: &String) -> Vec<chunk_list::Model> {
-    ChunkList::find().filter(chunk_list::Column::FileHash.eq(md5))
-    .order_by_asc(chunk_list::Column::ChunkNumber)
-    .all(get_db().as_ref()).await.unwrap()
+    async fn get_chunk(&self, md5: &String, chunk_number: i32) -> Result<Option<chunk_list::Model>, anyhow::Error>;
 }
 
-pub async fn get_chunk(md5: &String, chunk_number: i32) -> Option<chunk_list::Model> {
-    let chunks = ChunkList::find().filter(chunk_list::Column::FileHash.eq(md5).and(chunk_list::Column::ChunkNumber.eq(chunk_number)))
-    .all(get_db().as_ref()).await.unwrap();
+#[async_trait]
+impl ChunkListRepository for DatabaseConnection {
+    async fn add_chunk_list(&self, chunk_list: chunk_list::Model) -> Result<(), anyhow::Error> {
+        let mut chunk_list = chunk_list::ActiveModel::from(chunk_list).reset_all();
+        chunk_list.id = NotSet;
+        chunk_list.insert(self).await?;
+        Ok(())
+    }
+
+    async fn query_chunk_list(&self, md5: &String) -> Result<Vec<chunk_list::Model>, anyhow::Error> {
+        let chunks = ChunkList::find().filter(chunk_list::Column::FileHash.eq(md5))
+        .order_by_asc(chunk_list::Column::ChunkNumber)
+        .all(self).await?;
+        Ok(chunks)
+    }
 
-    if chunks.is_empty() {
-        return None
+    async fn get_chunk(&self, md5: &String, chunk_number: i32) -> Result<Option<chunk_list::Model>, anyhow::Error> {
+        let chunk = ChunkList::find().filter(chunk_list::Column::FileHash.eq(md5).and(chunk_list::Column::ChunkNumber.eq(chunk_number)))
+        .one(self).await?;
+        Ok(chunk)
     }
-    Some(chunks.get(0).unwrap().clone())
-}
\ No newline at end of file
+}
@@ -1,57 +1,177 @@
-use anyhow::Ok;
-use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
+use async_trait::async_trait;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait, sea_query::Expr};
 use uuid::Uuid;
-use crate::{domain::model::entity::{collection, collection_item, prelude::{Collection, CollectionItem}}, infrastructure::database_connection::{self, get_db}};
+use crate::domain::{model::{entity::{collection, collection_item, prelude::{Collection, CollectionItem}}, valueobject::page::{PageCursor, PageRequest, PageResult}}, repository::tag_repository, service::access_policy};
 
-/// 根据collection_id获取专辑 
-pub async fn get_by_id(collection_id: &String) -> Option<collection::Model> {
-    Collection::find_by_id(Uuid::parse_str(&collection_id).unwrap()).one(database_connection::get_db().as_ref()).await.unwrap()
+/// 专辑及专辑项存储
+#[async_trait]
+pub trait CollectionRepository: Send + Sync {
+    /// 根据collection_id获取专辑,id格式错误视为不存在
+    async fn get_by_id(&self, collection_id: &String) -> Result<Option<collection::Model>, anyhow::Error>;
+
+    /// 根据collection_id和author_id获取我的专辑
+    async fn get_my_collection_by_id(&self, collection_id: &String, author_id: &Uuid) -> Result<Option<collection::Model>, anyhow::Error> {
+        let collection = self.get_by_id(collection_id).await?;
+        Ok(collection.filter(|collection| collection.author == *author_id && collection.status == 1))
+    }
+
+    /// 根据Author获取专辑列表(作者本人使用,包括未公开的)
+    async fn get_by_author(&self, author_id: &Uuid) -> Result<Vec<collection::Model>, anyhow::Error>;
+
+    /// 按创建时间倒序分页查询某作者的专辑,有游标时从游标之后开始
+    /// public_only时只包括匿名可见的专辑
+    async fn page_collections(&self, author_id: &Uuid, public_only: bool, request: &PageRequest) -> Result<PageResult<collection::Model>, anyhow::Error>;
+
+    /// 创建专辑及其标签,标签需已规范化
+    async fn create_collection(&self, collection: collection::Model, tags: &Vec<String>) -> Result<(), anyhow::Error>;
+
+    /// 根据title搜索我的专辑
+    async fn search_collection_by(&self, title: &String, author_id: &Uuid) -> Result<Vec<collection::Model>, anyhow::Error>;
+
+    /// 专辑已上架NFT
+    async fn mark_listed(&self, collection_id: &Uuid) -> Result<(), anyhow::Error>;
+
+    /// 所有已上架NFT的专辑
+    async fn get_listed_collections(&self) -> Result<Vec<collection::Model>, anyhow::Error>;
+
+    /// 创建collection item
+    async fn create_collection_item(&self, collection_item: collection_item::Model) -> Result<(), anyhow::Error>;
+
+    /// 专辑所有内容
+    async fn get_items_by(&self, collection_id: &Uuid) -> Result<Vec<collection_item::Model>, anyhow::Error>;
+
+    /// 图文
+    async fn get_article_by_id(&self, article_id: &String) -> Result<Option<collection_item::Model>, anyhow::Error> {
+        let item = self.get_item_by(article_id).await?;
+        Ok(item.filter(|article| article.category == "article"))
+    }
+
+    /// 专辑项,id格式错误视为不存在
+    async fn get_item_by(&self, item_id: &String) -> Result<Option<collection_item::Model>, anyhow::Error>;
+
+    /// 记录专辑项在IPFS上的CID
+    async fn set_item_ipfs(&self, item_id: &Uuid, cid: &str) -> Result<(), anyhow::Error>;
 }
 
-/// 根据collection_id和author_id获取我的专辑
-pub async fn get_my_collection_by_id(collection_id: &String, author_id: &String) -> Option<collection::Model> {
-    let opt = Collection::find_by_id(Uuid::parse_str(&collection_id).unwrap()).one(database_connection::get_db().as_ref()).await.unwrap();
-    if opt.is_some() {
-        let collection = opt.unwrap();
-        if collection.author.eq(author_id) && collection.status == 1 {
-            return Option::Some(collection);
+#[async_trait]
+impl CollectionRepository for DatabaseConnection {
+    async fn get_by_id(&self, collection_id: &String) -> Result<Option<collection::Model>, anyhow::Error> {
+        let Ok(collection_id) = Uuid::parse_str(collection_id) else {
+            return Ok(None)
+        };
+        let collection = Collection::find_by_id(collection_id).one(self).await?;
+        Ok(collection)
+    }
+
+    async fn get_by_author(&self, author_id: &Uuid) -> Result<Vec<collection::Model>, anyhow::Error> {
+        let collections = Collection::find().filter(collection::Column::Author.eq(*author_id))
+        .filter(collection::Column::Status.eq(1))
+        .order_by_desc(collection::Column::CreatedTime)
+        .all(self)
+        .await?;
+        Ok(collections)
+    }
+
+    async fn page_collections(&self, author_id: &Uuid, public_only: bool, request: &PageRequest) -> Result<PageResult<collection::Model>, anyhow::Error> {
+        let condition = if public_only {
+            access_policy::public_collection_condition()
+        } else {
+            Condition::all()
+        }.add(collection::Column::Author.eq(*author_id));
+        let total = if request.with_total {
+            Some(Collection::find().filter(condition.clone()).count(self).await?)
+        } else {
+            None
+        };
+        let mut query = Collection::find().filter(condition);
+        if let Some(cursor) = &request.cursor {
+            query = query.filter(after_cursor(cursor));
         }
+        let rows = query.order_by_desc(collection::Column::CreatedTime)
+        .order_by_desc(collection::Column::Id)
+        .offset(request.offset())
+        .limit(request.limit + 1)
+        .all(self)
+        .await?;
+        Ok(PageResult::from_rows(rows, request, total, |collection| PageCursor::new(None, collection.created_time, collection.id)))
     }
-    Option::None
-}
 
-/// 根据Author获取专辑列表
-pub async fn get_by_author(author_id: &String) -> Vec<crate::domain::model::entity::collection::Model> {
-    let db = database_connection::get_db();
-    Collection::find().filter(collection::Column::Author.eq(author_id))
-    .filter(collection::Column::IsPublic.eq(1))
-    .order_by_desc(collection::Column::CreatedTime)
-    .all(db.as_ref())
-    .await.expect("Database error")
-}
+    async fn create_collection(&self, collection: collection::Model, tags: &Vec<String>) -> Result<(), anyhow::Error> {
+        let txn = self.begin().await?;
+        let collection_id = collection.id;
+        collection::ActiveModel::from(collection).reset_all().insert(&txn).await?;
+        tag_repository::insert_collection_tags(&txn, &collection_id, tags).await?;
+        txn.commit().await?;
+        Ok(())
+    }
 
-/// 创建专辑
-pub async fn create_collection(collection: collection::ActiveModel) -> Result<(), anyhow::Error> {
-    collection.insert(database_connection::get_db().as_ref()).await?;
-    Ok(())
-}
+    async fn search_collection_by(&self, title: &String, author_id: &Uuid) -> Result<Vec<collection::Model>, anyhow::Error> {
+        let results = Collection::find().filter(collection::Column::Author.eq(*author_id))
+        .filter(Condition::all().add(collection::Column::Title.eq(title)).add(collection::Column::Status.eq(1)))
+        .all(self)
+        .await?;
+        Ok(results)
+    }
+
+    async fn mark_listed(&self, collection_id: &Uuid) -> Result<(), anyhow::Error> {
+        Collection::update_many()
+        .col_expr(collection::Column::Listing, Expr::value(1))
+        .filter(collection::Column::Id.eq(*collection_id))
+        .exec(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_listed_collections(&self) -> Result<Vec<collection::Model>, anyhow::Error> {
+        let collections = Collection::find().filter(collection::Column::Listing.eq(1))
+        .filter(collection::Column::Status.eq(1))
+        .order_by_asc(collection::Column::CreatedTime)
+        .all(self)
+        .await?;
+        Ok(collections)
+    }
+
+    async fn create_collection_item(&self, collection_item: collection_item::Model) -> Result<(), anyhow::Error> {
+        collection_item::ActiveModel::from(collection_item).reset_all().insert(self).await?;
+        Ok(())
+    }
+
+    async fn get_items_by(&self, collection_id: &Uuid) -> Result<Vec<collection_item::Model>, anyhow::Error> {
+        let results = CollectionItem::find().filter(collection_item::Column::CollectionId.eq(*collection_id))
+        .filter(collection_item::Column::Status.eq(1))
+        .order_by_desc(collection_item::Column::CreatedTime)
+        .all(self)
+        .await?;
+        Ok(results)
+    }
+
+    async fn get_item_by(&self, item_id: &String) -> Result<Option<collection_item::Model>, anyhow::Error> {
+        let Ok(item_id) = Uuid::parse_str(item_id) else {
+            return Ok(None)
+        };
+        let item = CollectionItem::find_by_id(item_id).one(self).await?;
+        Ok(item)
+    }
 
-/// 根据title搜索我的专辑
-pub async fn search_collection_by(title: &String, author_id: &String) -> Result<Vec<crate::domain::model::entity::collection::Model>, anyhow::Error> {
-    let results = Collection::find().filter(collection::Column::Author.eq(author_id))
-    .filter(Condition::all().add(collection::Column::Title.eq(title)).add(collection::Column::Status.eq(1)))
-    .all(database_connection::get_db().as_ref())
-    .await;
-    if results.is_err() {
-        return Err(results.err().unwrap().into());
+    async fn set_item_ipfs(&self, item_id: &Uuid, cid: &str) -> Result<(), anyhow::Error> {
+        CollectionItem::update_many()
+        .col_expr(collection_item::Column::Ipfs, Expr::value(cid))
+        .filter(collection_item::Column::Id.eq(*item_id))
+        .exec(self)
+        .await?;
+        Ok(())
     }
-    Ok(results.unwrap())
 }
 
-/// 创建collection item
-pub async fn create_collection_item(collection_item: collection_item::ActiveModel) -> Result<(), anyhow::Error> {
-    collection_item.insert(database_connection::get_db().as_ref()).await?;
-    Ok(())
+/// 排在游标之后的专辑(created_time, id倒序)
+fn after_cursor(cursor: &PageCursor) -> Condition {
+    Condition::any()
+    .add(collection::Column::CreatedTime.lt(cursor.created_time))
+    .add(
+        Condition::all()
+        .add(collection::Column::CreatedTime.eq(cursor.created_time))
+        .add(collection::Column::Id.lt(cursor.id))
+    )
 }
 
 // /// 专辑所有图文
@@ -66,44 +186,3 @@ pub async fn create_collection_item(collection_item: collection_item::ActiveMode
 //     }
 //     Ok(results.unwrap())
 // }
-
-/// 专辑所有内容
-pub async fn get_items_by(collection_id: &String) -> Result<Vec<crate::domain::model::entity::collection_item::Model>, anyhow::Error> {
-    let results = CollectionItem::find().filter(collection_item::Column::CollectionId.eq(collection_id))
-    .filter(collection_item::Column::Status.eq(1))
-    .order_by_desc(collection_item::Column::CreatedTime)
-    .all(database_connection::get_db().as_ref())
-    .await;
-    if results.is_err() {
-        return Err(results.err().unwrap().into());
-    }
-    Ok(results.unwrap())
-}
-
-/// 图文
-pub async fn get_article_by_id(article_id: &String) -> Option<crate::domain::model::entity::collection_item::Model> {
-    let opt = CollectionItem::find_by_id(Uuid::parse_str(&article_id).unwrap()).one(database_connection::get_db().as_ref()).await.unwrap();
-    if opt.is_some() {
-        let article = opt.unwrap();
-        if article.category == "article" {
-            return Some(article);
-        }
-    }
-    Option::None
-}
-
-/// 专辑项
-pub async fn get_item_by(item_id: &String) -> Option<crate::domain::model::entity::collection_item::Model> {
-    CollectionItem::find_by_id(Uuid::parse_str(&item_id).unwrap()).one(database_connection::get_db().as_ref()).await.unwrap()
-}
-
-pub async fn get_all_collections() -> Result<Vec<collection::Model>, anyhow::Error> {
-    let collections = Collection::find().filter(Condition::any().add(collection::Column::IsPublic.eq(1)).add(collection::Column::Listing.eq(1)))
-    .order_by_desc(collection::Column::CreatedTime)
-    .all(get_db().as_ref())
-    .await;
-    if collections.is_err() {
-        return Err(collections.err().unwrap().into())
-    }
-    Ok(collections.unwrap())
-}
\ No newline at end of file
@@ -1,14 +1,48 @@
-use sea_orm::ActiveModelTrait;
+use async_trait::async_trait;
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use uuid::Uuid;
 
-use crate::{domain::model::entity::file_entity, infrastructure::database_connection};
+use crate::domain::model::entity::{file_entity, prelude::FileEntity};
 
-/// 添加文件
-pub async fn add_file(file_entity: file_entity::ActiveModel) -> Result<(), anyhow::Error> {
-    file_entity.insert(database_connection::get_db().as_ref()).await?;
-    Ok(())
+/// 上传文件存储
+#[async_trait]
+pub trait FileRepository: Send + Sync {
+    /// 添加文件
+    async fn add_file(&self, file_entity: file_entity::Model) -> Result<(), anyhow::Error>;
+
+    /// 根据存储路径获取文件,如上传的专辑封面{uuid}.{ext}
+    async fn get_file_by_path(&self, path: &str) -> Result<Option<file_entity::Model>, anyhow::Error>;
+
+    /// 记录文件在IPFS上的CID
+    async fn set_file_ipfs(&self, file_id: &Uuid, cid: &str) -> Result<(), anyhow::Error>;
+}
+
+#[async_trait]
+impl FileRepository for DatabaseConnection {
+    async fn add_file(&self, file_entity: file_entity::Model) -> Result<(), anyhow::Error> {
+        file_entity::ActiveModel::from(file_entity).reset_all().insert(self).await?;
+        Ok(())
+    }
+
+    async fn get_file_by_path(&self, path: &str) -> Result<Option<file_entity::Model>, anyhow::Error> {
+        let file = FileEntity::find().filter(file_entity::Column::Path.eq(path))
+        .filter(file_entity::Column::Status.eq(1))
+        .one(self)
+        .await?;
+        Ok(file)
+    }
+
+    async fn set_file_ipfs(&self, file_id: &Uuid, cid: &str) -> Result<(), anyhow::Error> {
+        FileEntity::update_many()
+        .col_expr(file_entity::Column::Ipfs, Expr::value(cid))
+        .filter(file_entity::Column::Id.eq(*file_id))
+        .exec(self)
+        .await?;
+        Ok(())
+    }
 }
 
 // /// 更新文件
 // pub async fn update_file(file_entity: file_entity::ActiveModel) -> Result<(), anyhow::Error> {
 //     todo!("更新文件");
-// }
\ No newline at end of file
+// }
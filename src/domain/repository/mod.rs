@@ -4,4 +4,8 @@ pub mod file_repository;
 pub mod bassinet_coin_repository;
 pub mod bassinet_nft_repository;
 pub mod media_repository;
-pub mod chunk_list_repository;
\ No newline at end of file
+pub mod chunk_list_repository;
+pub mod search_repository;
+pub mod category_repository;
+pub mod tag_repository;
+pub mod nft_token_repository;
\ No newline at end of file
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use chrono::Local;
+use sea_orm::{sea_query::OnConflict, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait};
+use uuid::Uuid;
+
+use crate::domain::{model::{entity::{nft_event_cursor, nft_token, prelude::{NftEventCursor, NftToken}}, valueobject::nft_event::{EventCursor, EventStream, NftEvent}}, service::nft_ownership};
+
+/// 链上索引的NFT及持有者
+#[async_trait]
+pub trait NftTokenRepository: Send + Sync {
+    /// package_id的某类事件已索引到的位置
+    async fn get_event_cursor(&self, package_id: &str, stream: EventStream) -> Result<Option<EventCursor>, anyhow::Error>;
+
+    /// 在一个事务中应用一页事件并保存游标,返回更新的NFT数量
+    async fn apply_nft_events(&self, package_id: &str, collection_id: &Uuid, stream: EventStream, events: &[NftEvent], cursor: &EventCursor) -> Result<usize, anyhow::Error>;
+
+    /// owner是否持有package_id的任意一个未销毁的NFT
+    async fn owns_token(&self, owner: &str, package_id: &str) -> Result<bool, anyhow::Error>;
+
+    /// package_id中持有者待RPC确认的NFT
+    async fn get_unresolved_tokens(&self, package_id: &str) -> Result<Vec<nft_token::Model>, anyhow::Error>;
+
+    /// 保存确认持有者后的NFT
+    async fn update_token(&self, token: nft_token::Model) -> Result<(), anyhow::Error>;
+}
+
+#[async_trait]
+impl NftTokenRepository for DatabaseConnection {
+    async fn get_event_cursor(&self, package_id: &str, stream: EventStream) -> Result<Option<EventCursor>, anyhow::Error> {
+        let cursor = NftEventCursor::find_by_id((package_id.to_owned(), stream.name().to_owned())).one(self).await?;
+        Ok(cursor.map(|cursor| EventCursor { tx_digest: cursor.tx_digest, event_seq: cursor.event_seq as u64 }))
+    }
+
+    async fn apply_nft_events(&self, package_id: &str, collection_id: &Uuid, stream: EventStream, events: &[NftEvent], cursor: &EventCursor) -> Result<usize, anyhow::Error> {
+        let txn = self.begin().await?;
+        let mut updated = 0;
+        for event in events {
+            let token = NftToken::find_by_id(event.object_id.clone()).one(&txn).await?;
+            let exists = token.is_some();
+            let Some(token) = nft_ownership::apply(token, package_id, *collection_id, event) else {
+                continue
+            };
+            let token = nft_token::ActiveModel::from(token).reset_all();
+            if exists {
+                token.update(&txn).await?;
+            } else {
+                token.insert(&txn).await?;
+            }
+            updated += 1;
+        }
+        let cursor = nft_event_cursor::ActiveModel {
+            package_id: Set(package_id.to_owned()),
+            stream: Set(stream.name().to_owned()),
+            tx_digest: Set(cursor.tx_digest.clone()),
+            event_seq: Set(cursor.event_seq as i64),
+            updated_time: Set(Local::now().naive_utc()),
+        };
+        NftEventCursor::insert(cursor)
+        .on_conflict(
+            OnConflict::columns([nft_event_cursor::Column::PackageId, nft_event_cursor::Column::Stream])
+            .update_columns([nft_event_cursor::Column::TxDigest, nft_event_cursor::Column::EventSeq, nft_event_cursor::Column::UpdatedTime])
+            .to_owned()
+        )
+        .exec_without_returning(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(updated)
+    }
+
+    async fn owns_token(&self, owner: &str, package_id: &str) -> Result<bool, anyhow::Error> {
+        let token = NftToken::find()
+        .filter(nft_token::Column::Owner.eq(owner))
+        .filter(nft_token::Column::PackageId.eq(package_id))
+        .filter(nft_token::Column::Status.eq(1))
+        .one(self)
+        .await?;
+        Ok(token.is_some())
+    }
+
+    async fn get_unresolved_tokens(&self, package_id: &str) -> Result<Vec<nft_token::Model>, anyhow::Error> {
+        let tokens = NftToken::find()
+        .filter(nft_token::Column::PackageId.eq(package_id))
+        .filter(nft_token::Column::OwnerUnresolved.eq(1))
+        .all(self)
+        .await?;
+        Ok(tokens)
+    }
+
+    async fn update_token(&self, token: nft_token::Model) -> Result<(), anyhow::Error> {
+        nft_token::ActiveModel::from(token).reset_all().update(self).await?;
+        Ok(())
+    }
+}
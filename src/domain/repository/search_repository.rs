@@ -0,0 +1,261 @@
+use async_trait::async_trait;
+use sea_orm::{prelude::DateTime, DatabaseConnection, DbBackend, FromQueryResult, Statement, Value};
+use uuid::Uuid;
+
+use crate::domain::model::valueobject::page::{PageCursor, PageRequest, PageResult};
+
+/// 全文检索配置,见migration/m20261019_000003_full_text_search
+const SEARCH_CONFIG: &str = "bassinet_search";
+
+/// 专辑检索条件
+#[derive(Debug, Default)]
+pub struct CollectionSearchCriteria {
+    pub keyword: Option<String>,
+    pub author: Option<Uuid>,
+    // 包含某类专辑项,article,video
+    pub category: Option<String>,
+    // 平台分类
+    pub category_id: Option<Uuid>,
+    pub tag: Option<String>,
+    // 是否已上架NFT
+    pub listed: Option<bool>,
+    pub created_from: Option<DateTime>,
+    pub created_to: Option<DateTime>,
+}
+
+/// 专辑检索结果
+#[derive(Debug, FromQueryResult)]
+pub struct CollectionSearchRow {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub created_time: DateTime,
+    pub is_public: i32,
+    pub listing: Option<i32>,
+    pub icon_url: Option<String>,
+    pub rank: f64,
+    pub title_highlight: String,
+    pub snippet: Option<String>,
+    // 命中的专辑项
+    pub item_id: Option<Uuid>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CountRow {
+    total: i64,
+}
+
+/// 分面统计
+#[derive(Debug, FromQueryResult)]
+pub struct FacetRow {
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub count: i64,
+}
+
+/// 检索语句的各部分
+struct SearchQuery {
+    params: SqlParams,
+    from_sql: String,
+    select_sql: String,
+    where_sql: String,
+}
+
+/// 按顺序绑定参数
+#[derive(Default)]
+struct SqlParams {
+    values: Vec<Value>,
+}
+
+impl SqlParams {
+    fn bind<V: Into<Value>>(&mut self, value: V) -> String {
+        self.values.push(value.into());
+        format!("${}", self.values.len())
+    }
+}
+
+/// LIKE参数转义
+fn like_pattern(keyword: &str) -> String {
+    let escaped = keyword.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn build_query(criteria: &CollectionSearchCriteria) -> SearchQuery {
+    let mut params = SqlParams::default();
+    let mut conditions = vec!["c.status = 1".to_owned(), "(c.is_public = 1 OR c.listing = 1)".to_owned()];
+
+    let keyword = criteria.keyword.as_ref().map(|keyword| keyword.trim()).filter(|keyword| !keyword.is_empty());
+    let (from_sql, select_sql) = if let Some(keyword) = keyword {
+        let query = params.bind(keyword);
+        let like = params.bind(like_pattern(keyword));
+        // 只在公开专辑的公开专辑项中检索内容,避免泄露需持有NFT的内容
+        let from_sql = format!(
+            r#"collection c
+            CROSS JOIN websearch_to_tsquery('{SEARCH_CONFIG}', {query}) AS q(query)
+            LEFT JOIN LATERAL (
+                SELECT ci.id AS item_id,
+                    ts_rank(ci.search_vector, q.query) AS rank,
+                    coalesce(ci.title, '') || ' ' || coalesce(ci.content, ci.description, '') AS source
+                FROM collection_item ci
+                WHERE ci.collection_id = c.id AND ci.status = 1 AND ci.is_public = 1 AND c.is_public = 1
+                    AND (ci.search_vector @@ q.query OR ci.title ILIKE {like})
+                ORDER BY rank DESC
+                LIMIT 1
+            ) i ON true"#
+        );
+        let select_sql = format!(
+            r#"(ts_rank(c.search_vector, q.query) * 2 + similarity(c.title, {query}) + coalesce(i.rank, 0))::float8 AS rank,
+            ts_headline('{SEARCH_CONFIG}', c.title, q.query, 'HighlightAll=true') AS title_highlight,
+            CASE WHEN i.item_id IS NULL
+                THEN ts_headline('{SEARCH_CONFIG}', c.description, q.query, 'MaxFragments=2, MaxWords=30, MinWords=10')
+                ELSE ts_headline('{SEARCH_CONFIG}', i.source, q.query, 'MaxFragments=2, MaxWords=30, MinWords=10')
+            END AS snippet,
+            i.item_id AS item_id"#
+        );
+        conditions.push(format!("(c.search_vector @@ q.query OR c.title ILIKE {like} OR c.description ILIKE {like} OR i.item_id IS NOT NULL)"));
+        (from_sql, select_sql)
+    } else {
+        ("collection c".to_owned(), "0::float8 AS rank, c.title AS title_highlight, NULL::text AS snippet, NULL::uuid AS item_id".to_owned())
+    };
+
+    if let Some(author) = &criteria.author {
+        conditions.push(format!("c.author = {}", params.bind(*author)));
+    }
+    if let Some(category) = &criteria.category {
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM collection_item ce WHERE ce.collection_id = c.id AND ce.status = 1 AND ce.category = {})",
+            params.bind(category.clone())
+        ));
+    }
+    if let Some(category_id) = criteria.category_id {
+        conditions.push(format!("c.category_id = {}", params.bind(category_id)));
+    }
+    if let Some(tag) = &criteria.tag {
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM collection_tag cte JOIN tag te ON te.id = cte.tag_id WHERE cte.collection_id = c.id AND te.name = {})",
+            params.bind(tag.trim().to_lowercase())
+        ));
+    }
+    match criteria.listed {
+        Some(true) => conditions.push("c.listing = 1".to_owned()),
+        Some(false) => conditions.push("coalesce(c.listing, 0) <> 1".to_owned()),
+        None => {}
+    }
+    if let Some(created_from) = criteria.created_from {
+        conditions.push(format!("c.created_time >= {}", params.bind(created_from)));
+    }
+    if let Some(created_to) = criteria.created_to {
+        conditions.push(format!("c.created_time < {}", params.bind(created_to)));
+    }
+
+    SearchQuery { params, from_sql, select_sql, where_sql: conditions.join(" AND ") }
+}
+
+/// 专辑检索
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    /// 检索公开或已上架的专辑,有关键字时按相关度排序并返回高亮片段
+    async fn search_collections(&self, criteria: &CollectionSearchCriteria, request: &PageRequest) -> Result<PageResult<CollectionSearchRow>, anyhow::Error>;
+
+    /// 检索结果按分类及标签的分面统计
+    async fn search_facets(&self, criteria: &CollectionSearchCriteria, tag_limit: u64) -> Result<(Vec<FacetRow>, Vec<FacetRow>), anyhow::Error>;
+}
+
+#[async_trait]
+impl SearchRepository for DatabaseConnection {
+    async fn search_collections(&self, criteria: &CollectionSearchCriteria, request: &PageRequest) -> Result<PageResult<CollectionSearchRow>, anyhow::Error> {
+        let SearchQuery { mut params, from_sql, select_sql, where_sql } = build_query(criteria);
+
+        let total = if request.with_total {
+            let count = CountRow::find_by_statement(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!("SELECT COUNT(*)::bigint AS total FROM {from_sql} WHERE {where_sql}"),
+                params.values.clone(),
+            )).one(self).await?;
+            Some(count.map(|count| count.total as u64).unwrap_or(0))
+        } else {
+            None
+        };
+
+        // rank是计算列,在外层按(rank, created_time, id)比较游标
+        let cursor_sql = match &request.cursor {
+            Some(cursor) => format!(
+                "WHERE (s.rank, s.created_time, s.id) < ({}::float8, {}, {})",
+                params.bind(cursor.rank.unwrap_or(0.0)),
+                params.bind(cursor.created_time),
+                params.bind(cursor.id)
+            ),
+            None => String::new(),
+        };
+        let limit_param = params.bind((request.limit + 1) as i64);
+        let offset_param = params.bind(request.offset() as i64);
+        let rows = CollectionSearchRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                r#"SELECT s.* FROM (
+                    SELECT c.id, c.title, c.description, c.created_time, c.is_public, c.listing, c.icon_url, {select_sql}
+                    FROM {from_sql}
+                    WHERE {where_sql}
+                ) s
+                {cursor_sql}
+                ORDER BY s.rank DESC, s.created_time DESC, s.id DESC
+                LIMIT {limit_param} OFFSET {offset_param}"#
+            ),
+            params.values,
+        )).all(self).await?;
+        Ok(PageResult::from_rows(rows, request, total, |row| PageCursor::new(Some(row.rank), row.created_time, row.id)))
+    }
+
+    async fn search_facets(&self, criteria: &CollectionSearchCriteria, tag_limit: u64) -> Result<(Vec<FacetRow>, Vec<FacetRow>), anyhow::Error> {
+        let SearchQuery { mut params, from_sql, where_sql, .. } = build_query(criteria);
+
+        let categories = FacetRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                r#"SELECT cat.id AS id, cat.name AS name, COUNT(*)::bigint AS count
+                FROM {from_sql}
+                JOIN category cat ON cat.id = c.category_id
+                WHERE {where_sql}
+                GROUP BY cat.id, cat.name, cat.seq
+                ORDER BY cat.seq ASC"#
+            ),
+            params.values.clone(),
+        )).all(self).await?;
+
+        let limit_param = params.bind(tag_limit as i64);
+        let tags = FacetRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                r#"SELECT NULL::uuid AS id, t.name AS name, COUNT(*)::bigint AS count
+                FROM {from_sql}
+                JOIN collection_tag ct ON ct.collection_id = c.id
+                JOIN tag t ON t.id = ct.tag_id
+                WHERE {where_sql}
+                GROUP BY t.name
+                ORDER BY count DESC, t.name ASC
+                LIMIT {limit_param}"#
+            ),
+            params.values,
+        )).all(self).await?;
+        Ok((categories, tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{like_pattern, SqlParams};
+
+    #[test]
+    fn test_like_pattern() {
+        assert_eq!(like_pattern("rust"), "%rust%");
+        assert_eq!(like_pattern("100%_a\\b"), "%100\\%\\_a\\\\b%");
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut params = SqlParams::default();
+        assert_eq!(params.bind("a"), "$1");
+        assert_eq!(params.bind(1i64), "$2");
+        assert_eq!(params.values.len(), 2);
+    }
+}
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use chrono::Local;
+use sea_orm::{sea_query::OnConflict, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult, QueryFilter, QueryOrder, Statement, TransactionTrait};
+use uuid::Uuid;
+
+use crate::domain::model::entity::{collection, collection_tag, prelude::{Collection, CollectionTag, Tag}, tag};
+
+/// 标签及使用次数
+#[derive(Debug, FromQueryResult)]
+pub struct TagCount {
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RelatedRow {
+    id: Uuid,
+}
+
+/// 标签存储
+#[async_trait]
+pub trait TagRepository: Send + Sync {
+    /// 专辑的标签
+    async fn get_tags_by_collection(&self, collection_id: &Uuid) -> Result<Vec<String>, anyhow::Error>;
+
+    /// 设置专辑标签(覆盖原有标签),标签需已规范化
+    async fn set_collection_tags(&self, collection_id: &Uuid, names: &Vec<String>) -> Result<(), anyhow::Error>;
+
+    /// 公开专辑中的常用标签,可按前缀过滤
+    async fn popular_tags(&self, prefix: Option<String>, limit: u64) -> Result<Vec<TagCount>, anyhow::Error>;
+
+    /// 与某专辑共享标签最多的公开专辑
+    async fn related_collections(&self, collection_id: &Uuid, limit: u64) -> Result<Vec<collection::Model>, anyhow::Error>;
+}
+
+#[async_trait]
+impl TagRepository for DatabaseConnection {
+    async fn get_tags_by_collection(&self, collection_id: &Uuid) -> Result<Vec<String>, anyhow::Error> {
+        let tag_ids: Vec<Uuid> = CollectionTag::find().filter(collection_tag::Column::CollectionId.eq(*collection_id))
+        .all(self)
+        .await?
+        .into_iter()
+        .map(|collection_tag| collection_tag.tag_id)
+        .collect();
+        if tag_ids.is_empty() {
+            return Ok(Vec::new())
+        }
+        let tags = Tag::find().filter(tag::Column::Id.is_in(tag_ids))
+        .order_by_asc(tag::Column::Name)
+        .all(self)
+        .await?;
+        Ok(tags.into_iter().map(|tag| tag.name).collect())
+    }
+
+    async fn set_collection_tags(&self, collection_id: &Uuid, names: &Vec<String>) -> Result<(), anyhow::Error> {
+        let txn = self.begin().await?;
+        CollectionTag::delete_many().filter(collection_tag::Column::CollectionId.eq(*collection_id))
+        .exec(&txn)
+        .await?;
+        insert_collection_tags(&txn, collection_id, names).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn popular_tags(&self, prefix: Option<String>, limit: u64) -> Result<Vec<TagCount>, anyhow::Error> {
+        let prefix = prefix.map(|prefix| prefix.trim().to_lowercase()).unwrap_or_default();
+        let pattern = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_") + "%";
+        let tags = TagCount::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT t.name, COUNT(*)::bigint AS count
+            FROM tag t
+            JOIN collection_tag ct ON ct.tag_id = t.id
+            JOIN collection c ON c.id = ct.collection_id
+            WHERE c.status = 1 AND (c.is_public = 1 OR c.listing = 1) AND t.name LIKE $1
+            GROUP BY t.name
+            ORDER BY count DESC, t.name ASC
+            LIMIT $2"#,
+            [pattern.into(), (limit as i64).into()],
+        )).all(self).await?;
+        Ok(tags)
+    }
+
+    async fn related_collections(&self, collection_id: &Uuid, limit: u64) -> Result<Vec<collection::Model>, anyhow::Error> {
+        let rows = RelatedRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT c.id
+            FROM collection_tag ct
+            JOIN collection_tag other ON other.tag_id = ct.tag_id AND other.collection_id <> ct.collection_id
+            JOIN collection c ON c.id = other.collection_id
+            WHERE ct.collection_id = $1 AND c.status = 1 AND (c.is_public = 1 OR c.listing = 1)
+            GROUP BY c.id
+            ORDER BY COUNT(*) DESC, MAX(c.created_time) DESC
+            LIMIT $2"#,
+            [(*collection_id).into(), (limit as i64).into()],
+        )).all(self).await?;
+        if rows.is_empty() {
+            return Ok(Vec::new())
+        }
+        let ids: Vec<Uuid> = rows.into_iter().map(|row| row.id).collect();
+        let mut collections = Collection::find().filter(collection::Column::Id.is_in(ids.clone()))
+        .all(self)
+        .await?;
+        // 保持共享标签数的排序
+        collections.sort_by_key(|collection| ids.iter().position(|id| *id == collection.id));
+        Ok(collections)
+    }
+}
+
+/// 为专辑添加标签,不存在的标签同时创建,在调用方的事务中执行
+pub(crate) async fn insert_collection_tags(db: &impl ConnectionTrait, collection_id: &Uuid, names: &Vec<String>) -> Result<(), anyhow::Error> {
+    if names.is_empty() {
+        return Ok(())
+    }
+    let now = Local::now().naive_utc();
+    let tags = names.iter().map(|name| tag::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(name.clone()),
+        created_time: Set(now),
+    });
+    Tag::insert_many(tags)
+    .on_conflict(OnConflict::column(tag::Column::Name).do_nothing().to_owned())
+    .exec_without_returning(db)
+    .await?;
+    let tags = Tag::find().filter(tag::Column::Name.is_in(names.clone()))
+    .all(db)
+    .await?;
+    let collection_tags = tags.into_iter().map(|tag| collection_tag::ActiveModel {
+        collection_id: Set(*collection_id),
+        tag_id: Set(tag.id),
+    });
+    CollectionTag::insert_many(collection_tags)
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use sea_orm::{ColumnTrait, Condition};
+
+use crate::{domain::model::entity::{collection, collection_item}, state::AppState};
+
+/// 访问者
+#[derive(Debug, Clone, Default)]
+pub struct Viewer {
+    pub account_id: Option<String>,
+    pub wallet_address: Option<String>,
+}
+
+impl Viewer {
+    pub fn new(account_id: String, wallet_address: Option<String>) -> Self {
+        Self { account_id: Some(account_id), wallet_address }
+    }
+
+    /// 匿名访问者
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    fn is_author_of(&self, collection: &collection::Model) -> bool {
+        self.account_id.as_ref().is_some_and(|account_id| *account_id == collection.author.to_string())
+    }
+}
+
+/// 访问判定结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    // 可访问
+    Granted,
+    // 持有专辑对应的NFT才可访问
+    NftRequired,
+    // 不可访问
+    Denied,
+}
+
+/// 专辑是否可见
+/// 1. 作者可见自己所有有效专辑
+/// 2. 其他人可见公开或已上架的专辑
+pub fn collection_access(viewer: &Viewer, collection: &collection::Model) -> Access {
+    if collection.status != 1 {
+        return Access::Denied
+    }
+    if viewer.is_author_of(collection) || collection.is_public == 1 || collection.listing == Some(1) {
+        return Access::Granted
+    }
+    Access::Denied
+}
+
+/// 专辑项是否可查看
+/// 1. 作者可查看自己所有有效专辑项
+/// 2. 公开专辑中的公开专辑项任何人可查看
+/// 3. 已上架专辑中的其他专辑项需持有对应NFT
+pub fn item_access(viewer: &Viewer, collection: &collection::Model, item: &collection_item::Model) -> Access {
+    if item.status != Some(1) || item.collection_id != collection.id {
+        return Access::Denied
+    }
+    if collection_access(viewer, collection) == Access::Denied {
+        return Access::Denied
+    }
+    if viewer.is_author_of(collection) {
+        return Access::Granted
+    }
+    if collection.is_public == 1 && item.is_public == 1 {
+        return Access::Granted
+    }
+    if collection.listing == Some(1) {
+        return Access::NftRequired
+    }
+    Access::Denied
+}
+
+/// 结合NFT持有情况得出最终结果
+pub fn is_permitted(access: Access, owns_nft: bool) -> bool {
+    match access {
+        Access::Granted => true,
+        Access::NftRequired => owns_nft,
+        Access::Denied => false,
+    }
+}
+
+/// 访问者是否持有专辑对应的NFT
+/// 结果在缓存中保留sui.ownership_cache_secs秒,查询失败的结果不缓存
+pub async fn owns_collection_nft(state: &AppState, viewer: &Viewer, collection: &collection::Model) -> bool {
+    let Some(wallet_address) = viewer.wallet_address.as_ref() else {
+        return false
+    };
+    if collection.listing != Some(1) {
+        return false
+    }
+    let nft = match state.bassinet_nft_repository.get_nft_by_collection_id(&collection.id).await {
+        Ok(Some(nft)) => nft,
+        Ok(None) => return false,
+        Err(err) => {
+            tracing::error!("query collection nft error:{}", err);
+            return false
+        }
+    };
+    let cache_secs = state.config.sui.ownership_cache_secs;
+    let key = ownership_key_of(&nft.package_id, wallet_address);
+    if cache_secs > 0 {
+        if let Ok(Some(owns)) = state.cache.get(&key).await {
+            return owns == "1"
+        }
+    }
+    let Some(owns) = query_ownership(state, wallet_address, &nft.package_id).await else {
+        // RPC不可用时按链上索引判断,不写入缓存,节点恢复后重新确认
+        return indexed_ownership(state, wallet_address, &nft.package_id).await
+    };
+    if cache_secs > 0 {
+        if let Err(err) = state.cache.set_ex(&key, if owns { "1" } else { "0" }, cache_secs).await {
+            tracing::error!("save nft ownership error:{}", err);
+        }
+    }
+    owns
+}
+
+/// NFT持有情况在缓存中的key
+fn ownership_key_of(package_id: &str, wallet_address: &str) -> String {
+    format!("nft_owner_{}_{}", package_id, wallet_address.to_lowercase())
+}
+
+/// 通过RPC查询持有情况,RPC查询失败时返回None
+/// 链上索引不作为依据:不经合约的public_transfer和kiosk取出没有事件,索引中可能仍是之前的持有者
+async fn query_ownership(state: &AppState, wallet_address: &String, package_id: &String) -> Option<bool> {
+    match state.nft_query.get_any_bassinet_nft_by(wallet_address, package_id).await {
+        Ok(object_id) => Some(object_id.is_some()),
+        Err(err) => {
+            tracing::error!("query bassinet nft error:{}", err);
+            None
+        }
+    }
+}
+
+/// 链上索引中的持有情况,仅在RPC不可用时使用
+async fn indexed_ownership(state: &AppState, wallet_address: &String, package_id: &String) -> bool {
+    match state.nft_token_repository.owns_token(&wallet_address.to_lowercase(), package_id).await {
+        Ok(owns) => owns,
+        Err(err) => {
+            tracing::error!("query nft token index error:{}", err);
+            false
+        }
+    }
+}
+
+/// 是否可查看专辑项,需要时查询NFT持有情况
+pub async fn can_access_item(state: &AppState, viewer: &Viewer, collection: &collection::Model, item: &collection_item::Model) -> bool {
+    let access = item_access(viewer, collection, item);
+    if access == Access::NftRequired {
+        return is_permitted(access, owns_collection_nft(state, viewer, collection).await)
+    }
+    is_permitted(access, false)
+}
+
+/// 匿名可见专辑的查询条件,与collection_access保持一致
+pub fn public_collection_condition() -> Condition {
+    Condition::all()
+    .add(collection::Column::Status.eq(1))
+    .add(
+        Condition::any()
+        .add(collection::Column::IsPublic.eq(1))
+        .add(collection::Column::Listing.eq(1))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{domain::model::entity::{collection, collection_item}, state::fakes};
+
+    use super::{collection_access, is_permitted, item_access, Access, Viewer};
+
+    const AUTHOR: Uuid = Uuid::from_u128(1);
+
+    fn collection(is_public: i32, listing: Option<i32>, status: i32) -> collection::Model {
+        collection::Model { id: Uuid::nil(), is_public, listing, status, ..fakes::collection(AUTHOR) }
+    }
+
+    fn item(is_public: i32, status: Option<i32>) -> collection_item::Model {
+        collection_item::Model { is_public, status, ..fakes::item(&collection(1, None, 1)) }
+    }
+
+    fn author() -> Viewer {
+        Viewer::new(AUTHOR.to_string(), None)
+    }
+
+    fn other() -> Viewer {
+        Viewer::new("other".to_owned(), Some("0x1".to_owned()))
+    }
+
+    #[test]
+    fn test_collection_access() {
+        let cases = [
+            // (is_public, listing, status, author, other, anonymous)
+            (1, Some(0), 1, Access::Granted, Access::Granted, Access::Granted),
+            (0, Some(1), 1, Access::Granted, Access::Granted, Access::Granted),
+            (0, Some(0), 1, Access::Granted, Access::Denied, Access::Denied),
+            (0, None, 1, Access::Granted, Access::Denied, Access::Denied),
+            (1, Some(1), 0, Access::Denied, Access::Denied, Access::Denied),
+        ];
+        for (is_public, listing, status, for_author, for_other, for_anonymous) in cases {
+            let collection = collection(is_public, listing, status);
+            assert_eq!(collection_access(&author(), &collection), for_author);
+            assert_eq!(collection_access(&other(), &collection), for_other);
+            assert_eq!(collection_access(&Viewer::anonymous(), &collection), for_anonymous);
+        }
+    }
+
+    #[test]
+    fn test_item_access() {
+        let cases = [
+            // (collection is_public, listing, item is_public, author, other)
+            (1, Some(0), 1, Access::Granted, Access::Granted),
+            (1, Some(0), 0, Access::Granted, Access::Denied),
+            (1, Some(1), 0, Access::Granted, Access::NftRequired),
+            (0, Some(1), 1, Access::Granted, Access::NftRequired),
+            (0, Some(1), 0, Access::Granted, Access::NftRequired),
+            (0, Some(0), 1, Access::Granted, Access::Denied),
+            (0, None, 0, Access::Granted, Access::Denied),
+        ];
+        for (collection_public, listing, item_public, for_author, for_other) in cases {
+            let collection = collection(collection_public, listing, 1);
+            let item = item(item_public, Some(1));
+            assert_eq!(item_access(&author(), &collection, &item), for_author);
+            assert_eq!(item_access(&other(), &collection, &item), for_other);
+            assert_eq!(item_access(&Viewer::anonymous(), &collection, &item), for_other);
+        }
+    }
+
+    #[test]
+    fn test_item_access_invalid() {
+        let collection = collection(1, Some(1), 1);
+        assert_eq!(item_access(&author(), &collection, &item(1, Some(0))), Access::Denied);
+        assert_eq!(item_access(&author(), &collection, &item(1, None)), Access::Denied);
+
+        let mut foreign = item(1, Some(1));
+        foreign.collection_id = Uuid::new_v4();
+        assert_eq!(item_access(&author(), &collection, &foreign), Access::Denied);
+
+        let removed = self::collection(1, Some(1), 0);
+        assert_eq!(item_access(&author(), &removed, &item(1, Some(1))), Access::Denied);
+    }
+
+    #[test]
+    fn test_is_permitted() {
+        assert!(is_permitted(Access::Granted, false));
+        assert!(is_permitted(Access::NftRequired, true));
+        assert!(!is_permitted(Access::NftRequired, false));
+        assert!(!is_permitted(Access::Denied, true));
+    }
+}
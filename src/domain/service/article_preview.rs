@@ -0,0 +1,32 @@
+/// 试读分隔标记,标记之前的内容为免费试读部分
+pub const PREVIEW_MARKER: &str = "<!-- more -->";
+
+/// 图文试读内容
+/// 未设置分隔标记时不提供试读
+pub fn preview(content: &str) -> &str {
+    match content.find(PREVIEW_MARKER) {
+        Some(index) => content[..index].trim_end(),
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preview;
+
+    #[test]
+    fn test_preview() {
+        let content = "# 标题\n\n免费部分\n\n<!-- more -->\n\n付费部分";
+        assert_eq!(preview(content), "# 标题\n\n免费部分");
+    }
+
+    #[test]
+    fn test_preview_without_marker() {
+        assert_eq!(preview("# 标题\n\n全部内容"), "");
+    }
+
+    #[test]
+    fn test_preview_marker_first() {
+        assert_eq!(preview("<!-- more -->付费部分"), "");
+    }
+}
\ No newline at end of file
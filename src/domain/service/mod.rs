@@ -0,0 +1,4 @@
+pub mod access_policy;
+pub mod article_preview;
+pub mod tagging;
+pub mod nft_ownership;
\ No newline at end of file
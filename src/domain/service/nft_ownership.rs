@@ -0,0 +1,157 @@
+use chrono::DateTime;
+use uuid::Uuid;
+
+use crate::domain::model::{entity::nft_token, valueobject::nft_event::{NftEvent, NftEventKind}};
+
+/// 按事件更新NFT的归属,返回需要保存的记录,事件不改变记录时返回None
+///
+/// 各类事件分别索引,到达顺序不一定是链上顺序:早于已应用事件的事件不再改变持有者,
+/// 只有铸造事件仍会补充铸造者和铸造时间;销毁后的NFT不再改变
+///
+/// 同一checkpoint中的交易时间相同,交易内序号只能比较同一交易的事件,
+/// 与已应用事件时间相同但不在同一交易时先后无法确定,仍应用该事件并标记持有者待RPC确认
+pub fn apply(token: Option<nft_token::Model>, package_id: &str, collection_id: Uuid, event: &NftEvent) -> Option<nft_token::Model> {
+    let mut token = token.unwrap_or_else(|| nft_token::Model {
+        object_id: event.object_id.clone(),
+        package_id: package_id.to_owned(),
+        collection_id,
+        owner: None,
+        kiosk_id: None,
+        listed_price: None,
+        minted_by: None,
+        minted_time: None,
+        event_time: 0,
+        event_seq: 0,
+        status: 1,
+        event_tx: String::new(),
+        owner_unresolved: 0,
+    });
+    let original = token.clone();
+    if let NftEventKind::Minted { owner } = &event.kind {
+        token.minted_by = Some(owner.clone());
+        token.minted_time = DateTime::from_timestamp_millis(event.timestamp_ms as i64).map(|time| time.naive_utc());
+    }
+    let position = (event.timestamp_ms as i64, event.id.event_seq as i64);
+    let tied = position.0 == token.event_time && event.id.tx_digest != token.event_tx;
+    if token.status == 1 && (tied || position >= (token.event_time, token.event_seq)) {
+        match &event.kind {
+            NftEventKind::Minted { owner } | NftEventKind::Transferred { to: owner } => {
+                token.owner = Some(owner.clone());
+                token.kiosk_id = None;
+                token.listed_price = None;
+            }
+            NftEventKind::Listed { kiosk_id, seller, price } => {
+                token.owner = Some(seller.clone());
+                token.kiosk_id = Some(kiosk_id.clone());
+                token.listed_price = i64::try_from(*price).ok();
+            }
+            NftEventKind::Delisted { kiosk_id } => {
+                token.kiosk_id = Some(kiosk_id.clone());
+                token.listed_price = None;
+            }
+            NftEventKind::Purchased { buyer } => {
+                token.owner = Some(buyer.clone());
+                token.kiosk_id = None;
+                token.listed_price = None;
+            }
+            NftEventKind::Burned => {
+                token.owner = None;
+                token.kiosk_id = None;
+                token.listed_price = None;
+                token.status = 0;
+            }
+        }
+        (token.event_time, token.event_seq) = position;
+        token.event_tx = event.id.tx_digest.clone();
+        if tied {
+            token.owner_unresolved = 1;
+        }
+    }
+    (token != original).then_some(token)
+}
+
+/// 用RPC查询到的当前持有地址确认持有者,NFT放在kiosk中时没有持有地址,保留按事件得到的kiosk
+pub fn resolve(mut token: nft_token::Model, owner: Option<String>) -> nft_token::Model {
+    if let Some(owner) = owner {
+        token.owner = Some(owner);
+        token.kiosk_id = None;
+        token.listed_price = None;
+    }
+    token.owner_unresolved = 0;
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::domain::model::valueobject::nft_event::{EventCursor, NftEvent, NftEventKind};
+
+    use super::{apply, resolve};
+
+    fn event(timestamp_ms: u64, kind: NftEventKind) -> NftEvent {
+        NftEvent {
+            id: EventCursor { tx_digest: format!("tx{}", timestamp_ms), event_seq: 0 },
+            object_id: "0xa1".to_owned(),
+            kind,
+            timestamp_ms,
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let collection_id = Uuid::new_v4();
+        let minted = apply(None, "0xnft", collection_id, &event(1_000, NftEventKind::Minted { owner: "0xalice".to_owned() })).unwrap();
+        assert_eq!(minted.owner.as_deref(), Some("0xalice"));
+        assert_eq!(minted.minted_by.as_deref(), Some("0xalice"));
+
+        let listed = apply(Some(minted), "0xnft", collection_id, &event(2_000, NftEventKind::Listed { kiosk_id: "0xk1".to_owned(), seller: "0xalice".to_owned(), price: 500 })).unwrap();
+        assert_eq!((listed.kiosk_id.as_deref(), listed.listed_price), (Some("0xk1"), Some(500)));
+
+        let purchased = apply(Some(listed), "0xnft", collection_id, &event(3_000, NftEventKind::Purchased { buyer: "0xbob".to_owned() })).unwrap();
+        assert_eq!(purchased.owner.as_deref(), Some("0xbob"));
+        assert_eq!(purchased.listed_price, None);
+
+        // 较早的事件不改变持有者,重复的事件不产生更新
+        assert_eq!(apply(Some(purchased.clone()), "0xnft", collection_id, &event(2_500, NftEventKind::Transferred { to: "0xcarol".to_owned() })), None);
+        assert_eq!(apply(Some(purchased.clone()), "0xnft", collection_id, &event(3_000, NftEventKind::Purchased { buyer: "0xbob".to_owned() })), None);
+
+        let burned = apply(Some(purchased), "0xnft", collection_id, &event(4_000, NftEventKind::Burned)).unwrap();
+        assert_eq!((burned.owner, burned.status), (None, 0));
+    }
+
+    #[test]
+    fn test_apply_out_of_order() {
+        // 先索引到购买事件,之后才索引到铸造事件
+        let collection_id = Uuid::new_v4();
+        let purchased = apply(None, "0xnft", collection_id, &event(3_000, NftEventKind::Purchased { buyer: "0xbob".to_owned() })).unwrap();
+        let minted = apply(Some(purchased), "0xnft", collection_id, &event(1_000, NftEventKind::Minted { owner: "0xalice".to_owned() })).unwrap();
+        assert_eq!(minted.owner.as_deref(), Some("0xbob"));
+        assert_eq!(minted.minted_by.as_deref(), Some("0xalice"));
+        assert_eq!(minted.event_time, 3_000);
+    }
+
+    #[test]
+    fn test_apply_same_checkpoint() {
+        let collection_id = Uuid::new_v4();
+        let listed = apply(None, "0xnft", collection_id, &event(1_000, NftEventKind::Listed { kiosk_id: "0xk1".to_owned(), seller: "0xalice".to_owned(), price: 500 })).unwrap();
+        assert_eq!(listed.owner_unresolved, 0);
+
+        // 同一交易中序号更大的事件按顺序应用
+        let mut same_tx = event(1_000, NftEventKind::Delisted { kiosk_id: "0xk1".to_owned() });
+        same_tx.id.event_seq = 1;
+        let delisted = apply(Some(listed.clone()), "0xnft", collection_id, &same_tx).unwrap();
+        assert_eq!((delisted.listed_price, delisted.owner_unresolved), (None, 0));
+
+        // 同一时间的另一笔交易,即使序号更小也无法判断先后
+        let mut other_tx = event(1_000, NftEventKind::Transferred { to: "0xcarol".to_owned() });
+        other_tx.id.tx_digest = "tx1000b".to_owned();
+        let transferred = apply(Some(delisted), "0xnft", collection_id, &other_tx).unwrap();
+        assert_eq!((transferred.owner.as_deref(), transferred.owner_unresolved), (Some("0xcarol"), 1));
+
+        let resolved = resolve(transferred.clone(), Some("0xalice".to_owned()));
+        assert_eq!((resolved.owner.as_deref(), resolved.kiosk_id, resolved.owner_unresolved), (Some("0xalice"), None, 0));
+        let in_kiosk = resolve(listed, None);
+        assert_eq!((in_kiosk.owner.as_deref(), in_kiosk.kiosk_id.as_deref()), (Some("0xalice"), Some("0xk1")));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::error::ApiError;
+
+/// 每个专辑最多标签数
+pub const MAX_TAGS: usize = 10;
+/// 标签最大长度(字符)
+pub const MAX_TAG_CHARS: usize = 32;
+
+/// 规范化标签: 去除首尾空白及前缀#,英文小写,去重,保持原有顺序
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, ApiError> {
+    let mut results: Vec<String> = Vec::new();
+    for tag in tags.iter() {
+        let tag = tag.trim().trim_start_matches('#').trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            return Err(ApiError::TagTooLong);
+        }
+        if tag.chars().any(|c| c.is_control() || c == ',') {
+            return Err(ApiError::InvalidTag);
+        }
+        if !results.contains(&tag) {
+            results.push(tag);
+        }
+    }
+    if results.len() > MAX_TAGS {
+        return Err(ApiError::TooManyTags);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ApiError;
+
+    use super::{normalize_tags, MAX_TAGS, MAX_TAG_CHARS};
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let result = normalize_tags(&tags(&[" Rust ", "#rust", "", "音乐", "  "])).unwrap();
+        assert_eq!(result, tags(&["rust", "音乐"]));
+    }
+
+    #[test]
+    fn test_normalize_tags_invalid() {
+        assert!(matches!(normalize_tags(&tags(&[&"长".repeat(MAX_TAG_CHARS + 1)])), Err(ApiError::TagTooLong)));
+        assert!(matches!(normalize_tags(&tags(&["a,b"])), Err(ApiError::InvalidTag)));
+        let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{}", i)).collect();
+        assert!(matches!(normalize_tags(&too_many), Err(ApiError::TooManyTags)));
+    }
+}
\ No newline at end of file
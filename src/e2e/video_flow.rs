@@ -0,0 +1,181 @@
+use axum::http::StatusCode;
+use ed25519_dalek::SigningKey;
+use uuid::Uuid;
+
+use crate::infrastructure::messaging::account_bound_consumer;
+
+use super::{json, MultipartBody, TestApp};
+
+/// 注册 → 登录 → 创建专辑 → 分片上传 → 添加视频 → 获取viewing key并访问视频文件
+async fn video_flow(app: &TestApp) {
+    let author = SigningKey::from_bytes(&[1u8; 32]);
+    let visitor = SigningKey::from_bytes(&[2u8; 32]);
+    let pub_key = hex::encode(author.verifying_key().as_bytes());
+
+    // 注册,request id保存在缓存中
+    let payload = app.signed_payload(&author).await;
+    assert!(app.store.cache.lock().unwrap().contains_key(payload["request_id"].as_str().unwrap()));
+    let mut sign_up = payload.clone();
+    sign_up["nick_name"] = "papi".into();
+    let (status, body) = app.post_json("/signup", None, sign_up.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let (status, _) = app.post_json("/signup", None, sign_up).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // 登录
+    let (status, body) = app.post_json("/signin", None, app.signed_payload(&author).await).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = json(&body)["access_token"].as_str().unwrap().to_owned();
+    let (status, _) = app.post_json("/signin", None, app.signed_payload(&visitor).await).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 钱包绑定消息
+    let message = serde_json::json!({"address": "0x87e4", "public_key": pub_key, "success": true}).to_string();
+    account_bound_consumer::handle_message(&app.state, message.as_bytes()).await.unwrap();
+    let (status, body) = app.get("/account_info", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json(&body)["wallet_address"], "0x87e4");
+
+    // 上传图标并创建专辑
+    let request = MultipartBody::new().file("file", "icon.png", "image/png", b"not really a png").into_request("/upload_icon", &token);
+    let (status, body) = app.send(request).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let icon_path = json(&body)["path"].as_str().unwrap().to_owned();
+    let collection = serde_json::json!({
+        "title": "papi",
+        "description": "",
+        "is_public": 0,
+        "request_id": Uuid::new_v4().to_string(),
+        "icon_path": icon_path,
+        "tags": ["Video"],
+    });
+    let (status, body) = app.post_json("/my_collections", Some(&token), collection).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let collection_id = String::from_utf8(body.to_vec()).unwrap();
+
+    // 未公开专辑的封面只有作者能获取
+    let (status, body) = app.get(&format!("/collections/{}/image", collection_id), Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.to_vec(), b"not really a png");
+    let (status, _) = app.get(&format!("/collections/{}/image", collection_id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.get(&format!("/collections/{}/thumbnail", collection_id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 分片上传,分片不完整时不能合并
+    let video: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+    let md5 = Uuid::new_v4().simple().to_string();
+    let chunks: Vec<&[u8]> = video.chunks(3000).collect();
+    let file_hash = serde_json::json!({"file_hash": md5});
+    for (chunk_number, chunk) in chunks.iter().enumerate() {
+        let (status, _) = app.post_json("/merge_chunks", Some(&token), file_hash.clone()).await;
+        assert!(status.is_client_error(), "{}", status);
+        let request = MultipartBody::new()
+        .text("fileName", "clip.mp4")
+        .text("totalChunks", &chunks.len().to_string())
+        .text("chunkNumber", &chunk_number.to_string())
+        .text("chunkSize", &chunk.len().to_string())
+        .text("md5", &md5)
+        .file("chunk", "blob", "application/octet-stream", chunk)
+        .into_request("/upload_media_chunks", &token);
+        let (status, body) = app.send(request).await;
+        assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    }
+    let (status, body) = app.post_json("/check_chunks", Some(&token), file_hash.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json(&body).as_array().unwrap().len(), chunks.len());
+    let (status, body) = app.post_json("/merge_chunks", Some(&token), file_hash).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let video_path = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(video_path, format!("{}/{}.mp4", md5, md5));
+
+    // 添加视频
+    let add_video = serde_json::json!({
+        "request_id": Uuid::new_v4().to_string(),
+        "title": "clip",
+        "description": "",
+        "is_public": 0,
+        "video_path": video_path,
+        "collection_id": collection_id,
+        "file_hash": md5,
+    });
+    let (status, body) = app.post_json("/videos", Some(&token), add_video).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let (status, body) = app.get(&format!("/my_collections/{}", collection_id), Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let collection = json(&body);
+    assert_eq!(collection["tags"], serde_json::json!(["video"]));
+    let video_id = collection["items"][0]["id"].as_str().unwrap().to_owned();
+
+    // 未公开的视频只有作者能获取viewing key
+    let (status, _) = app.get(&format!("/videos/{}", video_id), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let mut sign_up = app.signed_payload(&visitor).await;
+    sign_up["nick_name"] = "visitor".into();
+    let (status, body) = app.post_json("/signup", None, sign_up).await;
+    assert_eq!(status, StatusCode::OK);
+    let visitor_token = json(&body)["access_token"].as_str().unwrap().to_owned();
+    let (status, _) = app.get(&format!("/videos/{}", video_id), Some(&visitor_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = app.get(&format!("/videos/{}", video_id), Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let url_path = json(&body)["url_path"].as_str().unwrap().to_owned();
+    let media_uri = url_path.strip_prefix(&app.state.config.server.medias_http_addr).unwrap().to_owned();
+    assert!(media_uri.contains("viewingKey="));
+
+    // 媒体服务校验viewing key
+    let (status, body) = app.get_media(&media_uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.to_vec(), video);
+    let (status, _) = app.get_media(media_uri.split('?').next().unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.get_media(&format!("{}?viewingKey={}", media_uri.split('?').next().unwrap(), Uuid::new_v4())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // viewing key只能访问签发时的视频
+    let other_md5 = Uuid::new_v4().simple().to_string();
+    let request = MultipartBody::new()
+    .text("fileName", "other.mp4")
+    .text("totalChunks", "1")
+    .text("chunkNumber", "0")
+    .text("chunkSize", "3")
+    .text("md5", &other_md5)
+    .file("chunk", "blob", "application/octet-stream", b"mp4")
+    .into_request("/upload_media_chunks", &token);
+    let (status, _) = app.send(request).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = app.post_json("/merge_chunks", Some(&token), serde_json::json!({"file_hash": other_md5})).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let other_path = String::from_utf8(body.to_vec()).unwrap();
+    let add_video = serde_json::json!({
+        "request_id": Uuid::new_v4().to_string(),
+        "title": "other",
+        "description": "",
+        "is_public": 0,
+        "video_path": other_path,
+        "collection_id": collection_id,
+        "file_hash": other_md5,
+    });
+    let (status, body) = app.post_json("/videos", Some(&token), add_video).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let viewing_key = media_uri.split_once("viewingKey=").unwrap().1;
+    let (status, _) = app.get_media(&format!("/{}?viewingKey={}", other_path, viewing_key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_video_flow_in_memory() {
+    let app = TestApp::in_memory();
+    video_flow(&app).await;
+    app.close().await;
+}
+
+/// 未设置BASSINET_TEST_DATABASE_URL时跳过
+#[tokio::test]
+async fn test_video_flow_postgres() {
+    let Some(app) = TestApp::postgres().await else {
+        return
+    };
+    video_flow(&app).await;
+    app.close().await;
+}
@@ -0,0 +1,248 @@
+use std::{fmt, io};
+
+use axum::{http::{header::RETRY_AFTER, StatusCode}, response::{IntoResponse, Response}, Json};
+use sea_orm::DbErr;
+
+use crate::interface::rest::{dto::ApiResult, i18n::{current_language, Language}};
+
+/// 接口错误
+/// 错误码一经发布不再变更,客户端据此判断错误类型,message随Accept-Language本地化
+/// 1xxx 请求参数, 2xxx 认证及账户, 3xxx 专辑内容, 4xxx 文件, 5xxx 服务端
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound,
+    MissingRequestId,
+    InvalidParameter,
+    InvalidCursor,
+    UnsupportedMediaType,
+    TagTooLong,
+    InvalidTag,
+    TooManyTags,
+    UnknownCategory,
+    UnknownAuthor,
+    MissingCredentials,
+    WrongCredentials,
+    InvalidToken,
+    TokenCreation,
+    UnknownAccount,
+    AccountExists,
+    CollectionNotFound,
+    ArticleNotFound,
+    VideoNotFound,
+    VideoForbidden,
+    DuplicateCollectionTitle,
+    InvalidIcon,
+    NftNotListed,
+    TokenNotFound,
+    FileNotFound,
+    IncompleteChunks,
+    /// 请求过于频繁,需要等待的秒数
+    TooManyRequests(u64),
+    /// 内部错误,详细信息只记录日志不返回给客户端
+    Internal(anyhow::Error),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::MissingRequestId
+            | ApiError::InvalidParameter
+            | ApiError::InvalidCursor
+            | ApiError::TagTooLong
+            | ApiError::InvalidTag
+            | ApiError::TooManyTags
+            | ApiError::UnknownCategory
+            | ApiError::UnknownAuthor
+            | ApiError::MissingCredentials
+            | ApiError::InvalidIcon => StatusCode::BAD_REQUEST,
+            ApiError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::WrongCredentials
+            | ApiError::InvalidToken
+            | ApiError::UnknownAccount => StatusCode::UNAUTHORIZED,
+            ApiError::VideoForbidden => StatusCode::FORBIDDEN,
+            ApiError::CollectionNotFound
+            | ApiError::ArticleNotFound
+            | ApiError::VideoNotFound
+            | ApiError::NftNotListed
+            | ApiError::TokenNotFound
+            | ApiError::FileNotFound => StatusCode::NOT_FOUND,
+            ApiError::AccountExists
+            | ApiError::DuplicateCollectionTitle
+            | ApiError::IncompleteChunks => StatusCode::CONFLICT,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::TokenCreation
+            | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// 稳定错误码
+    pub fn error_code(&self) -> u32 {
+        match self {
+            ApiError::NotFound => 1000,
+            ApiError::MissingRequestId => 1001,
+            ApiError::InvalidParameter => 1002,
+            ApiError::InvalidCursor => 1003,
+            ApiError::UnsupportedMediaType => 1004,
+            ApiError::TagTooLong => 1005,
+            ApiError::InvalidTag => 1006,
+            ApiError::TooManyTags => 1007,
+            ApiError::UnknownCategory => 1008,
+            ApiError::UnknownAuthor => 1009,
+            ApiError::TooManyRequests(_) => 1010,
+            ApiError::MissingCredentials => 2001,
+            ApiError::WrongCredentials => 2002,
+            ApiError::InvalidToken => 2003,
+            ApiError::TokenCreation => 2004,
+            ApiError::UnknownAccount => 2005,
+            ApiError::AccountExists => 2006,
+            ApiError::CollectionNotFound => 3001,
+            ApiError::ArticleNotFound => 3002,
+            ApiError::VideoNotFound => 3003,
+            ApiError::VideoForbidden => 3004,
+            ApiError::DuplicateCollectionTitle => 3005,
+            ApiError::InvalidIcon => 3006,
+            ApiError::NftNotListed => 3007,
+            ApiError::TokenNotFound => 3008,
+            ApiError::FileNotFound => 4001,
+            ApiError::IncompleteChunks => 4002,
+            ApiError::Internal(_) => 5000,
+        }
+    }
+
+    pub fn message(&self, language: Language) -> &'static str {
+        let (zh, en) = match self {
+            ApiError::NotFound => ("接口不存在", "Resource not found"),
+            ApiError::MissingRequestId => ("缺少request_id", "Missing request_id"),
+            ApiError::InvalidParameter => ("无效参数", "Invalid parameter"),
+            ApiError::InvalidCursor => ("无效游标", "Invalid cursor"),
+            ApiError::UnsupportedMediaType => ("不支持的文件格式", "Unsupported file format"),
+            ApiError::TagTooLong => ("标签过长", "Tag is too long"),
+            ApiError::InvalidTag => ("标签包含非法字符", "Tag contains invalid characters"),
+            ApiError::TooManyTags => ("标签数量超出限制", "Too many tags"),
+            ApiError::UnknownCategory => ("未知分类", "Unknown category"),
+            ApiError::UnknownAuthor => ("未知创作者", "Unknown author"),
+            ApiError::MissingCredentials => ("缺少凭证", "Missing credentials"),
+            ApiError::WrongCredentials => ("凭证错误", "Wrong credentials"),
+            ApiError::InvalidToken => ("无效令牌", "Invalid token"),
+            ApiError::TokenCreation => ("令牌创建失败", "Token creation error"),
+            ApiError::UnknownAccount => ("未知账户", "Unknown account"),
+            ApiError::AccountExists => ("账号已存在", "Account already exists"),
+            ApiError::CollectionNotFound => ("未知专辑", "Collection not found"),
+            ApiError::ArticleNotFound => ("未知图文", "Article not found"),
+            ApiError::VideoNotFound => ("未知视频", "Video not found"),
+            ApiError::VideoForbidden => ("无法访问该视频", "Access to this video is not allowed"),
+            ApiError::DuplicateCollectionTitle => ("专辑名称重复", "Collection title already exists"),
+            ApiError::InvalidIcon => ("请上传专辑图片文件", "Please upload a collection image"),
+            ApiError::NftNotListed => ("专辑未上架NFT", "Collection is not listed as an NFT"),
+            ApiError::TokenNotFound => ("未知NFT编号", "NFT token not found"),
+            ApiError::FileNotFound => ("未知文件", "File not found"),
+            ApiError::IncompleteChunks => ("分片不完整", "File chunks are incomplete"),
+            ApiError::TooManyRequests(_) => ("请求过于频繁,请稍后再试", "Too many requests, please try again later"),
+            ApiError::Internal(_) => ("服务器内部错误", "Internal server error"),
+        };
+        match language {
+            Language::Zh => zh,
+            Language::En => en,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Internal(err) => write!(f, "{}", err),
+            _ => f.write_str(self.message(Language::En)),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Internal(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        ApiError::Internal(err)
+    }
+}
+
+impl From<DbErr> for ApiError {
+    fn from(err: DbErr) -> Self {
+        ApiError::Internal(err.into())
+    }
+}
+
+impl From<io::Error> for ApiError {
+    fn from(err: io::Error) -> Self {
+        ApiError::Internal(err.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::Internal(err) = &self {
+            tracing::error!("{:?}", err);
+        }
+        let body = ApiResult {
+            error_code: self.error_code(),
+            message: self.message(current_language()).to_owned(),
+        };
+        let mut response = (self.status(), Json(body)).into_response();
+        if let ApiError::TooManyRequests(seconds) = self {
+            response.headers_mut().insert(RETRY_AFTER, seconds.into());
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::ApiError;
+    use crate::interface::rest::i18n::Language;
+
+    fn all_errors() -> Vec<ApiError> {
+        vec![
+            ApiError::NotFound, ApiError::MissingRequestId, ApiError::InvalidParameter, ApiError::InvalidCursor,
+            ApiError::UnsupportedMediaType, ApiError::TagTooLong, ApiError::InvalidTag, ApiError::TooManyTags,
+            ApiError::UnknownCategory, ApiError::UnknownAuthor, ApiError::MissingCredentials, ApiError::WrongCredentials,
+            ApiError::InvalidToken, ApiError::TokenCreation, ApiError::UnknownAccount, ApiError::AccountExists,
+            ApiError::CollectionNotFound, ApiError::ArticleNotFound, ApiError::VideoNotFound, ApiError::VideoForbidden,
+            ApiError::DuplicateCollectionTitle, ApiError::InvalidIcon, ApiError::NftNotListed, ApiError::TokenNotFound,
+            ApiError::FileNotFound, ApiError::IncompleteChunks,
+            ApiError::TooManyRequests(1),
+            ApiError::Internal(anyhow::anyhow!("database is down")),
+        ]
+    }
+
+    #[test]
+    fn test_error_codes_unique() {
+        let errors = all_errors();
+        let codes: HashSet<u32> = errors.iter().map(|err| err.error_code()).collect();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn test_messages_localized() {
+        for err in all_errors() {
+            assert!(!err.message(Language::Zh).is_empty());
+            assert!(!err.message(Language::En).is_empty());
+            assert_ne!(err.message(Language::Zh), err.message(Language::En));
+        }
+    }
+
+    #[test]
+    fn test_internal_error_hides_detail() {
+        let err = ApiError::Internal(anyhow::anyhow!("database is down"));
+        assert_eq!(err.error_code(), 5000);
+        assert!(!err.message(Language::En).contains("database"));
+        assert_eq!(err.to_string(), "database is down");
+    }
+}
\ No newline at end of file
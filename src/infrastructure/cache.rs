@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+/// 带过期时间的键值缓存,用于请求id和viewing key
+#[async_trait]
+pub trait Cache: Send + Sync {
+    /// 获取未过期的值
+    async fn get(&self, key: &str) -> Result<Option<String>, anyhow::Error>;
+
+    /// 写入值,seconds秒后过期
+    async fn set_ex(&self, key: &str, value: &str, seconds: u64) -> Result<(), anyhow::Error>;
+}
@@ -1,29 +1,42 @@
-use std::{sync::Arc, time::Duration};
+use std::time::Duration;
 
-use once_cell::sync::Lazy;
+use async_trait::async_trait;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
-use tokio::{runtime::Handle, task};
-
-pub static DB: Lazy<Arc<DatabaseConnection>> = Lazy::new(|| {
-    task::block_in_place(move || {
-        Handle::current().block_on(async {
-            let mut connection_options = ConnectOptions::new(std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"));
-
-            connection_options
-            .max_connections(100)
-            .min_connections(5)
-            .connect_timeout(Duration::from_secs(8))
-            .acquire_timeout(Duration::from_secs(8))
-            .idle_timeout(Duration::from_secs(8))
-            .max_lifetime(Duration::from_secs(8))
-            .sqlx_logging(false);
-
-            let connection = Database::connect(connection_options).await.expect("Can't connect to database");
-            Arc::new(connection)
-        })
-    })
-});
 
-pub fn get_db() -> Arc<DatabaseConnection> {
-    DB.clone()
-}
\ No newline at end of file
+use crate::settings::DatabaseConfig;
+
+use super::health::{HealthCheck, PoolStatus};
+
+/// 连接数据库,启动时调用一次,连接池随AppState共享
+pub async fn connect(config: &DatabaseConfig) -> Result<DatabaseConnection, anyhow::Error> {
+    let mut connection_options = ConnectOptions::new(&config.url);
+
+    connection_options
+    .max_connections(100)
+    .min_connections(5)
+    .connect_timeout(Duration::from_secs(8))
+    .acquire_timeout(Duration::from_secs(8))
+    .idle_timeout(Duration::from_secs(8))
+    .max_lifetime(Duration::from_secs(8))
+    .sqlx_logging(false);
+
+    let connection = Database::connect(connection_options).await?;
+    Ok(connection)
+}
+
+#[async_trait]
+impl HealthCheck for DatabaseConnection {
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        self.ping().await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        let pool = self.get_postgres_connection_pool();
+        Some(PoolStatus {
+            size: pool.size() as usize,
+            idle: pool.num_idle(),
+            max_size: pool.options().get_max_connections() as usize,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+
+/// 连接池使用情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    // 已建立的连接
+    pub size: usize,
+    // 其中空闲的连接
+    pub idle: usize,
+    pub max_size: usize,
+}
+
+/// 外部依赖(数据库、Redis、Sui fullnode)的可用性检查,用于readiness
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// 依赖可用时返回Ok
+    async fn check(&self) -> Result<(), anyhow::Error>;
+
+    /// 有连接池的依赖返回连接池使用情况
+    fn pool_status(&self) -> Option<PoolStatus> {
+        None
+    }
+}
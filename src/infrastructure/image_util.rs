@@ -1,7 +1,11 @@
-use std::path::Path;
-use tokio::fs;
+use std::{io::Cursor, path::Path};
 
-pub fn image_type(extension: &str) -> Option<&str>{
+use bytes::Bytes;
+use image::ImageFormat;
+
+use crate::infrastructure::storage::{self, StorageBackend, Volume};
+
+pub fn image_type(extension: &str) -> Option<&'static str>{
     let extension = extension.to_lowercase();
     return if extension == "gif" {
         Some("image/gif")
@@ -18,29 +22,62 @@ pub fn image_type(extension: &str) -> Option<&str>{
     }
 }
 
-/// 根据图片生成缩略图
-pub async fn make_thumbnail(file_path: &Path) -> Option<String> {
-    let file_stem = file_path.file_stem().unwrap();
-    let extension = file_path.extension().unwrap();
-    let parent = file_path.parent().unwrap();
-    let target = parent.join(file_stem.to_str().unwrap().to_string() + "_thumb." + extension.to_str().unwrap());
-    let image_type = image_type(extension.to_str().unwrap()).unwrap();
-    if image_type == "image/svg+xml" {
-        let result = fs::copy(file_path, &target).await;
-        if result.is_err() {
-            return None
-        }
-    }else {
-        let image = image::open(file_path);
-        if image.is_err() {
-            return Option::None
-        }
-        let thumb = image::imageops::thumbnail(&image.unwrap(), 100, 100);
-        
-        let result = thumb.save(&target);
-        if result.is_err() {
-            return Option::None
-        }
+/// 根据图片生成100x100的缩略图,svg直接使用原图
+pub fn make_thumbnail(data: &[u8], extension: &str) -> Option<Vec<u8>> {
+    if image_type(extension)? == "image/svg+xml" {
+        return Some(data.to_vec())
+    }
+    let format = ImageFormat::from_extension(extension)?;
+    let image = image::load_from_memory_with_format(data, format).ok()?;
+    let mut thumb = Cursor::new(Vec::new());
+    image.thumbnail_exact(100, 100).write_to(&mut thumb, format).ok()?;
+    Some(thumb.into_inner())
+}
+
+/// 缩略图和原图在同一目录,如{collection_id}/{file_stem}_thumb.{extension}
+pub fn thumbnail_key(key: &str) -> Option<String> {
+    let path = Path::new(key);
+    let file_stem = path.file_stem()?.to_str()?;
+    let extension = path.extension()?.to_str()?;
+    let thumb = format!("{}_thumb.{}", file_stem, extension);
+    Some(match key.rsplit_once('/') {
+        Some((parent, _)) => format!("{}/{}", parent, thumb),
+        None => thumb,
+    })
+}
+
+/// 读取原图生成缩略图并保存,返回缩略图
+pub async fn store_thumbnail(storage: &dyn StorageBackend, key: &str) -> Result<Option<Bytes>, anyhow::Error> {
+    let (Some(thumbnail_key), Some(extension)) = (thumbnail_key(key), Path::new(key).extension().and_then(|extension| extension.to_str())) else {
+        return Ok(None)
+    };
+    let Some(image) = storage::read_all(storage, Volume::Assets, key).await? else {
+        return Ok(None)
+    };
+    let Some(thumbnail) = make_thumbnail(&image, extension) else {
+        return Ok(None)
+    };
+    let thumbnail = Bytes::from(thumbnail);
+    storage::put_bytes(storage, Volume::Assets, &thumbnail_key, thumbnail.clone()).await?;
+    Ok(Some(thumbnail))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, RgbImage};
+
+    use super::{make_thumbnail, thumbnail_key};
+
+    #[test]
+    fn test_make_thumbnail() {
+        let mut png = Cursor::new(Vec::new());
+        RgbImage::new(300, 200).write_to(&mut png, ImageFormat::Png).unwrap();
+        let thumbnail = make_thumbnail(png.get_ref(), "png").unwrap();
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (100, 100));
+        assert!(make_thumbnail(b"not really a png", "png").is_none());
+        assert_eq!(thumbnail_key("7d9f/icon.png").as_deref(), Some("7d9f/icon_thumb.png"));
     }
-    Some(target.as_os_str().to_str().unwrap().to_string())
-}
\ No newline at end of file
+}
@@ -1,66 +1,71 @@
-use std::{fs::File, io::Write};
-use anyhow::anyhow;
+use async_trait::async_trait;
+use ipfs_api_backend_hyper::{request::Add, Error, IpfsApi, IpfsClient, TryFromUri};
 
-use futures::StreamExt;
-use ipfs_api_backend_hyper::{IpfsApi, IpfsClient};
+use crate::settings::IpfsConfig;
 
-#[allow(dead_code)]
-/**
- * 向ipfs添加文件，返回ipfs文件名
- */
-pub async fn add_file(file_path: &str) -> anyhow::Result<String>{
-    let client = IpfsClient::default();
-    let file = File::open(file_path).expect("could not read source file!");
+use super::storage::{self, ByteStream};
 
-    match client.add(file).await {
-        Ok(file) => {
-            eprintln!("added file: {:?}", file);
-            return Ok(file.name);
-        },
-        Err(e) => {
-            eprintln!("error adding file: {}", e);
-            return Err(anyhow!("error adding file: {}", e));
-        }
+/// 向IPFS节点添加并固定内容
+#[async_trait]
+pub trait Pinner: Send + Sync {
+    /// 添加内容并固定,返回CIDv1;相同的内容总是得到相同的CID
+    async fn add(&self, body: ByteStream<'_>) -> Result<String, anyhow::Error>;
+
+    /// 内容是否仍被节点固定
+    async fn is_pinned(&self, cid: &str) -> Result<bool, anyhow::Error>;
+}
+
+/// 配置[ipfs]中的节点
+pub struct IpfsPinner {
+    client: IpfsClient,
+}
+
+impl IpfsPinner {
+    pub fn new(config: &IpfsConfig) -> anyhow::Result<Self> {
+        Ok(Self { client: IpfsClient::from_str(&config.api_url)? })
     }
 }
 
-#[allow(dead_code)]
-/**
- * 从ipfs下载文件
- */
-pub async fn get_file(qm_hash: &str, file_path: &str) -> anyhow::Result<()> {
-    let client = IpfsClient::default();
-    let mut file = File::options().create_new(true).append(true).open(file_path).expect("can not open file");
+#[async_trait]
+impl Pinner for IpfsPinner {
+    /// add只接受同步的Read,先写入临时文件
+    async fn add(&self, body: ByteStream<'_>) -> Result<String, anyhow::Error> {
+        let (spooled, _) = storage::spool(body).await?;
+        let options = Add { pin: Some(true), cid_version: Some(1), ..Default::default() };
+        let added = self.client.add_with_options(spooled.reopen()?, options).await?;
+        Ok(added.hash)
+    }
 
-    let mut ipfs_stream = client.get(qm_hash);
-    while let Some(value) = ipfs_stream.next().await {
-        file.write_all(&value?)?;
+    async fn is_pinned(&self, cid: &str) -> Result<bool, anyhow::Error> {
+        match self.client.pin_ls(Some(cid), Some("recursive")).await {
+            Ok(pins) => Ok(!pins.keys.is_empty()),
+            Err(err) if not_pinned(&err) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
     }
-    anyhow::Ok(())
-} 
+}
+
+fn not_pinned(err: &Error) -> bool {
+    err.to_string().contains("not pinned")
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::infrastructure::ipfs::add_file;
+    use bytes::Bytes;
+    use futures::{stream, StreamExt};
 
-    use super::get_file;
+    use crate::settings::IpfsConfig;
 
-    /**
-     * added file: AddResponse { name: "QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL", hash: "QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL", size: "567727" }
-     * "QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL"
-     */
-    #[test]
-    fn test_add_file() {
-        let result = tokio_test::block_on(add_file("d:/raft.pdf"));
-        assert_eq!(result.is_ok(), true);
-    }
+    use super::{IpfsPinner, Pinner};
 
-    /**
-     * get_file("/ipfs/QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL", "d:/QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL.pdf")也支持
-     */
-    #[test]
-    fn test_get_file() {
-        let result = tokio_test::block_on(get_file("QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL", "d:/QmbxQJkxyJGbLnDqpHUJRYzgVY6g1yXp3RgpaLzBpHwdAL.pdf"));
-        assert_eq!(result.is_ok(), true);
+    #[tokio::test]
+    #[ignore = "需要本地IPFS节点"]
+    async fn test_add_and_verify() {
+        let pinner = IpfsPinner::new(&IpfsConfig::default()).unwrap();
+        let body = || stream::once(async { Ok(Bytes::from_static(b"bassinet")) }).boxed();
+        let cid = pinner.add(body()).await.unwrap();
+        assert!(cid.starts_with("bafk"), "{}", cid);
+        assert_eq!(pinner.add(body()).await.unwrap(), cid);
+        assert!(pinner.is_pinned(&cid).await.unwrap());
     }
 }
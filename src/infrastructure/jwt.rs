@@ -1,27 +1,25 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
-use axum::{extract::FromRequestParts, response::{IntoResponse, Response}, http::{StatusCode, request::Parts}, Json, RequestPartsExt};
+use axum::{extract::{FromRef, FromRequestParts, OptionalFromRequestParts}, http::{header::AUTHORIZATION, request::Parts}, RequestPartsExt};
 use jsonwebtoken::{EncodingKey, DecodingKey, Validation, decode};
-use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
-use serde_json::json;
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
 
-pub static KEYS: Lazy<Keys> = Lazy::new(|| {
-    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    Keys::new(secret.as_bytes())
-});
+use utoipa::ToSchema;
 
+use crate::error::ApiError;
+
+/// 签发和校验token的密钥,由jwt.secret生成,放在AppState中
 pub struct Keys {
     pub encoding: EncodingKey,
     pub decoding: DecodingKey,
 }
 
 impl Keys {
-    fn new(secret: &[u8]) -> Self {
+    pub fn new(secret: &[u8]) -> Self {
         Self {
             encoding: EncodingKey::from_secret(secret),
             decoding: DecodingKey::from_secret(secret),
@@ -36,25 +34,12 @@ pub struct Claims {
     pub exp: u64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthBody {
     access_token: String,
     token_type: String,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, thiserror::Error)]
-pub enum AuthError {
-    #[error("Wrong credentials")]
-    WrongCredentials,
-    #[error("Missing credentials")]
-    MissingCredentials,
-    #[error("Token creation error")]
-    TokenCreation,
-    #[error("Invalid token")]
-    InvalidToken,
-}
-
 impl Display for Claims {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "PubKey: {}", self.pubkey)
@@ -70,37 +55,43 @@ impl AuthBody {
     }
 }
 
+/// 校验并解析token
+pub fn decode_token(keys: &Keys, token: &str) -> Result<Claims, ApiError> {
+    let token_data = decode::<Claims>(token, &keys.decoding, &Validation::default())
+        .map_err(|_| ApiError::InvalidToken)?;
+    Ok(token_data.claims)
+}
+
 impl<S> FromRequestParts<S> for Claims
 where
+    Arc<Keys>: FromRef<S>,
     S: Send + Sync,
 {
-    type Rejection = AuthError;
+    type Rejection = ApiError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         // Extract the token from the authorization header
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
             .await
-            .map_err(|_| AuthError::InvalidToken)?;
+            .map_err(|_| ApiError::InvalidToken)?;
         // Decode the user data
-        let token_data = decode::<Claims>(bearer.token(), &KEYS.decoding, &Validation::default())
-            .map_err(|_| AuthError::InvalidToken)?;
-
-        Ok(token_data.claims)
+        decode_token(&Arc::<Keys>::from_ref(state), bearer.token())
     }
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AuthError::WrongCredentials => (StatusCode::UNAUTHORIZED, "Wrong credentials"),
-            AuthError::MissingCredentials => (StatusCode::BAD_REQUEST, "Missing credentials"),
-            AuthError::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error"),
-            AuthError::InvalidToken => (StatusCode::BAD_REQUEST, "Invalid token"),
-        };
-        let body = Json(json!({
-            "error": error_message,
-        }));
-        (status, body).into_response()
+/// 可选登录,未携带Authorization时为None
+impl<S> OptionalFromRequestParts<S> for Claims
+where
+    Arc<Keys>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key(AUTHORIZATION) {
+            return Ok(None)
+        }
+        <Claims as FromRequestParts<S>>::from_request_parts(parts, state).await.map(Some)
     }
 }
@@ -1,5 +1,5 @@
-use std::env;
-use tokio::time::{sleep, Duration};
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Deserialize};
 
 pub mod account_bound_consumer;
 pub mod coin_published_consumer;
@@ -35,21 +35,6 @@ pub mod nft_published_consumer;
 //     Ok(())
 // }
 
-/// Load the application configuration.
-/// Uses environment variable, but in reality it might use some other external configuration source.
-pub async fn load_config() -> Config {
-    sleep(Duration::from_millis(10)).await; // delay to simulate loading configuration
-    Config {
-        virtual_host: env::var("RABBIT_VHOST").unwrap_or("/".to_owned()),
-        host: env::var("RABBIT_HOST").unwrap_or("localhost".to_owned()),
-        password: env::var("RABBIT_PASSWORD").unwrap_or("guest".to_owned()),
-        port: env::var("RABBIT_PORT")
-            .map(|s| s.parse::<u16>().expect("can't parse RABBIT_PORT"))
-            .unwrap_or(5672),
-        username: env::var("RABBIT_USER").unwrap_or("guest".to_owned()),
-    }
-}
-
 // async fn shutdown_monitor(cfg: Arc<Config>) -> anyhow::Result<String> {
 //     // Show how tasks can share access to application config, though obviously we don't need config here right now.
 //     info!(
@@ -78,7 +63,15 @@ pub async fn load_config() -> Config {
 //     Ok("Ctrl+C".to_owned())
 // }
 
-/// Application configuration data.
+/// 解析消息体,非UTF-8或JSON格式错误时返回错误
+pub fn parse_message<T: DeserializeOwned>(content: &[u8]) -> anyhow::Result<T> {
+    let json = std::str::from_utf8(content).context("message is not valid utf-8")?;
+    serde_json::from_str(json).context("message is not a valid json payload")
+}
+
+/// RabbitMQ连接配置,对应配置文件的[rabbitmq]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub virtual_host: String,
     pub host: String,
@@ -87,8 +80,50 @@ pub struct Config {
     pub username: String,
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            virtual_host: "/".to_owned(),
+            host: "localhost".to_owned(),
+            password: "guest".to_owned(),
+            port: 5672,
+            username: "guest".to_owned(),
+        }
+    }
+}
+
 // #[derive(Error, Debug)]
 // pub enum RabbitError {
 //     #[error("RabbitMQ server connection lost: {0}")]
 //     ConnectionLost(String),
-// }
\ No newline at end of file
+// }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{account_bound_consumer::AccountBoundMessage, coin_published_consumer::CoinPublishedMessage, nft_published_consumer::NftPublishedMessage, parse_message};
+
+    #[test]
+    fn test_parse_message() {
+        let message: AccountBoundMessage = parse_message(br#"{"address": "0x87e4", "public_key": "dd27", "success": true}"#).unwrap();
+        assert!(message.success);
+        let message: AccountBoundMessage = parse_message(br#"{"address": "0x87e4", "public_key": "dd27"}"#).unwrap();
+        assert!(!message.success);
+        assert!(parse_message::<AccountBoundMessage>(br#"{"address": 1, "public_key": "dd27"}"#).is_err());
+        assert!(parse_message::<NftPublishedMessage>(br#"{"collection_id": "x", "limit": -1}"#).is_err());
+        assert!(parse_message::<CoinPublishedMessage>(&[0xff, 0xfe]).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_message_never_panics(content in proptest::collection::vec(any::<u8>(), 0..512), json in "\\{.*\\}") {
+            let _ = parse_message::<AccountBoundMessage>(&content);
+            let _ = parse_message::<CoinPublishedMessage>(&content);
+            let _ = parse_message::<NftPublishedMessage>(&content);
+            let _ = parse_message::<AccountBoundMessage>(json.as_bytes());
+            let _ = parse_message::<CoinPublishedMessage>(json.as_bytes());
+            let _ = parse_message::<NftPublishedMessage>(json.as_bytes());
+        }
+    }
+}
\ No newline at end of file
@@ -10,12 +10,12 @@ use amqprs::{
 };
 use anyhow::Context;
 use anyhow::anyhow;
-use tokio::time::{sleep, Duration};
-use tracing::{debug, error, info, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, Instrument};
 
-use crate::{application::command_service::sui_application_service};
+use crate::{application::command_service::sui_application_service, infrastructure::{metrics, telemetry}, state::AppState};
 
-use super::Config;
+use super::{parse_message, Config};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NftPublishedMessage {
@@ -34,30 +34,14 @@ pub struct NftPublishedMessage {
     pub minting_price: u64,
 }
 
-/// This function is the long-running RabbitMQ task.
-/// It starts the RabbitMQ client, and if it fails, it will attempt to reconnect.
-pub async fn nft_published_consumer(cfg: Arc<Config>) -> anyhow::Result<()> {
-    loop {
-        let result = process(cfg.clone()).await;
-        match result {
-            Ok(value) => {
-                // Not actually implemented right now.
-                warn!("exiting in response to a shutdown command");
-                return Ok(value);
-            }
-            Err(err) => {
-                error!("RabbitMQ connection returned error: {err:?}");
-                sleep(Duration::from_millis(1000)).await;
-                info!("ready to restart consumer task");
-            }
-        }
-    }
-}
+/// supervisor中的任务名
+pub const TASK: &str = "nft_published_consumer";
 
-/// RabbitMQ client task. Returns an error result if the connection is lost.
-async fn process(cfg: Arc<Config>) -> anyhow::Result<()> {
+/// 消费消息直到收到停止信号,连接断开时返回错误,由supervisor按退避间隔重启
+pub async fn nft_published_consumer(cfg: Arc<Config>, state: AppState, shutdown: CancellationToken) -> anyhow::Result<()> {
     // debug!("starting RabbitMQ task");
 
+    state.tasks.set_connected(TASK, false);
     let connection = Connection::open(
         &OpenConnectionArguments::new(&cfg.host, cfg.port, &cfg.username, &cfg.password)
             .virtual_host(&cfg.virtual_host),
@@ -113,32 +97,52 @@ async fn process(cfg: Arc<Config>) -> anyhow::Result<()> {
     // } else {
     //     Err(RabbitError::ConnectionLost("connection shut down normally. Since we don't close it ourselves, this shouldn't happen in this program".to_owned()).into())
     // }
-    let (ctag, mut rx) = channel.basic_consume_rx(consume_args).await.unwrap();
-    let new_channel = channel.clone();
-    let jh = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            let content = msg.content.unwrap();
-            let deliver = msg.deliver.unwrap();
-            let json = std::str::from_utf8(&content).unwrap();
-            info!(
-                "consume delivery {}, content: {}",
-                deliver,
-                json
-            );
-
-            let nft_info: NftPublishedMessage = serde_json::from_str(json).unwrap();
-
-            let result = sui_application_service::add_bassinet_nft(&nft_info).await;
-            if result.is_err() {
-                tracing::error!("message:{}, error:{}", json, result.err().unwrap());
-                // TODO 
-            }
-            // Ack explicitly
-            let args = BasicAckArguments::new(deliver.delivery_tag(), false);
-            new_channel.basic_ack(args).await.unwrap();
+    let (ctag, mut rx) = channel.basic_consume_rx(consume_args).await.context("failed basic_consume")?;
+    state.tasks.set_connected(TASK, true);
+    loop {
+        // 只在两条消息之间响应停止信号,处理中的消息确认后才退出
+        let msg = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            msg = rx.recv() => msg,
+        };
+        let Some(msg) = msg else {
+            state.tasks.set_connected(TASK, false);
+            let _ = channel.basic_cancel(BasicCancelArguments::new(&ctag)).await;
+            return Err(anyhow!("nft published consumer stopped"))
+        };
+        let span = telemetry::consumer_span(TASK, msg.basic_properties.as_ref().and_then(|properties| properties.headers()));
+        let (Some(content), Some(deliver)) = (msg.content, msg.deliver) else {
+            continue;
+        };
+        info!(
+            "consume delivery {}, content: {}",
+            deliver,
+            String::from_utf8_lossy(&content)
+        );
+        let result = handle_message(&state, &content).instrument(span.clone()).await;
+        metrics::consumer_message(TASK, result.is_ok());
+        if let Err(err) = result {
+            span.record("otel.status_code", "error");
+            tracing::error!("message:{}, error:{:#}", String::from_utf8_lossy(&content), err);
         }
-    });
-    assert!(jh.await.is_err());
-    channel.basic_cancel(BasicCancelArguments::new(&ctag)).await.unwrap();
-    Err(anyhow!("binding account consumer panic"))
+        // Ack explicitly, 无法处理的消息同样确认,避免反复投递
+        let args = BasicAckArguments::new(deliver.delivery_tag(), false);
+        channel.basic_ack(args).await.context("basic_ack failed")?;
+    }
+    info!("nft published consumer shutting down");
+    let _ = channel.basic_cancel(BasicCancelArguments::new(&ctag)).await;
+    let _ = channel.close().await;
+    let _ = connection.close().await;
+    Ok(())
+}
+
+/// 处理NftPublished消息
+pub(crate) async fn handle_message(state: &AppState, content: &[u8]) -> anyhow::Result<()> {
+    let nft_info: NftPublishedMessage = parse_message(content)?;
+    sui_application_service::add_bassinet_nft(state, &nft_info).await?;
+    // 由ipfs_pinner任务固定内容,不阻塞消息确认和停止
+    if state.config.ipfs.enabled {
+        state.pin_requests.notify_one();
+    }
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,50 @@
+use std::{sync::OnceLock, time::Duration};
+
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+use super::health::PoolStatus;
+
+/// 耗时类直方图的分桶(秒)
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// 安装全局的Prometheus recorder,安装前记录的指标会被丢弃,重复调用返回同一个handle
+pub fn install() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        PrometheusBuilder::new()
+        .set_buckets(LATENCY_BUCKETS)
+        .expect("buckets must not be empty")
+        .install_recorder()
+        .expect("failed to install prometheus recorder")
+    })
+}
+
+/// 接口耗时,route为匹配到的路由模板,避免按id产生过多的时间序列
+pub fn http_request(method: &str, route: &str, status: u16, elapsed: Duration) {
+    histogram!("http_request_duration_seconds", "method" => method.to_owned(), "route" => route.to_owned(), "status" => status.to_string())
+    .record(elapsed.as_secs_f64());
+}
+
+/// 上传的字节数,kind为file、icon或chunk
+pub fn upload_bytes(kind: &'static str, bytes: u64) {
+    counter!("upload_bytes_total", "kind" => kind).increment(bytes);
+}
+
+/// 合并视频分片的耗时
+pub fn chunk_merge(elapsed: Duration) {
+    histogram!("chunk_merge_duration_seconds").record(elapsed.as_secs_f64());
+}
+
+/// 消费者处理的消息数,处理失败的消息同样会被确认
+pub fn consumer_message(consumer: &'static str, success: bool) {
+    counter!("consumer_messages_total", "consumer" => consumer, "result" => if success { "ok" } else { "error" }).increment(1);
+}
+
+/// 连接池使用情况,采集指标时更新
+pub fn pool(name: &'static str, status: PoolStatus) {
+    gauge!("pool_connections", "pool" => name, "state" => "idle").set(status.idle as f64);
+    gauge!("pool_connections", "pool" => name, "state" => "in_use").set(status.size.saturating_sub(status.idle) as f64);
+    gauge!("pool_max_connections", "pool" => name).set(status.max_size as f64);
+}
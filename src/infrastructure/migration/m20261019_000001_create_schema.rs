@@ -0,0 +1,250 @@
+use sea_orm_migration::prelude::*;
+
+/// 初始表结构,已有数据库中存在的表保持不变
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+            .table(Account::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(Account::Id).uuid().not_null().primary_key())
+            .col(ColumnDef::new(Account::NickName).string())
+            .col(ColumnDef::new(Account::Avatar).string().not_null())
+            .col(ColumnDef::new(Account::PubKey).string())
+            .col(ColumnDef::new(Account::WalletAddress).string())
+            .col(ColumnDef::new(Account::CreatedTime).timestamp().not_null())
+            .col(ColumnDef::new(Account::Status).integer())
+            .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+            .table(Collection::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(Collection::Id).uuid().not_null().primary_key())
+            .col(ColumnDef::new(Collection::Title).string().not_null())
+            .col(ColumnDef::new(Collection::Description).string().not_null())
+            .col(ColumnDef::new(Collection::CreatedTime).timestamp().not_null())
+            .col(ColumnDef::new(Collection::IsPublic).integer().not_null())
+            .col(ColumnDef::new(Collection::Author).string().not_null())
+            .col(ColumnDef::new(Collection::Listing).integer())
+            .col(ColumnDef::new(Collection::IconUrl).string())
+            .col(ColumnDef::new(Collection::PackageId).string())
+            .col(ColumnDef::new(Collection::Seq).integer().not_null())
+            .col(ColumnDef::new(Collection::Status).integer().not_null())
+            .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+            .table(CollectionItem::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(CollectionItem::Id).uuid().not_null().primary_key())
+            .col(ColumnDef::new(CollectionItem::CollectionId).string().not_null())
+            .col(ColumnDef::new(CollectionItem::Seq).integer().not_null())
+            .col(ColumnDef::new(CollectionItem::Title).string())
+            .col(ColumnDef::new(CollectionItem::Description).string())
+            .col(ColumnDef::new(CollectionItem::CreatedTime).timestamp().not_null())
+            .col(ColumnDef::new(CollectionItem::IsPublic).integer().not_null())
+            .col(ColumnDef::new(CollectionItem::Author).string().not_null())
+            .col(ColumnDef::new(CollectionItem::Category).string().not_null())
+            .col(ColumnDef::new(CollectionItem::Content).text())
+            .col(ColumnDef::new(CollectionItem::Path).string())
+            .col(ColumnDef::new(CollectionItem::Hash).string())
+            .col(ColumnDef::new(CollectionItem::Ipfs).string())
+            .col(ColumnDef::new(CollectionItem::Status).integer())
+            .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+            .table(BassinetCoin::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(BassinetCoin::Id).uuid().not_null().primary_key())
+            .col(ColumnDef::new(BassinetCoin::PackageId).string().not_null().unique_key())
+            .col(ColumnDef::new(BassinetCoin::Symbol).string().not_null())
+            .col(ColumnDef::new(BassinetCoin::Name).string().not_null())
+            .col(ColumnDef::new(BassinetCoin::Description).string())
+            .col(ColumnDef::new(BassinetCoin::IconUrl).string())
+            .col(ColumnDef::new(BassinetCoin::TreasuryLockId).string().not_null())
+            .col(ColumnDef::new(BassinetCoin::AdminCapId).string().not_null())
+            .col(ColumnDef::new(BassinetCoin::AccountId).string())
+            .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+            .table(BassinetNft::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(BassinetNft::Id).uuid().not_null().primary_key())
+            .col(ColumnDef::new(BassinetNft::PackageId).string().not_null().unique_key())
+            .col(ColumnDef::new(BassinetNft::CollectionId).string().not_null().unique_key())
+            .col(ColumnDef::new(BassinetNft::Description).string())
+            .col(ColumnDef::new(BassinetNft::CollectionUrl).string())
+            .col(ColumnDef::new(BassinetNft::Limit).big_integer())
+            .col(ColumnDef::new(BassinetNft::MintingPrice).big_integer().not_null())
+            .col(ColumnDef::new(BassinetNft::RewardsQuantity).big_integer())
+            .col(ColumnDef::new(BassinetNft::MintId).string())
+            .col(ColumnDef::new(BassinetNft::PolicyId).string())
+            .col(ColumnDef::new(BassinetNft::PolicyCapId).string())
+            .col(ColumnDef::new(BassinetNft::CoinId).string())
+            .col(ColumnDef::new(BassinetNft::CoinPackageId).string())
+            .col(ColumnDef::new(BassinetNft::CoinTreasuryLockId).string())
+            .col(ColumnDef::new(BassinetNft::CoinAdminCapId).string())
+            .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+            .table(FileEntity::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(FileEntity::Id).uuid().not_null().primary_key())
+            .col(ColumnDef::new(FileEntity::ItemId).string())
+            .col(ColumnDef::new(FileEntity::Name).string().not_null())
+            .col(ColumnDef::new(FileEntity::Mime).string().not_null())
+            .col(ColumnDef::new(FileEntity::Length).integer())
+            .col(ColumnDef::new(FileEntity::Path).string())
+            .col(ColumnDef::new(FileEntity::Hash).string())
+            .col(ColumnDef::new(FileEntity::Ipfs).string())
+            .col(ColumnDef::new(FileEntity::Status).integer())
+            .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+            .table(ChunkList::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(ChunkList::Id).integer().not_null().auto_increment().primary_key())
+            .col(ColumnDef::new(ChunkList::FileHash).string().not_null())
+            .col(ColumnDef::new(ChunkList::ChunkNumber).integer().not_null())
+            .col(ColumnDef::new(ChunkList::ChunkSize).integer().not_null())
+            .col(ColumnDef::new(ChunkList::FileName).string().not_null())
+            .col(ColumnDef::new(ChunkList::TotalChunks).integer().not_null())
+            .col(ColumnDef::new(ChunkList::CreatedTime).timestamp().not_null())
+            .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ChunkList::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(FileEntity::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(BassinetNft::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(BassinetCoin::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(CollectionItem::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(Collection::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(Account::Table).if_exists().to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Account {
+    Table,
+    Id,
+    NickName,
+    Avatar,
+    PubKey,
+    WalletAddress,
+    CreatedTime,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum Collection {
+    Table,
+    Id,
+    Title,
+    Description,
+    CreatedTime,
+    IsPublic,
+    Author,
+    Listing,
+    IconUrl,
+    PackageId,
+    Seq,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum CollectionItem {
+    Table,
+    Id,
+    CollectionId,
+    Seq,
+    Title,
+    Description,
+    CreatedTime,
+    IsPublic,
+    Author,
+    Category,
+    Content,
+    Path,
+    Hash,
+    Ipfs,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum BassinetCoin {
+    Table,
+    Id,
+    PackageId,
+    Symbol,
+    Name,
+    Description,
+    IconUrl,
+    TreasuryLockId,
+    AdminCapId,
+    AccountId,
+}
+
+#[derive(DeriveIden)]
+enum BassinetNft {
+    Table,
+    Id,
+    PackageId,
+    CollectionId,
+    Description,
+    CollectionUrl,
+    Limit,
+    MintingPrice,
+    RewardsQuantity,
+    MintId,
+    PolicyId,
+    PolicyCapId,
+    CoinId,
+    CoinPackageId,
+    CoinTreasuryLockId,
+    CoinAdminCapId,
+}
+
+#[derive(DeriveIden)]
+enum FileEntity {
+    Table,
+    Id,
+    ItemId,
+    Name,
+    Mime,
+    Length,
+    Path,
+    Hash,
+    Ipfs,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum ChunkList {
+    Table,
+    Id,
+    FileHash,
+    ChunkNumber,
+    ChunkSize,
+    FileName,
+    TotalChunks,
+    CreatedTime,
+}
\ No newline at end of file
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+/// 专辑分类与标签
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            -- 平台维护的分类
+            CREATE TABLE IF NOT EXISTS category (
+                id uuid PRIMARY KEY,
+                slug varchar(64) NOT NULL UNIQUE,
+                name varchar(64) NOT NULL,
+                seq integer NOT NULL DEFAULT 1,
+                status integer NOT NULL DEFAULT 1
+            );
+
+            -- 创作者定义的标签
+            CREATE TABLE IF NOT EXISTS tag (
+                id uuid PRIMARY KEY,
+                name varchar(32) NOT NULL UNIQUE,
+                created_time timestamp NOT NULL DEFAULT now()
+            );
+
+            CREATE TABLE IF NOT EXISTS collection_tag (
+                collection_id uuid NOT NULL REFERENCES collection (id) ON DELETE CASCADE,
+                tag_id uuid NOT NULL REFERENCES tag (id) ON DELETE CASCADE,
+                PRIMARY KEY (collection_id, tag_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_collection_tag_tag_id ON collection_tag (tag_id);
+
+            ALTER TABLE collection ADD COLUMN IF NOT EXISTS category_id uuid REFERENCES category (id);
+            CREATE INDEX IF NOT EXISTS idx_collection_category_id ON collection (category_id);
+
+            INSERT INTO category (id, slug, name, seq) VALUES
+                ('6f1f4a8e-0c59-4b1e-9a36-3c1a5f7d2b01', 'art', '艺术', 1),
+                ('6f1f4a8e-0c59-4b1e-9a36-3c1a5f7d2b02', 'music', '音乐', 2),
+                ('6f1f4a8e-0c59-4b1e-9a36-3c1a5f7d2b03', 'education', '教育', 3),
+                ('6f1f4a8e-0c59-4b1e-9a36-3c1a5f7d2b04', 'technology', '科技', 4),
+                ('6f1f4a8e-0c59-4b1e-9a36-3c1a5f7d2b05', 'life', '生活', 5)
+            ON CONFLICT (slug) DO NOTHING;
+            "#
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            ALTER TABLE collection DROP COLUMN IF EXISTS category_id;
+            DROP TABLE IF EXISTS collection_tag;
+            DROP TABLE IF EXISTS tag;
+            DROP TABLE IF EXISTS category;
+            "#
+        ).await?;
+        Ok(())
+    }
+}
\ No newline at end of file
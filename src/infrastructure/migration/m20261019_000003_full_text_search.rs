@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+/// 专辑及专辑项全文检索
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+            -- 优先使用zhparser中文分词,未安装时退回simple配置(中文由pg_trgm兜底)
+            DO $$
+            BEGIN
+                IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'bassinet_search') THEN
+                    BEGIN
+                        CREATE EXTENSION IF NOT EXISTS zhparser;
+                        CREATE TEXT SEARCH CONFIGURATION bassinet_search (PARSER = zhparser);
+                        ALTER TEXT SEARCH CONFIGURATION bassinet_search ADD MAPPING FOR n,v,a,i,e,l,j WITH simple;
+                    EXCEPTION WHEN OTHERS THEN
+                        RAISE NOTICE 'zhparser not available, falling back to simple configuration';
+                        CREATE TEXT SEARCH CONFIGURATION bassinet_search (COPY = simple);
+                    END;
+                END IF;
+            END
+            $$;
+
+            ALTER TABLE collection ADD COLUMN IF NOT EXISTS search_vector tsvector
+                GENERATED ALWAYS AS (
+                    setweight(to_tsvector('bassinet_search', coalesce(title, '')), 'A') ||
+                    setweight(to_tsvector('bassinet_search', coalesce(description, '')), 'B')
+                ) STORED;
+
+            ALTER TABLE collection_item ADD COLUMN IF NOT EXISTS search_vector tsvector
+                GENERATED ALWAYS AS (
+                    setweight(to_tsvector('bassinet_search', coalesce(title, '')), 'A') ||
+                    setweight(to_tsvector('bassinet_search', coalesce(description, '')), 'B') ||
+                    setweight(to_tsvector('bassinet_search', coalesce(content, '')), 'C')
+                ) STORED;
+
+            CREATE INDEX IF NOT EXISTS idx_collection_search_vector ON collection USING GIN (search_vector);
+            CREATE INDEX IF NOT EXISTS idx_collection_item_search_vector ON collection_item USING GIN (search_vector);
+
+            CREATE INDEX IF NOT EXISTS idx_collection_title_trgm ON collection USING GIN (title gin_trgm_ops);
+            CREATE INDEX IF NOT EXISTS idx_collection_description_trgm ON collection USING GIN (description gin_trgm_ops);
+            CREATE INDEX IF NOT EXISTS idx_collection_item_title_trgm ON collection_item USING GIN (title gin_trgm_ops);
+            "#
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            DROP INDEX IF EXISTS idx_collection_item_title_trgm;
+            DROP INDEX IF EXISTS idx_collection_description_trgm;
+            DROP INDEX IF EXISTS idx_collection_title_trgm;
+            ALTER TABLE collection_item DROP COLUMN IF EXISTS search_vector;
+            ALTER TABLE collection DROP COLUMN IF EXISTS search_vector;
+            DROP TEXT SEARCH CONFIGURATION IF EXISTS bassinet_search;
+            "#
+        ).await?;
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+/// 关联字段由varchar改为uuid,并增加外键及索引
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            ALTER TABLE collection ALTER COLUMN author TYPE uuid USING author::uuid;
+            ALTER TABLE collection ADD CONSTRAINT fk_collection_author
+                FOREIGN KEY (author) REFERENCES account (id);
+            CREATE INDEX IF NOT EXISTS idx_collection_author ON collection (author, created_time DESC, id DESC);
+            CREATE INDEX IF NOT EXISTS idx_collection_created_time ON collection (created_time DESC, id DESC);
+
+            ALTER TABLE collection_item ALTER COLUMN collection_id TYPE uuid USING collection_id::uuid;
+            ALTER TABLE collection_item ADD CONSTRAINT fk_collection_item_collection
+                FOREIGN KEY (collection_id) REFERENCES collection (id) ON DELETE CASCADE;
+            CREATE INDEX IF NOT EXISTS idx_collection_item_collection_id ON collection_item (collection_id, created_time DESC);
+
+            ALTER TABLE bassinet_coin ALTER COLUMN account_id TYPE uuid USING NULLIF(account_id, '')::uuid;
+            ALTER TABLE bassinet_coin ADD CONSTRAINT fk_bassinet_coin_account
+                FOREIGN KEY (account_id) REFERENCES account (id);
+            CREATE INDEX IF NOT EXISTS idx_bassinet_coin_account_id ON bassinet_coin (account_id);
+
+            ALTER TABLE bassinet_nft ALTER COLUMN collection_id TYPE uuid USING collection_id::uuid;
+            ALTER TABLE bassinet_nft ADD CONSTRAINT fk_bassinet_nft_collection
+                FOREIGN KEY (collection_id) REFERENCES collection (id);
+            ALTER TABLE bassinet_nft ADD CONSTRAINT fk_bassinet_nft_coin
+                FOREIGN KEY (coin_package_id) REFERENCES bassinet_coin (package_id);
+            "#
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            ALTER TABLE bassinet_nft DROP CONSTRAINT IF EXISTS fk_bassinet_nft_coin;
+            ALTER TABLE bassinet_nft DROP CONSTRAINT IF EXISTS fk_bassinet_nft_collection;
+            ALTER TABLE bassinet_nft ALTER COLUMN collection_id TYPE varchar USING collection_id::text;
+
+            DROP INDEX IF EXISTS idx_bassinet_coin_account_id;
+            ALTER TABLE bassinet_coin DROP CONSTRAINT IF EXISTS fk_bassinet_coin_account;
+            ALTER TABLE bassinet_coin ALTER COLUMN account_id TYPE varchar USING account_id::text;
+
+            DROP INDEX IF EXISTS idx_collection_item_collection_id;
+            ALTER TABLE collection_item DROP CONSTRAINT IF EXISTS fk_collection_item_collection;
+            ALTER TABLE collection_item ALTER COLUMN collection_id TYPE varchar USING collection_id::text;
+
+            DROP INDEX IF EXISTS idx_collection_created_time;
+            DROP INDEX IF EXISTS idx_collection_author;
+            ALTER TABLE collection DROP CONSTRAINT IF EXISTS fk_collection_author;
+            ALTER TABLE collection ALTER COLUMN author TYPE varchar USING author::text;
+            "#
+        ).await?;
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,26 @@
+use sea_orm_migration::prelude::*;
+
+/// 记录固定到IPFS的NFT元数据
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            ALTER TABLE bassinet_nft ADD COLUMN IF NOT EXISTS metadata_ipfs varchar;
+            "#
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            ALTER TABLE bassinet_nft DROP COLUMN IF EXISTS metadata_ipfs;
+            "#
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+/// 链上索引的NFT及其当前持有者
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            -- 已铸造的BassinetNFT,event_time、event_seq和event_tx为最后一次应用的事件
+            -- owner_unresolved为1时同一时间不同交易的事件先后无法确定,持有者待RPC确认
+            CREATE TABLE IF NOT EXISTS nft_token (
+                object_id varchar PRIMARY KEY,
+                package_id varchar NOT NULL,
+                collection_id uuid NOT NULL REFERENCES collection (id),
+                owner varchar,
+                kiosk_id varchar,
+                listed_price bigint,
+                minted_by varchar,
+                minted_time timestamp,
+                event_time bigint NOT NULL,
+                event_seq bigint NOT NULL,
+                status integer NOT NULL DEFAULT 1,
+                event_tx varchar NOT NULL DEFAULT '',
+                owner_unresolved integer NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_nft_token_owner ON nft_token (owner, package_id) WHERE status = 1;
+            CREATE INDEX IF NOT EXISTS idx_nft_token_collection_id ON nft_token (collection_id);
+            CREATE INDEX IF NOT EXISTS idx_nft_token_unresolved ON nft_token (package_id) WHERE owner_unresolved = 1;
+
+            -- 每个package每类事件已索引到的位置
+            CREATE TABLE IF NOT EXISTS nft_event_cursor (
+                package_id varchar NOT NULL,
+                stream varchar(32) NOT NULL,
+                tx_digest varchar NOT NULL,
+                event_seq bigint NOT NULL,
+                updated_time timestamp NOT NULL DEFAULT now(),
+                PRIMARY KEY (package_id, stream)
+            );
+            "#
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(
+            r#"
+            DROP TABLE IF EXISTS nft_event_cursor;
+            DROP TABLE IF EXISTS nft_token;
+            "#
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use clap::Subcommand;
+use sea_orm::DatabaseConnection;
+use sea_orm_migration::prelude::*;
+
+mod m20261019_000001_create_schema;
+mod m20261019_000002_tags_and_categories;
+mod m20261019_000003_full_text_search;
+mod m20261019_000004_typed_foreign_keys;
+mod m20261019_000005_nft_metadata;
+mod m20261019_000006_nft_tokens;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261019_000001_create_schema::Migration),
+            Box::new(m20261019_000002_tags_and_categories::Migration),
+            Box::new(m20261019_000003_full_text_search::Migration),
+            Box::new(m20261019_000004_typed_foreign_keys::Migration),
+            Box::new(m20261019_000005_nft_metadata::Migration),
+            Box::new(m20261019_000006_nft_tokens::Migration),
+        ]
+    }
+}
+
+/// migrate子命令
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    /// 执行未完成的迁移
+    Up {
+        /// 执行的迁移数,默认全部
+        #[arg(short = 'n', long)]
+        steps: Option<u32>,
+    },
+    /// 回滚迁移
+    Down {
+        /// 回滚的迁移数
+        #[arg(short = 'n', long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// 查看迁移状态
+    Status,
+}
+
+pub async fn run(db: &DatabaseConnection, action: MigrateAction) -> Result<(), anyhow::Error> {
+    match action {
+        MigrateAction::Up { steps } => Migrator::up(db, steps).await?,
+        MigrateAction::Down { steps } => Migrator::down(db, Some(steps)).await?,
+        MigrateAction::Status => Migrator::status(db).await?,
+    }
+    Ok(())
+}
+
+/// 启动时执行未完成的迁移
+pub async fn migrate_pending(db: &DatabaseConnection) -> Result<(), anyhow::Error> {
+    let pending = Migrator::get_pending_migrations(db).await?;
+    if pending.is_empty() {
+        return Ok(())
+    }
+    tracing::info!("applying {} pending migrations", pending.len());
+    Migrator::up(db, None).await?;
+    Ok(())
+}
\ No newline at end of file
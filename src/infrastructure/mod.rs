@@ -3,6 +3,13 @@ pub mod jwt;
 pub mod database_connection;
 pub mod redis_async_pool;
 pub mod redis_connection;
+pub mod cache;
+pub mod rate_limit;
+pub mod health;
+pub mod metrics;
+pub mod telemetry;
 pub mod messaging;
 pub mod image_util;
-pub mod sui;
\ No newline at end of file
+pub mod storage;
+pub mod sui;
+pub mod migration;
\ No newline at end of file
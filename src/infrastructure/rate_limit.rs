@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// 令牌桶参数,桶满时可以连续请求burst次,之后每分钟恢复per_minute个令牌
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RatePolicy {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl RatePolicy {
+    /// 每毫秒恢复的令牌数
+    pub fn tokens_per_ms(&self) -> f64 {
+        self.per_minute as f64 / 60_000.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    Allowed,
+    // 桶中没有令牌,需要等待的时间
+    RetryAfter(Duration),
+}
+
+/// 按key限流,多个实例共用同一组桶
+#[async_trait]
+pub trait RateLimiter: Send + Sync {
+    /// 桶中至少有一个令牌时取出cost个令牌并放行,cost为0时只检查不消耗
+    async fn acquire(&self, key: &str, policy: RatePolicy, cost: u32) -> Result<Throttle, anyhow::Error>;
+}
+
+/// 桶的状态,未出现过的key视为满桶
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub tokens: f64,
+    // 上次更新的时间,unix timestamp(毫秒)
+    pub updated_at: u64,
+}
+
+/// 令牌桶算法,Redis中的Lua脚本与此保持一致
+pub fn take(bucket: Option<Bucket>, now: u64, policy: RatePolicy, cost: u32) -> (Bucket, Throttle) {
+    let rate = policy.tokens_per_ms();
+    let burst = policy.burst as f64;
+    let tokens = match bucket {
+        Some(bucket) => (bucket.tokens + now.saturating_sub(bucket.updated_at) as f64 * rate).min(burst),
+        None => burst,
+    };
+    if tokens < 1.0 {
+        let wait = if rate > 0.0 { ((1.0 - tokens) / rate).ceil() as u64 } else { u64::MAX };
+        return (Bucket { tokens, updated_at: now }, Throttle::RetryAfter(Duration::from_millis(wait)))
+    }
+    (Bucket { tokens: tokens - cost as f64, updated_at: now }, Throttle::Allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{take, RatePolicy, Throttle};
+
+    #[test]
+    fn test_take() {
+        // 每毫秒恢复0.5个令牌
+        let policy = RatePolicy { burst: 2, per_minute: 30_000 };
+        let (bucket, throttle) = take(None, 0, policy, 1);
+        assert_eq!(throttle, Throttle::Allowed);
+        let (bucket, throttle) = take(Some(bucket), 0, policy, 1);
+        assert_eq!(throttle, Throttle::Allowed);
+        let (bucket, throttle) = take(Some(bucket), 1, policy, 1);
+        assert_eq!(throttle, Throttle::RetryAfter(Duration::from_millis(1)));
+        assert_eq!(bucket.tokens, 0.5);
+        // 只检查不消耗
+        let (bucket, throttle) = take(Some(bucket), 2, policy, 0);
+        assert_eq!(throttle, Throttle::Allowed);
+        assert_eq!(bucket.tokens, 1.0);
+        // 恢复不超过burst
+        let (bucket, _) = take(Some(bucket), 100, policy, 1);
+        assert_eq!(bucket.tokens, 1.0);
+    }
+}
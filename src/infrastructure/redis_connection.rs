@@ -1,33 +1,101 @@
-use std::sync::Arc;
+use std::time::Duration;
 
-use deadpool::managed::{Pool, PoolConfig, Object};
-use redis::Client;
+use async_trait::async_trait;
+use deadpool::managed::{Pool, PoolConfig};
 use once_cell::sync::Lazy;
-use tokio::{runtime::Handle, task};
-use super::redis_async_pool::RedisConnectionManager;
-
-pub static  REDIS_POOL : Lazy<Arc<Pool<RedisConnectionManager>>> = Lazy::new(|| {
-    task::block_in_place(move || {
-        Handle::current().block_on(async {
-            let redis_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
-            let client = Client::open(redis_url).expect("Failed to create Redis client");
-
-            let pool_config = PoolConfig::default();
-            let connection_pool: Pool<RedisConnectionManager> =
-                Pool::builder(RedisConnectionManager::new(client, true, None))
-                .config(pool_config)
-                .max_size(5)
-                .build()
-                .expect("Failed to create Redis pool");
-            Arc::new(connection_pool)
-        })
-    })
-});
+use redis::{AsyncCommands, Client, Script};
+
+use crate::settings::RedisConfig;
+
+use super::{cache::Cache, health::{HealthCheck, PoolStatus}, rate_limit::{RateLimiter, RatePolicy, Throttle}, redis_async_pool::RedisConnectionManager};
+
+pub type RedisPool = Pool<RedisConnectionManager>;
+
+/// 令牌桶,与rate_limit::take一致;使用Redis服务器时间,多个实例共用同一个时钟
+/// 返回需要等待的毫秒数,0表示放行
+static TOKEN_BUCKET: Lazy<Script> = Lazy::new(|| Script::new(r"
+local burst = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = burst
+if bucket[1] then
+    tokens = math.min(burst, tonumber(bucket[1]) + math.max(0, now - tonumber(bucket[2])) * rate)
+end
+local wait = 0
+if tokens < 1 then
+    wait = math.ceil((1 - tokens) / rate)
+else
+    tokens = tokens - cost
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / rate))
+return wait
+"));
+
+/// 创建Redis连接池,连接在第一次使用时建立
+pub fn create_pool(config: &RedisConfig) -> Result<RedisPool, anyhow::Error> {
+    let client = Client::open(config.url.as_str())?;
+
+    let pool_config = PoolConfig::default();
+    let connection_pool: RedisPool =
+        Pool::builder(RedisConnectionManager::new(client, true, None))
+        .config(pool_config)
+        .max_size(5)
+        .build()?;
+    Ok(connection_pool)
+}
+
+#[async_trait]
+impl Cache for RedisPool {
+    #[tracing::instrument(name = "redis GET", skip(self), fields(otel.kind = "client", db.system = "redis"), err)]
+    async fn get(&self, key: &str) -> Result<Option<String>, anyhow::Error> {
+        let mut connection = Pool::get(self).await?;
+        let value: Option<String> = connection.get(key).await?;
+        Ok(value)
+    }
 
-pub fn get_redis_pool() -> Arc<Pool<RedisConnectionManager>> {
-    REDIS_POOL.clone()
+    #[tracing::instrument(name = "redis SETEX", skip(self, value), fields(otel.kind = "client", db.system = "redis"), err)]
+    async fn set_ex(&self, key: &str, value: &str, seconds: u64) -> Result<(), anyhow::Error> {
+        let mut connection = Pool::get(self).await?;
+        let _: () = connection.set_ex(key, value, seconds).await?;
+        Ok(())
+    }
 }
 
-pub async  fn get_redis_connection() -> Object<RedisConnectionManager> {
-    get_redis_pool().get().await.expect("Failed to get connection")
-}
\ No newline at end of file
+#[async_trait]
+impl RateLimiter for RedisPool {
+    #[tracing::instrument(name = "redis rate_limit", skip(self), fields(otel.kind = "client", db.system = "redis"), err)]
+    async fn acquire(&self, key: &str, policy: RatePolicy, cost: u32) -> Result<Throttle, anyhow::Error> {
+        let mut connection = Pool::get(self).await?;
+        let connection: &mut redis::aio::MultiplexedConnection = &mut connection;
+        let wait: u64 = TOKEN_BUCKET.key(format!("rate_limit:{}", key))
+        .arg(policy.burst)
+        .arg(policy.tokens_per_ms())
+        .arg(cost)
+        .invoke_async(connection)
+        .await?;
+        Ok(match wait {
+            0 => Throttle::Allowed,
+            wait => Throttle::RetryAfter(Duration::from_millis(wait)),
+        })
+    }
+}
+
+#[async_trait]
+impl HealthCheck for RedisPool {
+    #[tracing::instrument(name = "redis PING", skip(self), fields(otel.kind = "client", db.system = "redis"), err)]
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        let mut connection = Pool::get(self).await?;
+        let connection: &mut redis::aio::MultiplexedConnection = &mut connection;
+        let _: String = redis::cmd("PING").query_async(connection).await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        let status = self.status();
+        Some(PoolStatus { size: status.size, idle: status.available, max_size: status.max_size })
+    }
+}
@@ -0,0 +1,135 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::ensure;
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use ipfs_api_backend_hyper::{request::FilesRead, Error, IpfsApi, IpfsClient, TryFromUri};
+
+use crate::settings::IpfsSettings;
+
+use super::{clamp, is_valid_key, spool, ByteStream, Object, StorageBackend, Volume};
+
+/// IPFS节点的MFS,文件保存在{root}/assets和{root}/medias下
+pub struct IpfsStorage {
+    client: IpfsClient,
+    root: String,
+    gateway_url: Option<String>,
+}
+
+impl IpfsStorage {
+    pub fn new(settings: &IpfsSettings) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: IpfsClient::from_str(&settings.api_url)?,
+            root: settings.root.trim_end_matches('/').to_owned(),
+            gateway_url: settings.gateway_url.as_ref().map(|url| url.trim_end_matches('/').to_owned()),
+        })
+    }
+
+    fn path(&self, volume: Volume, key: &str) -> anyhow::Result<String> {
+        ensure!(is_valid_key(key), "invalid storage key `{}`", key);
+        Ok(format!("{}/{}/{}", self.root, volume.name(), key))
+    }
+
+    /// 文件的大小和CID,不存在时返回None
+    async fn stat(&self, path: &str) -> Result<Option<(u64, String)>, anyhow::Error> {
+        match self.client.files_stat(path).await {
+            Ok(stat) if stat.typ == "file" => Ok(Some((stat.size, stat.hash))),
+            Ok(_) => Ok(None),
+            Err(err) if not_found(&err) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn remove(&self, path: &str) -> Result<(), anyhow::Error> {
+        match self.client.files_rm(path, false).await {
+            Err(err) if !not_found(&err) => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn not_found(err: &Error) -> bool {
+    err.to_string().contains("does not exist")
+}
+
+#[async_trait]
+impl StorageBackend for IpfsStorage {
+    /// files/write只接受同步的Read,先写入临时文件
+    async fn put(&self, volume: Volume, key: &str, body: ByteStream<'_>) -> Result<u64, anyhow::Error> {
+        let path = self.path(volume, key)?;
+        let (spooled, bytes) = spool(body).await?;
+        // 不截断时,覆盖较大的旧文件会留下旧内容的尾部
+        self.remove(&path).await?;
+        self.client.files_write(&path, true, true, spooled.reopen()?).await?;
+        Ok(bytes)
+    }
+
+    async fn get(&self, volume: Volume, key: &str, range: Option<Range<u64>>) -> Result<Option<Object>, anyhow::Error> {
+        let path = self.path(volume, key)?;
+        let Some((size, _)) = self.stat(&path).await? else {
+            return Ok(None)
+        };
+        let range = clamp(range, size);
+        let body = if range.is_empty() {
+            futures::stream::empty().boxed()
+        } else {
+            self.client.files_read_with_options(FilesRead {
+                path: &path,
+                offset: Some(range.start as i64),
+                count: Some((range.end - range.start) as i64),
+            })
+            .map_err(std::io::Error::other)
+            .boxed()
+        };
+        Ok(Some(Object { body, size, range }))
+    }
+
+    async fn size(&self, volume: Volume, key: &str) -> Result<Option<u64>, anyhow::Error> {
+        Ok(self.stat(&self.path(volume, key)?).await?.map(|(size, _)| size))
+    }
+
+    async fn delete(&self, volume: Volume, key: &str) -> Result<(), anyhow::Error> {
+        self.remove(&self.path(volume, key)?).await
+    }
+
+    async fn copy(&self, volume: Volume, from: &str, to: &str) -> Result<(), anyhow::Error> {
+        let (from, to) = (self.path(volume, from)?, self.path(volume, to)?);
+        if let Some((parent, _)) = to.rsplit_once('/') {
+            self.client.files_mkdir(parent, true).await?;
+        }
+        self.remove(&to).await?;
+        self.client.files_cp(&from, &to).await?;
+        Ok(())
+    }
+
+    /// 网关上的地址不会过期,配置了gateway_url时才使用
+    async fn presign(&self, volume: Volume, key: &str, _expires: Duration) -> Result<Option<String>, anyhow::Error> {
+        let Some(gateway_url) = &self.gateway_url else {
+            return Ok(None)
+        };
+        Ok(self.stat(&self.path(volume, key)?).await?.map(|(_, cid)| format!("{}/ipfs/{}", gateway_url, cid)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{infrastructure::storage::{put_bytes, read_all, StorageBackend, Volume}, settings::IpfsSettings};
+
+    use super::IpfsStorage;
+
+    #[tokio::test]
+    #[ignore = "需要本地IPFS节点"]
+    async fn test_ipfs_storage() {
+        let storage = IpfsStorage::new(&IpfsSettings { root: "/bassinet-test".to_owned(), ..Default::default() }).unwrap();
+        put_bytes(&storage, Volume::Assets, "icons/a.svg", Bytes::from_static(b"<svg></svg>")).await.unwrap();
+        put_bytes(&storage, Volume::Assets, "icons/a.svg", Bytes::from_static(b"<svg/>")).await.unwrap();
+        storage.copy(Volume::Assets, "icons/a.svg", "collection/a.svg").await.unwrap();
+        assert_eq!(read_all(&storage, Volume::Assets, "collection/a.svg").await.unwrap().unwrap(), Bytes::from_static(b"<svg/>"));
+        for key in ["icons/a.svg", "collection/a.svg"] {
+            storage.delete(Volume::Assets, key).await.unwrap();
+            assert_eq!(storage.size(Volume::Assets, key).await.unwrap(), None);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{io::{ErrorKind, SeekFrom}, ops::Range, path::{Path, PathBuf}, time::Duration};
+
+use anyhow::{ensure, Context};
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::{fs::{self, File}, io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufWriter}};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use super::{clamp, is_valid_key, ByteStream, Object, StorageBackend, Volume};
+
+/// 本地目录,assets和medias分别对应server.assets_path和server.medias_path
+pub struct LocalStorage {
+    assets: PathBuf,
+    medias: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(assets: impl AsRef<Path>, medias: impl AsRef<Path>) -> Self {
+        Self { assets: assets.as_ref().to_path_buf(), medias: medias.as_ref().to_path_buf() }
+    }
+
+    fn path(&self, volume: Volume, key: &str) -> anyhow::Result<PathBuf> {
+        ensure!(is_valid_key(key), "invalid storage key `{}`", key);
+        let root = match volume {
+            Volume::Assets => &self.assets,
+            Volume::Medias => &self.medias,
+        };
+        Ok(root.join(key))
+    }
+
+    async fn create_parent(path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    /// 先写入同一目录下的临时文件再改名,读取时不会看到写了一半的文件
+    async fn put(&self, volume: Volume, key: &str, body: ByteStream<'_>) -> Result<u64, anyhow::Error> {
+        let path = self.path(volume, key)?;
+        Self::create_parent(&path).await?;
+        let temp = path.with_file_name(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+        let written = async {
+            let mut file = BufWriter::new(File::create(&temp).await?);
+            let bytes = tokio::io::copy(&mut StreamReader::new(body), &mut file).await?;
+            file.flush().await?;
+            drop(file);
+            fs::rename(&temp, &path).await?;
+            Ok::<_, std::io::Error>(bytes)
+        }.await;
+        if written.is_err() {
+            let _ = fs::remove_file(&temp).await;
+        }
+        written.with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    async fn get(&self, volume: Volume, key: &str, range: Option<Range<u64>>) -> Result<Option<Object>, anyhow::Error> {
+        let path = self.path(volume, key)?;
+        let mut file = match File::open(&path).await {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("failed to open {}", path.display())),
+        };
+        let metadata = file.metadata().await?;
+        if !metadata.is_file() {
+            return Ok(None)
+        }
+        let range = clamp(range, metadata.len());
+        file.seek(SeekFrom::Start(range.start)).await?;
+        let body = ReaderStream::new(file.take(range.end - range.start)).boxed();
+        Ok(Some(Object { body, size: metadata.len(), range }))
+    }
+
+    async fn size(&self, volume: Volume, key: &str) -> Result<Option<u64>, anyhow::Error> {
+        let path = self.path(volume, key)?;
+        match fs::metadata(&path).await {
+            Ok(metadata) => Ok(metadata.is_file().then_some(metadata.len())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("failed to stat {}", path.display())),
+        }
+    }
+
+    async fn delete(&self, volume: Volume, key: &str) -> Result<(), anyhow::Error> {
+        let path = self.path(volume, key)?;
+        match fs::remove_file(&path).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err).with_context(|| format!("failed to delete {}", path.display())),
+            _ => Ok(()),
+        }
+    }
+
+    /// 复制而不是硬链接,assets和medias可以位于不同的文件系统
+    async fn copy(&self, volume: Volume, from: &str, to: &str) -> Result<(), anyhow::Error> {
+        let (from, to) = (self.path(volume, from)?, self.path(volume, to)?);
+        Self::create_parent(&to).await?;
+        fs::copy(&from, &to).await.with_context(|| format!("failed to copy {} to {}", from.display(), to.display()))?;
+        Ok(())
+    }
+
+    async fn presign(&self, _volume: Volume, _key: &str, _expires: Duration) -> Result<Option<String>, anyhow::Error> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+
+    use crate::infrastructure::storage::{put_bytes, read_all, StorageBackend, Volume};
+
+    use super::LocalStorage;
+
+    #[tokio::test]
+    async fn test_local_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path().join("assets"), dir.path().join("medias"));
+
+        assert_eq!(put_bytes(&storage, Volume::Medias, "0f1e/chunk_0", Bytes::from_static(b"0123456789")).await.unwrap(), 10);
+        assert_eq!(storage.size(Volume::Medias, "0f1e/chunk_0").await.unwrap(), Some(10));
+        assert_eq!(storage.size(Volume::Assets, "0f1e/chunk_0").await.unwrap(), None);
+        assert_eq!(storage.size(Volume::Medias, "0f1e").await.unwrap(), None);
+
+        let object = storage.get(Volume::Medias, "0f1e/chunk_0", Some(2..5)).await.unwrap().unwrap();
+        assert_eq!((object.size, object.range.clone()), (10, 2..5));
+        let body: Vec<Bytes> = object.body.try_collect().await.unwrap();
+        assert_eq!(body.concat(), b"234");
+
+        storage.copy(Volume::Medias, "0f1e/chunk_0", "collection/clip.mp4").await.unwrap();
+        storage.delete(Volume::Medias, "0f1e/chunk_0").await.unwrap();
+        storage.delete(Volume::Medias, "0f1e/chunk_0").await.unwrap();
+        assert!(storage.get(Volume::Medias, "0f1e/chunk_0", None).await.unwrap().is_none());
+        assert_eq!(read_all(&storage, Volume::Medias, "collection/clip.mp4").await.unwrap().unwrap(), Bytes::from_static(b"0123456789"));
+
+        assert!(storage.size(Volume::Assets, "../medias/collection/clip.mp4").await.is_err());
+    }
+}
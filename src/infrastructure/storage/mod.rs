@@ -0,0 +1,135 @@
+//! 文件存储
+//!
+//! 资源和音视频文件按key(以/分隔的相对路径,如`{collection_id}/{file_name}`)存取,
+//! 后端可以是本地目录、S3兼容的对象存储或IPFS节点的MFS,由配置[storage]选择。
+
+use std::{io, ops::Range, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream::{self, BoxStream}, StreamExt, TryStreamExt};
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::StreamReader;
+
+use crate::{settings::{StorageConfig, StorageKind}, ServerConfig};
+
+pub(crate) mod local;
+pub(crate) mod s3;
+pub(crate) mod ipfs;
+
+pub type ByteStream<'a> = BoxStream<'a, io::Result<Bytes>>;
+
+/// 文件所属的空间,分别由assets和medias服务提供下载
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Volume {
+    Assets,
+    Medias,
+}
+
+impl Volume {
+    pub fn name(self) -> &'static str {
+        match self {
+            Volume::Assets => "assets",
+            Volume::Medias => "medias",
+        }
+    }
+}
+
+/// 读取到的内容
+pub struct Object {
+    pub body: ByteStream<'static>,
+    // 对象的总大小
+    pub size: u64,
+    // body对应的字节范围
+    pub range: Range<u64>,
+}
+
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// 写入对象,已存在时覆盖,返回写入的字节数
+    async fn put(&self, volume: Volume, key: &str, body: ByteStream<'_>) -> Result<u64, anyhow::Error>;
+
+    /// 读取对象,range超出对象大小的部分被截断;对象不存在时返回None
+    async fn get(&self, volume: Volume, key: &str, range: Option<Range<u64>>) -> Result<Option<Object>, anyhow::Error>;
+
+    /// 对象大小,不存在时返回None
+    async fn size(&self, volume: Volume, key: &str) -> Result<Option<u64>, anyhow::Error>;
+
+    /// 删除对象,不存在时忽略
+    async fn delete(&self, volume: Volume, key: &str) -> Result<(), anyhow::Error>;
+
+    /// 在同一空间内复制对象,目标已存在时覆盖
+    async fn copy(&self, volume: Volume, from: &str, to: &str) -> Result<(), anyhow::Error>;
+
+    /// 有效期为expires的下载地址;返回None时由本服务提供下载
+    async fn presign(&self, volume: Volume, key: &str, expires: Duration) -> Result<Option<String>, anyhow::Error>;
+}
+
+/// 按配置创建存储后端
+pub fn create(config: &StorageConfig, server: &ServerConfig) -> anyhow::Result<Arc<dyn StorageBackend>> {
+    Ok(match config.backend {
+        StorageKind::Local => Arc::new(local::LocalStorage::new(&server.assets_path, &server.medias_path)),
+        StorageKind::S3 => Arc::new(s3::S3Storage::new(&config.s3)),
+        StorageKind::Ipfs => Arc::new(ipfs::IpfsStorage::new(&config.ipfs)?),
+    })
+}
+
+/// key由不含.和..的非空段组成,不能是绝对路径
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != ".." && !segment.contains(['\\', ':']))
+}
+
+/// 写入内存中的数据
+pub async fn put_bytes(storage: &dyn StorageBackend, volume: Volume, key: &str, data: Bytes) -> Result<u64, anyhow::Error> {
+    storage.put(volume, key, stream::once(async { Ok(data) }).boxed()).await
+}
+
+/// 读取整个对象,用于图片等小文件
+pub async fn read_all(storage: &dyn StorageBackend, volume: Volume, key: &str) -> Result<Option<Bytes>, anyhow::Error> {
+    let Some(object) = storage.get(volume, key, None).await? else {
+        return Ok(None)
+    };
+    let chunks: Vec<Bytes> = object.body.try_collect().await?;
+    Ok(Some(chunks.concat().into()))
+}
+
+/// 写入临时文件,用于需要事先知道长度或需要同步读取的后端
+pub(crate) async fn spool(body: ByteStream<'_>) -> io::Result<(NamedTempFile, u64)> {
+    let spooled = NamedTempFile::new()?;
+    let mut file = tokio::fs::File::from_std(spooled.reopen()?);
+    let mut reader = StreamReader::new(body);
+    let bytes = tokio::io::copy(&mut reader, &mut file).await?;
+    file.flush().await?;
+    Ok((spooled, bytes))
+}
+
+/// 按对象大小截断请求的范围
+pub(crate) fn clamp(range: Option<Range<u64>>, size: u64) -> Range<u64> {
+    match range {
+        Some(range) => range.start.min(size)..range.end.min(size).max(range.start.min(size)),
+        None => 0..size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clamp, is_valid_key};
+
+    #[test]
+    fn test_is_valid_key() {
+        assert!(is_valid_key("icons/7d9f.png"));
+        assert!(is_valid_key("0f1e/chunk_0"));
+        for key in ["", "/etc/passwd", "../secret", "a/../../b", "a//b", "a/./b", "a\\b", "C:/x"] {
+            assert!(!is_valid_key(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_clamp() {
+        assert_eq!(clamp(None, 10), 0..10);
+        assert_eq!(clamp(Some(2..5), 10), 2..5);
+        assert_eq!(clamp(Some(8..20), 10), 8..10);
+        assert_eq!(clamp(Some(12..20), 10), 10..10);
+    }
+}
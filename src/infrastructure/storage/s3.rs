@@ -0,0 +1,156 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::ensure;
+use async_trait::async_trait;
+use aws_sdk_s3::{config::{BehaviorVersion, Builder, Credentials, Region}, presigning::PresigningConfig, primitives::ByteStream as S3Body, Client};
+use futures::StreamExt;
+use tokio_util::io::ReaderStream;
+
+use crate::settings::S3Settings;
+
+use super::{is_valid_key, spool, ByteStream, Object, StorageBackend, Volume};
+
+/// S3兼容的对象存储,assets和medias是同一个桶中的两个前缀
+pub struct S3Storage {
+    client: Client,
+    bucket: String,
+    presign_downloads: bool,
+}
+
+impl S3Storage {
+    pub fn new(settings: &S3Settings) -> Self {
+        let mut config = Builder::new()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new(settings.region.clone()))
+        .credentials_provider(Credentials::new(&settings.access_key, &settings.secret_key, None, None, "bassinet"))
+        .force_path_style(settings.path_style);
+        if let Some(endpoint) = &settings.endpoint {
+            config = config.endpoint_url(endpoint);
+        }
+        Self {
+            client: Client::from_conf(config.build()),
+            bucket: settings.bucket.clone(),
+            presign_downloads: settings.presign_downloads,
+        }
+    }
+
+    fn key(volume: Volume, key: &str) -> anyhow::Result<String> {
+        ensure!(is_valid_key(key), "invalid storage key `{}`", key);
+        Ok(format!("{}/{}", volume.name(), key))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    /// PutObject需要事先知道长度,先写入临时文件
+    async fn put(&self, volume: Volume, key: &str, body: ByteStream<'_>) -> Result<u64, anyhow::Error> {
+        let key = Self::key(volume, key)?;
+        let (spooled, bytes) = spool(body).await?;
+        let body = S3Body::from_path(spooled.path()).await?;
+        self.client.put_object().bucket(&self.bucket).key(key).body(body).send().await?;
+        Ok(bytes)
+    }
+
+    async fn get(&self, volume: Volume, key: &str, range: Option<Range<u64>>) -> Result<Option<Object>, anyhow::Error> {
+        let mut request = self.client.get_object().bucket(&self.bucket).key(Self::key(volume, key)?);
+        if let Some(range) = &range {
+            if range.is_empty() {
+                // 空范围无法用Range头表示
+                return Ok(self.size(volume, key).await?.map(|size| Object { body: futures::stream::empty().boxed(), size, range: range.start.min(size)..range.start.min(size) }))
+            }
+            request = request.range(format!("bytes={}-{}", range.start, range.end - 1));
+        }
+        let output = match request.send().await {
+            Ok(output) => output,
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_no_such_key()) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let length = output.content_length().unwrap_or_default() as u64;
+        let (range, size) = output.content_range().and_then(parse_content_range).unwrap_or((0..length, length));
+        let body = ReaderStream::new(output.body.into_async_read()).boxed();
+        Ok(Some(Object { body, size, range }))
+    }
+
+    async fn size(&self, volume: Volume, key: &str) -> Result<Option<u64>, anyhow::Error> {
+        match self.client.head_object().bucket(&self.bucket).key(Self::key(volume, key)?).send().await {
+            Ok(output) => Ok(Some(output.content_length().unwrap_or_default() as u64)),
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_not_found()) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, volume: Volume, key: &str) -> Result<(), anyhow::Error> {
+        self.client.delete_object().bucket(&self.bucket).key(Self::key(volume, key)?).send().await?;
+        Ok(())
+    }
+
+    async fn copy(&self, volume: Volume, from: &str, to: &str) -> Result<(), anyhow::Error> {
+        self.client.copy_object()
+        .bucket(&self.bucket)
+        .copy_source(format!("{}/{}", self.bucket, Self::key(volume, from)?))
+        .key(Self::key(volume, to)?)
+        .send()
+        .await?;
+        Ok(())
+    }
+
+    async fn presign(&self, volume: Volume, key: &str, expires: Duration) -> Result<Option<String>, anyhow::Error> {
+        if !self.presign_downloads {
+            return Ok(None)
+        }
+        let request = self.client.get_object().bucket(&self.bucket).key(Self::key(volume, key)?)
+        .presigned(PresigningConfig::expires_in(expires)?)
+        .await?;
+        Ok(Some(request.uri().to_string()))
+    }
+}
+
+/// 解析Content-Range: bytes 0-99/1234
+fn parse_content_range(value: &str) -> Option<(Range<u64>, u64)> {
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.parse().ok()?..end.parse::<u64>().ok()? + 1, size.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, time::Duration};
+
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+
+    use crate::{infrastructure::storage::{put_bytes, StorageBackend, Volume}, settings::S3Settings};
+
+    use super::{parse_content_range, S3Storage};
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-99/1234"), Some((0..100, 1234)));
+        assert_eq!(parse_content_range("bytes */1234"), None);
+    }
+
+    /// 本地MinIO:docker run -p 9000:9000 minio/minio server /data,并创建桶bassinet
+    #[tokio::test]
+    #[ignore = "需要本地MinIO"]
+    async fn test_s3_storage() {
+        let storage = S3Storage::new(&S3Settings {
+            endpoint: Some(env::var("BASSINET_TEST_S3_ENDPOINT").unwrap_or_else(|_| "http://localhost:9000".to_owned())),
+            bucket: "bassinet".to_owned(),
+            access_key: "minioadmin".to_owned(),
+            secret_key: "minioadmin".to_owned(),
+            presign_downloads: true,
+            ..Default::default()
+        });
+        put_bytes(&storage, Volume::Medias, "s3-test/chunk_0", Bytes::from_static(b"0123456789")).await.unwrap();
+        let object = storage.get(Volume::Medias, "s3-test/chunk_0", Some(2..5)).await.unwrap().unwrap();
+        assert_eq!((object.size, object.range.clone()), (10, 2..5));
+        let body: Vec<Bytes> = object.body.try_collect().await.unwrap();
+        assert_eq!(body.concat(), b"234");
+        storage.copy(Volume::Medias, "s3-test/chunk_0", "s3-test/clip.mp4").await.unwrap();
+        assert!(storage.presign(Volume::Medias, "s3-test/clip.mp4", Duration::from_secs(60)).await.unwrap().is_some());
+        for key in ["s3-test/chunk_0", "s3-test/clip.mp4"] {
+            storage.delete(Volume::Medias, key).await.unwrap();
+            assert_eq!(storage.size(Volume::Medias, key).await.unwrap(), None);
+        }
+    }
+}
@@ -1,6 +1,11 @@
+use async_trait::async_trait;
 use sui_sdk::types::base_types::ObjectID;
 
+use crate::domain::model::valueobject::nft_event::{EventCursor, EventStream, NftEventPage};
+
+pub(crate) mod nft_events;
 pub(crate) mod nft_query;
+pub(crate) mod rpc_pool;
 
 pub struct MyBassinetNft {
     pub object_id: ObjectID,
@@ -8,4 +13,21 @@ pub struct MyBassinetNft {
     pub package_id: String,
     pub module: String,
     pub name: String,
-}
\ No newline at end of file
+}
+
+/// 链上NFT查询
+#[async_trait]
+pub trait NftQuery: Send + Sync {
+    /// 账户持有的任意一个package_id::bassinet_nft::BassinetNFT,没有时为None
+    async fn get_any_bassinet_nft_by(&self, address: &String, package_id: &String) -> Result<Option<ObjectID>, anyhow::Error>;
+
+    /// NFT对象当前的持有地址,放在kiosk中、被包装或已销毁时为None
+    async fn get_nft_owner(&self, object_id: &str) -> Result<Option<String>, anyhow::Error>;
+}
+
+/// 链上NFT事件,链上索引使用
+#[async_trait]
+pub trait NftEventSource: Send + Sync {
+    /// package_id的某类事件中cursor之后的一页,按链上顺序返回
+    async fn query_nft_events(&self, package_id: &str, stream: EventStream, cursor: Option<EventCursor>, limit: usize) -> Result<NftEventPage, anyhow::Error>;
+}
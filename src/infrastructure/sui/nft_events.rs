@@ -0,0 +1,117 @@
+use serde_json::Value;
+
+use crate::domain::model::valueobject::nft_event::{EventCursor, NftEvent, NftEventKind};
+
+/// BassinetNFT所在的模块
+pub const NFT_MODULE: &str = "bassinet_nft";
+
+/// 解析JSON-RPC返回的事件(SuiEvent序列化后的形式),不影响NFT归属的事件返回None
+///
+/// 合约事件按名称识别:*Minted(owner/recipient/creator,缺省为发送者)、*Transferred(recipient/to)、*Burned,
+/// NFT对象ID为object_id/nft_id/id;kiosk事件为0x2::kiosk::ItemListed/ItemDelisted/ItemPurchased,
+/// 上架者和购买者为交易发送者
+pub fn decode(event: &Value) -> Option<NftEvent> {
+    let id = EventCursor {
+        tx_digest: event["id"]["txDigest"].as_str()?.to_owned(),
+        event_seq: u64_of(&event["id"]["eventSeq"])?,
+    };
+    let timestamp_ms = u64_of(&event["timestampMs"]).unwrap_or_default();
+    let sender = address_of(&event["sender"]);
+    let fields = &event["parsedJson"];
+    // 0x2::kiosk::ItemListed<..>的泛型参数不参与判断
+    let event_type = event["type"].as_str()?;
+    let event_type = event_type.split_once('<').map_or(event_type, |(event_type, _)| event_type);
+    let [_, module, name] = event_type.split("::").collect::<Vec<_>>()[..] else {
+        return None
+    };
+    let (object_id, kind) = match module {
+        NFT_MODULE => {
+            let object_id = first_address(fields, &["object_id", "nft_id", "id"])?;
+            let kind = if name.ends_with("Minted") {
+                NftEventKind::Minted { owner: first_address(fields, &["owner", "recipient", "creator"]).or(sender)? }
+            } else if name.ends_with("Transferred") {
+                NftEventKind::Transferred { to: first_address(fields, &["recipient", "to"])? }
+            } else if name.ends_with("Burned") {
+                NftEventKind::Burned
+            } else {
+                return None
+            };
+            (object_id, kind)
+        }
+        "kiosk" => {
+            let object_id = address_of(&fields["id"])?;
+            let kind = match name {
+                "ItemListed" => NftEventKind::Listed { kiosk_id: address_of(&fields["kiosk"])?, seller: sender?, price: u64_of(&fields["price"])? },
+                "ItemDelisted" => NftEventKind::Delisted { kiosk_id: address_of(&fields["kiosk"])? },
+                // 购买后NFT离开卖家的kiosk
+                "ItemPurchased" => NftEventKind::Purchased { buyer: sender? },
+                _ => return None,
+            };
+            (object_id, kind)
+        }
+        _ => return None,
+    };
+    Some(NftEvent { id, object_id, kind, timestamp_ms })
+}
+
+/// 地址统一为小写,与绑定的钱包地址比较
+fn address_of(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_lowercase)
+}
+
+fn first_address(fields: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| address_of(&fields[*key]))
+}
+
+/// JSON-RPC中的u64为字符串
+fn u64_of(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(value) => value.parse().ok(),
+        value => value.as_u64(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::Value;
+
+    use crate::domain::model::valueobject::nft_event::{NftEvent, NftEventKind};
+
+    use super::decode;
+
+    /// 按测试网JSON-RPC事件格式手写的样例,地址和对象ID均为占位值
+    pub(crate) const PACKAGE_ID: &str = "0x6d2f6f2c2a1e48e9b4a0f0f2c57b8a5bdfcd1e7a3ed1b3fbc53b8b4f1d40c0de";
+    pub(crate) const ALICE: &str = "0xa11ce00000000000000000000000000000000000000000000000000000000001";
+    pub(crate) const BOB: &str = "0xb0b0000000000000000000000000000000000000000000000000000000000002";
+    pub(crate) const CAROL: &str = "0xca40100000000000000000000000000000000000000000000000000000000003";
+
+    pub(crate) fn sample_events() -> Vec<Value> {
+        serde_json::from_str(include_str!("fixtures/nft_events.json")).unwrap()
+    }
+
+    #[test]
+    fn test_decode_sample_events() {
+        let events: Vec<NftEvent> = sample_events().iter().map(|event| decode(event).unwrap()).collect();
+        let kinds: Vec<&NftEventKind> = events.iter().map(|event| &event.kind).collect();
+        assert_eq!(kinds, [
+            &NftEventKind::Minted { owner: ALICE.to_owned() },
+            &NftEventKind::Minted { owner: ALICE.to_owned() },
+            &NftEventKind::Listed { kiosk_id: "0x000000000000000000000000000000000000000000000000000000000000c001".to_owned(), seller: ALICE.to_owned(), price: 5_000_000_000 },
+            &NftEventKind::Purchased { buyer: BOB.to_owned() },
+            &NftEventKind::Transferred { to: CAROL.to_owned() },
+            &NftEventKind::Burned,
+        ]);
+        assert_eq!(events[0].object_id, "0x00000000000000000000000000000000000000000000000000000000000000a1");
+        assert_eq!(events[0].timestamp_ms, 1_760_860_800_000);
+        assert_eq!(events[2].id.event_seq, 1);
+    }
+
+    #[test]
+    fn test_decode_ignores_other_events() {
+        let mut event = sample_events().remove(0);
+        event["type"] = Value::from(format!("{}::bassinet_nft::RewardsClaimed", PACKAGE_ID));
+        assert_eq!(decode(&event), None);
+        event["type"] = Value::from("0x2::coin::CoinMinted");
+        assert_eq!(decode(&event), None);
+    }
+}
@@ -1,39 +1,69 @@
-use std::str::FromStr;
-
-use sui_sdk::{rpc_types::{Page, SuiObjectDataFilter, SuiObjectDataOptions, SuiObjectResponse, SuiObjectResponseQuery}, types::{base_types::{ObjectID, SuiAddress}, parse_sui_struct_tag}, SuiClient, SuiClientBuilder};
-
-use super::MyBassinetNft;
-
-/// 获取指定账户的所有bassinet NFT package_id:bassinet_nft::BassinetNFT
-pub async fn query_bassinet_nfts(address: &String) -> Result<Vec<MyBassinetNft>, anyhow::Error> {
-    let mut results: Vec<MyBassinetNft> = Vec::new();
-
-    let client = get_client().await?;
-    let address = SuiAddress::from_str(&address).unwrap();
-    let options = SuiObjectDataOptions::new().with_type();
-    let query = SuiObjectResponseQuery::new(None, Some(options));
-    let limit = Some(100 as usize);
-    let mut cursor = None;
-    let mut has_next_page = true;
-    while has_next_page {
-        let result = client.read_api().get_owned_objects(address, Some(query.clone()), cursor, limit).await?;
-        has_next_page = result.has_next_page;
-        cursor = result.next_cursor;
-        let mut parse_results = parse(result);
-        if parse_results.len() > 0 {
-            results.append(&mut parse_results);
+use std::{str::FromStr, time::Duration};
+
+use async_trait::async_trait;
+use futures::FutureExt;
+use sui_sdk::{rpc_types::{EventFilter, Page, SuiObjectDataFilter, SuiObjectDataOptions, SuiObjectResponse, SuiObjectResponseQuery}, types::{base_types::{ObjectID, SuiAddress}, digests::TransactionDigest, event::EventID, object::Owner, parse_sui_struct_tag, Identifier}};
+use tokio_util::sync::CancellationToken;
+
+use crate::{domain::model::valueobject::nft_event::{EventCursor, EventStream, NftEventPage}, infrastructure::health::HealthCheck, settings::SuiConfig};
+
+use super::{nft_events::{self, NFT_MODULE}, rpc_pool::SuiRpcPool, MyBassinetNft, NftEventSource, NftQuery};
+
+/// supervisor中检查fullnode的任务名
+pub const TASK: &str = "sui_health";
+
+/// 通过配置的Sui fullnode查询链上对象,节点不可用时换下一个节点
+pub struct SuiNftQuery {
+    pool: SuiRpcPool,
+}
+
+impl SuiNftQuery {
+    pub fn new(config: &SuiConfig) -> Self {
+        Self { pool: SuiRpcPool::new(config) }
+    }
+
+    /// 按interval定期检查fullnode,直到收到停止信号
+    pub async fn monitor_endpoints(&self, interval: Duration, shutdown: CancellationToken) -> Result<(), anyhow::Error> {
+        self.pool.monitor(interval, shutdown).await
+    }
+
+    /// 获取指定账户的所有bassinet NFT package_id:bassinet_nft::BassinetNFT
+    pub async fn query_bassinet_nfts(&self, address: &String) -> Result<Vec<MyBassinetNft>, anyhow::Error> {
+        let mut results: Vec<MyBassinetNft> = Vec::new();
+
+        let address = SuiAddress::from_str(&address)?;
+        let options = SuiObjectDataOptions::new().with_type();
+        let query = SuiObjectResponseQuery::new(None, Some(options));
+        let limit = Some(100 as usize);
+        let mut cursor = None;
+        let mut has_next_page = true;
+        while has_next_page {
+            let result = self.pool.call(|client| {
+                let query = query.clone();
+                async move { Ok(client.read_api().get_owned_objects(address, Some(query), cursor, limit).await?) }.boxed()
+            }).await?;
+            has_next_page = result.has_next_page;
+            cursor = result.next_cursor;
+            let mut parse_results = parse(result);
+            if parse_results.len() > 0 {
+                results.append(&mut parse_results);
+            }
         }
+        
+        Ok(results)
     }
-    
-    Ok(results)
 }
 
 fn parse(page: Page<SuiObjectResponse, ObjectID>) -> Vec<MyBassinetNft> {
     let mut results = Vec::new();
     if !page.data.is_empty() {
         for item in page.data {
-            let data = item.data.unwrap();
-            let object_type = data.object_type().unwrap().to_string();
+            let Some(data) = item.data else {
+                continue;
+            };
+            let Ok(object_type) = data.object_type().map(|object_type| object_type.to_string()) else {
+                continue;
+            };
             let object_id = data.object_id;
             let bassinet_object_type = bassinet_struct_tag(&object_type);
             match bassinet_object_type {
@@ -49,43 +79,95 @@ fn parse(page: Page<SuiObjectResponse, ObjectID>) -> Vec<MyBassinetNft> {
 
 fn bassinet_struct_tag(object_type: &String) -> Option<(String, String, String)> {
     let strs: Vec<&str> = object_type.split("::").collect();
-    if strs.len() != 3 {
-        return None
+    match strs.as_slice() {
+        [package_id, module, name] if *module == "bassinet_nft" && *name == "BassinetNFT" => {
+            Some((package_id.to_string(), module.to_string(), name.to_string()))
+        },
+        _ => None,
     }
-    let module = strs.get(1).unwrap().to_string();
-    let name = strs.get(2).unwrap().to_string();
-    if module == "bassinet_nft" && name == "BassinetNFT" {
-        return Some((strs.get(0).unwrap().to_string(), module, name))
+}
+
+#[async_trait]
+impl NftQuery for SuiNftQuery {
+    async fn get_any_bassinet_nft_by(&self, address: &String, package_id: &String) -> Result<Option<ObjectID>, anyhow::Error> {
+        let address = SuiAddress::from_str(&address)?;
+
+        let mut tag_str = String::from(package_id);
+        tag_str.push_str("::");
+        tag_str.push_str("bassinet_nft");
+        tag_str.push_str("::BassinetNFT");
+        let tag = parse_sui_struct_tag(tag_str.as_str())?;
+
+        let filter = SuiObjectDataFilter::StructType(tag);
+        let query = SuiObjectResponseQuery::new(Some(filter), None);
+        let limit = Some(1 as usize);
+        let result = self.pool.call(|client| {
+            let query = query.clone();
+            async move { Ok(client.read_api().get_owned_objects(address, Some(query), None, limit).await?) }.boxed()
+        }).await?;
+        let object_id = result.data.first().and_then(|object| object.object_id().ok());
+        Ok(object_id)
+    }
+
+    async fn get_nft_owner(&self, object_id: &str) -> Result<Option<String>, anyhow::Error> {
+        let object_id = ObjectID::from_str(object_id)?;
+        let response = self.pool.call(|client| {
+            async move { Ok(client.read_api().get_object_with_options(object_id, SuiObjectDataOptions::new().with_owner()).await?) }.boxed()
+        }).await?;
+        match response.data.and_then(|data| data.owner) {
+            Some(Owner::AddressOwner(address)) => Ok(Some(address.to_string())),
+            _ => Ok(None),
+        }
     }
-    None
 }
 
-async fn get_client() -> Result<SuiClient, anyhow::Error> {
-    let client = SuiClientBuilder::default()
-    // .ws_url("wss://sui-testnet-rpc.publicnode.com")
-    // .build("https://sui-testnet-rpc.publicnode.com")
-    .build("https://fullnode.testnet.sui.io:443")
-    .await?;
-    Ok(client)
+#[async_trait]
+impl NftEventSource for SuiNftQuery {
+    async fn query_nft_events(&self, package_id: &str, stream: EventStream, cursor: Option<EventCursor>, limit: usize) -> Result<NftEventPage, anyhow::Error> {
+        let cursor = match cursor {
+            Some(cursor) => Some(EventID { tx_digest: TransactionDigest::from_str(&cursor.tx_digest)?, event_seq: cursor.event_seq }),
+            None => None,
+        };
+        let filter = event_filter(package_id, stream)?;
+        let page = self.pool.call(|client| {
+            let filter = filter.clone();
+            async move { Ok(client.event_api().query_events(filter, cursor, Some(limit), false).await?) }.boxed()
+        }).await?;
+        let mut events = Vec::with_capacity(page.data.len());
+        for event in &page.data {
+            // 与测试样例事件使用相同的JSON形式解析
+            let event = serde_json::to_value(event)?;
+            match nft_events::decode(&event) {
+                Some(event) => events.push(event),
+                None => tracing::debug!("skip event {} of type {}", event["id"], event["type"]),
+            }
+        }
+        Ok(NftEventPage {
+            events,
+            next_cursor: page.next_cursor.map(|id| EventCursor { tx_digest: id.tx_digest.to_string(), event_seq: id.event_seq }),
+            has_next_page: page.has_next_page,
+        })
+    }
+}
+
+fn event_filter(package_id: &str, stream: EventStream) -> Result<EventFilter, anyhow::Error> {
+    let kiosk_event = |name: &str| parse_sui_struct_tag(&format!("0x2::kiosk::{}<{}::{}::BassinetNFT>", name, package_id, NFT_MODULE));
+    let filter = match stream {
+        EventStream::Module => EventFilter::MoveEventModule { package: ObjectID::from_str(package_id)?, module: Identifier::new(NFT_MODULE)? },
+        EventStream::KioskListed => EventFilter::MoveEventType(kiosk_event("ItemListed")?),
+        EventStream::KioskDelisted => EventFilter::MoveEventType(kiosk_event("ItemDelisted")?),
+        EventStream::KioskPurchased => EventFilter::MoveEventType(kiosk_event("ItemPurchased")?),
+    };
+    Ok(filter)
 }
 
-/// 根据类型获取nft
-pub async fn get_any_bassinet_nft_by(address: &String, package_id: &String) -> Result<Option<ObjectID>, anyhow::Error> {
-    let client = get_client().await?;
-    let address = SuiAddress::from_str(&address).unwrap();
-
-    let mut tag_str = String::from(package_id);
-    tag_str.push_str("::");
-    tag_str.push_str("bassinet_nft");
-    tag_str.push_str("::BassinetNFT");
-    let tag = parse_sui_struct_tag(tag_str.as_str()).unwrap();
-
-    let filter = SuiObjectDataFilter::StructType(tag);
-    let query = SuiObjectResponseQuery::new(Some(filter), None);
-    let limit = Some(1 as usize);
-    let result = client.read_api().get_owned_objects(address, Some(query), None, limit).await?;
-    if result.data.is_empty() {
-        return Ok(None)
+#[async_trait]
+impl HealthCheck for SuiNftQuery {
+    /// 使用后台检查和最近请求的结果,不在readiness中访问fullnode
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        match self.pool.unavailable() {
+            Some(endpoints) => Err(anyhow::anyhow!("all sui fullnodes are unavailable: {}", endpoints)),
+            None => Ok(()),
+        }
     }
-    Ok(Some(result.data.get(0).unwrap().object_id().unwrap()))
-}
\ No newline at end of file
+}
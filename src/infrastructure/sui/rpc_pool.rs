@@ -0,0 +1,302 @@
+use std::{sync::atomic::{AtomicBool, Ordering}, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use jsonrpsee::core::ClientError;
+use sui_sdk::{error::Error as SuiError, SuiClient, SuiClientBuilder};
+use tokio::sync::OnceCell;
+use tokio_util::sync::CancellationToken;
+
+use crate::settings::{SuiConfig, SuiNetwork};
+
+/// 创建fullnode客户端并判断错误类型,测试中替换为不需要网络的实现
+#[async_trait]
+pub trait Connector: Send + Sync {
+    type Client: Send + Sync;
+
+    async fn connect(&self, url: &str, timeout: Duration) -> Result<Self::Client, anyhow::Error>;
+
+    /// 节点所在链的标识
+    async fn chain_identifier(&self, client: &Self::Client) -> Result<String, anyhow::Error>;
+
+    /// 请求的错误是否由节点故障引起
+    fn is_node_failure(&self, err: &anyhow::Error) -> bool;
+}
+
+/// 通过sui_sdk连接fullnode
+pub struct SuiConnector;
+
+#[async_trait]
+impl Connector for SuiConnector {
+    type Client = SuiClient;
+
+    async fn connect(&self, url: &str, timeout: Duration) -> Result<SuiClient, anyhow::Error> {
+        let client = SuiClientBuilder::default()
+        .request_timeout(timeout)
+        .build(url)
+        .await?;
+        Ok(client)
+    }
+
+    async fn chain_identifier(&self, client: &SuiClient) -> Result<String, anyhow::Error> {
+        Ok(client.read_api().get_chain_identifier().await?)
+    }
+
+    /// 连接、传输错误(包括HTTP状态码错误)和请求超时,节点正常返回的JSON-RPC错误不算
+    fn is_node_failure(&self, err: &anyhow::Error) -> bool {
+        match err.downcast_ref::<SuiError>() {
+            Some(SuiError::RpcError(ClientError::Call(_))) => false,
+            Some(SuiError::RpcError(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// 一个fullnode,客户端在第一次使用时创建并复用
+struct Endpoint<T> {
+    url: String,
+    client: OnceCell<T>,
+    healthy: AtomicBool,
+}
+
+/// 配置的fullnode,按顺序优先使用可用的节点
+/// 连接失败、超时或HTTP错误时标记节点不可用并换下一个节点重试,后台定期检查节点是否恢复
+pub struct SuiRpcPool<C: Connector = SuiConnector> {
+    connector: C,
+    endpoints: Vec<Endpoint<C::Client>>,
+    network: SuiNetwork,
+    timeout: Duration,
+    max_retries: u32,
+}
+
+impl SuiRpcPool {
+    pub fn new(config: &SuiConfig) -> Self {
+        Self::with_connector(config, SuiConnector)
+    }
+}
+
+impl<C: Connector> SuiRpcPool<C> {
+    pub fn with_connector(config: &SuiConfig, connector: C) -> Self {
+        Self {
+            connector,
+            endpoints: config.endpoints().into_iter().map(|url| Endpoint { url, client: OnceCell::new(), healthy: AtomicBool::new(true) }).collect(),
+            network: config.network,
+            timeout: Duration::from_secs(config.request_timeout_secs),
+            max_retries: config.max_retries,
+        }
+    }
+
+    async fn client<'a>(&self, endpoint: &'a Endpoint<C::Client>) -> Result<&'a C::Client, anyhow::Error> {
+        endpoint.client.get_or_try_init(|| self.connector.connect(&endpoint.url, self.timeout)).await
+    }
+
+    /// 可用的节点在前,都不可用时仍依次尝试
+    fn ordered(&self) -> Vec<&Endpoint<C::Client>> {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = self.endpoints.iter().partition(|endpoint| endpoint.healthy.load(Ordering::Relaxed));
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+
+    /// 执行请求,节点故障时换下一个节点,最多重试max_retries次
+    /// 节点返回的JSON-RPC错误和请求中的其他错误直接返回,不影响节点状态
+    pub async fn call<T, F>(&self, request: F) -> Result<T, anyhow::Error>
+    where
+        F: for<'a> Fn(&'a C::Client) -> BoxFuture<'a, Result<T, anyhow::Error>>,
+    {
+        let mut last_error = None;
+        for endpoint in self.ordered().into_iter().cycle().take(self.max_retries as usize + 1) {
+            let err = match self.client(endpoint).await {
+                Ok(client) => match tokio::time::timeout(self.timeout, request(client)).await {
+                    Ok(Ok(value)) => {
+                        endpoint.healthy.store(true, Ordering::Relaxed);
+                        return Ok(value)
+                    }
+                    Ok(Err(err)) if !self.connector.is_node_failure(&err) => return Err(err),
+                    Ok(Err(err)) => err,
+                    Err(_) => anyhow!("timed out after {:?}", self.timeout),
+                },
+                Err(err) => err,
+            };
+            if endpoint.healthy.swap(false, Ordering::Relaxed) {
+                tracing::warn!("sui fullnode {} failed, trying the next one: {:#}", endpoint.url, err);
+            }
+            last_error = Some(err.context(format!("sui fullnode {}", endpoint.url)));
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("no sui fullnode configured")))
+    }
+
+    /// 检查所有节点,链标识与配置的网络不一致的节点视为不可用,返回可用的节点数
+    pub async fn check_endpoints(&self) -> usize {
+        let mut available = 0;
+        for endpoint in &self.endpoints {
+            let result = match self.client(endpoint).await {
+                Ok(client) => tokio::time::timeout(self.timeout, self.connector.chain_identifier(client)).await
+                .unwrap_or_else(|_| Err(anyhow!("timed out after {:?}", self.timeout))),
+                Err(err) => Err(err),
+            };
+            let healthy = match result {
+                Ok(chain_identifier) => match self.network.chain_identifier() {
+                    Some(expected) if expected != chain_identifier => {
+                        tracing::error!("sui fullnode {} is on chain {}, expected {:?} ({})", endpoint.url, chain_identifier, self.network, expected);
+                        false
+                    }
+                    _ => true,
+                },
+                Err(err) => {
+                    tracing::warn!("sui fullnode {} is unavailable: {:#}", endpoint.url, err);
+                    false
+                }
+            };
+            let was_healthy = endpoint.healthy.swap(healthy, Ordering::Relaxed);
+            if healthy && !was_healthy {
+                tracing::info!("sui fullnode {} recovered", endpoint.url);
+            }
+            available += healthy as usize;
+        }
+        available
+    }
+
+    /// 当前是否有可用的节点
+    pub fn unavailable(&self) -> Option<String> {
+        if self.endpoints.iter().any(|endpoint| endpoint.healthy.load(Ordering::Relaxed)) {
+            return None
+        }
+        Some(self.endpoints.iter().map(|endpoint| endpoint.url.as_str()).collect::<Vec<_>>().join(", "))
+    }
+
+    /// 按interval定期检查节点,直到收到停止信号
+    pub async fn monitor(&self, interval: Duration, shutdown: CancellationToken) -> Result<(), anyhow::Error> {
+        loop {
+            self.check_endpoints().await;
+            tokio::select! {
+                _ = shutdown.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex}, time::Duration};
+
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+    use futures::FutureExt;
+
+    use crate::settings::SuiConfig;
+
+    use super::{Connector, SuiRpcPool};
+
+    const NODE_DOWN: &str = "connection refused";
+    // 配置的网络为testnet,这个节点在mainnet上
+    const MAINNET_NODE: &str = "http://mainnet";
+
+    /// 不需要网络的节点,down中的节点请求失败
+    struct FakeNode {
+        url: String,
+        down: Arc<Mutex<HashSet<String>>>,
+    }
+
+    impl FakeNode {
+        fn request(&self) -> Result<String, anyhow::Error> {
+            if self.down.lock().unwrap().contains(&self.url) {
+                return Err(anyhow!(NODE_DOWN))
+            }
+            Ok(self.url.clone())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeConnector {
+        down: Arc<Mutex<HashSet<String>>>,
+    }
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Client = FakeNode;
+
+        async fn connect(&self, url: &str, _timeout: Duration) -> Result<FakeNode, anyhow::Error> {
+            Ok(FakeNode { url: url.to_owned(), down: self.down.clone() })
+        }
+
+        async fn chain_identifier(&self, client: &FakeNode) -> Result<String, anyhow::Error> {
+            let url = client.request()?;
+            Ok(if url == MAINNET_NODE { "35834a8a" } else { "4c78adac" }.to_owned())
+        }
+
+        fn is_node_failure(&self, err: &anyhow::Error) -> bool {
+            err.to_string() == NODE_DOWN
+        }
+    }
+
+    fn healthy<C: Connector>(pool: &SuiRpcPool<C>) -> Vec<bool> {
+        pool.endpoints.iter().map(|endpoint| endpoint.healthy.load(Ordering::Relaxed)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_failover_and_recovery() {
+        let config = SuiConfig {
+            rpc_urls: vec![MAINNET_NODE.to_owned(), "http://a".to_owned(), "http://b".to_owned()],
+            ..Default::default()
+        };
+        let connector = FakeConnector::default();
+        let down = connector.down.clone();
+        let pool = SuiRpcPool::with_connector(&config, connector);
+
+        // 检查时链标识不一致的节点标记为不可用,不再优先使用
+        assert_eq!(pool.check_endpoints().await, 2);
+        assert_eq!(healthy(&pool), [false, true, true]);
+        assert_eq!(pool.call(|node| async move { node.request() }.boxed()).await.unwrap(), "http://a");
+
+        // a故障时换下一个节点,之后优先使用b
+        down.lock().unwrap().insert("http://a".to_owned());
+        assert_eq!(pool.call(|node| async move { node.request() }.boxed()).await.unwrap(), "http://b");
+        assert_eq!(healthy(&pool), [false, false, true]);
+
+        // 节点返回的错误直接返回,不换节点也不改变节点状态
+        let requests = AtomicUsize::new(0);
+        let err = pool.call(|_| {
+            requests.fetch_add(1, Ordering::SeqCst);
+            async move { Err::<String, _>(anyhow!("invalid params")) }.boxed()
+        }).await.unwrap_err();
+        assert_eq!(err.to_string(), "invalid params");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(healthy(&pool), [false, false, true]);
+
+        // 可用的b故障后,仅通过检查也会标记为不可用
+        down.lock().unwrap().insert("http://b".to_owned());
+        assert_eq!(pool.check_endpoints().await, 0);
+        assert_eq!(healthy(&pool), [false, false, false]);
+        assert!(pool.unavailable().is_some());
+
+        // 检查时恢复的节点重新标记为可用,按配置顺序优先使用
+        down.lock().unwrap().clear();
+        assert_eq!(pool.check_endpoints().await, 2);
+        assert_eq!(healthy(&pool), [false, true, true]);
+        assert_eq!(pool.call(|node| async move { node.request() }.boxed()).await.unwrap(), "http://a");
+    }
+
+    #[tokio::test]
+    async fn test_all_endpoints_failing() {
+        // 两个地址都无法连接,依次尝试后都标记为不可用
+        let config = SuiConfig {
+            rpc_urls: vec!["http://127.0.0.1:1".to_owned(), "http://127.0.0.1:2".to_owned()],
+            max_retries: 2,
+            ..Default::default()
+        };
+        let pool = SuiRpcPool::new(&config);
+        assert!(pool.unavailable().is_none());
+        let requests = AtomicUsize::new(0);
+        let result = pool.call(|client| {
+            requests.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(client.read_api().get_chain_identifier().await?) }.boxed()
+        }).await;
+        let err = format!("{:#}", result.unwrap_err());
+        // 第三次尝试回到第一个节点
+        assert!(err.contains("http://127.0.0.1:1"), "{}", err);
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+        assert_eq!(pool.unavailable().as_deref(), Some("http://127.0.0.1:1, http://127.0.0.1:2"));
+        assert_eq!(pool.check_endpoints().await, 0);
+    }
+}
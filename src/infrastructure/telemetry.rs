@@ -0,0 +1,275 @@
+//! 链路追踪
+//!
+//! tracing的span通过tracing-opentelemetry导出;HTTP请求和RabbitMQ消息按W3C trace context(traceparent)
+//! 继续上游的链路,SeaORM的查询在执行结束后补记为所在span的子span。
+
+use std::{collections::{BTreeMap, HashMap}, fmt, fs::OpenOptions, io::{self, Write}, sync::{Mutex, PoisonError}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use amqprs::{FieldTable, FieldValue};
+use anyhow::Context as _;
+use axum::{body::Body, http::{HeaderMap, Method, Request, Response}};
+use opentelemetry::{global, propagation::Extractor, trace::{Span as _, SpanId, SpanKind, Status, Tracer, TracerProvider as _}, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{error::{OTelSdkError, OTelSdkResult}, propagation::TraceContextPropagator, trace::{Sampler, SdkTracerProvider, SpanData, SpanExporter}, Resource};
+use serde::Serialize;
+use tracing::{field, Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::settings::{TelemetryConfig, TraceExporter};
+
+const TRACER_NAME: &str = env!("CARGO_CRATE_NAME");
+
+/// 启动时创建,drop时导出剩余的span
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn init(config: &TelemetryConfig) -> anyhow::Result<Self> {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let builder = SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build());
+        let builder = match config.exporter {
+            TraceExporter::None => return Ok(Self { provider: None }),
+            TraceExporter::Stdout => builder.with_batch_exporter(JsonLinesExporter::new(io::stdout())),
+            TraceExporter::File => {
+                let file = OpenOptions::new().create(true).append(true).open(&config.file_path)
+                .with_context(|| format!("failed to open trace file {}", config.file_path))?;
+                builder.with_batch_exporter(JsonLinesExporter::new(file))
+            }
+            TraceExporter::Otlp => {
+                let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(config.otlp_endpoint.clone())
+                .build()
+                .context("failed to create OTLP exporter")?;
+                builder.with_batch_exporter(exporter)
+            }
+        };
+        let provider = builder.build();
+        global::set_tracer_provider(provider.clone());
+        Ok(Self { provider: Some(provider) })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// 未启用导出时返回None
+    pub fn layer<S>(&self) -> Option<OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        self.provider.as_ref().map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(TRACER_NAME)))
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("failed to flush traces: {}", err);
+            }
+        }
+    }
+}
+
+/// 请求的span,请求头带traceparent时继续上游的链路
+pub fn http_span(request: &Request<Body>) -> Span {
+    let span = tracing::info_span!(
+        "http_request",
+        otel.name = %request.method(),
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        http.request.method = %request.method(),
+        url.path = request.uri().path(),
+        http.route = field::Empty,
+        http.response.status_code = field::Empty,
+    );
+    span.set_parent(global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(request.headers()))));
+    span
+}
+
+/// 匹配到路由后以路由模板命名span
+pub fn record_route(method: &Method, route: &str) {
+    let span = Span::current();
+    span.record("http.route", route);
+    span.record("otel.name", format!("{} {}", method, route));
+}
+
+pub fn record_response(response: &Response<Body>, _latency: Duration, span: &Span) {
+    span.record("http.response.status_code", response.status().as_u16());
+    if response.status().is_server_error() {
+        span.record("otel.status_code", "error");
+    }
+}
+
+/// 处理一条消息的span,消息头带traceparent时继续发布者的链路
+pub fn consumer_span(consumer: &'static str, headers: Option<&FieldTable>) -> Span {
+    let span = tracing::info_span!(
+        "consume",
+        otel.name = %format!("{} process", consumer),
+        otel.kind = "consumer",
+        otel.status_code = field::Empty,
+        messaging.system = "rabbitmq",
+        messaging.consumer = consumer,
+    );
+    if let Some(headers) = headers {
+        let carrier: HashMap<String, String> = headers.as_ref().iter()
+        .filter_map(|(key, value)| match value {
+            FieldValue::S(value) => Some((key.to_string(), value.to_string())),
+            _ => None,
+        })
+        .collect();
+        span.set_parent(global::get_text_map_propagator(|propagator| propagator.extract(&carrier)));
+    }
+    span
+}
+
+/// SeaORM的查询回调,查询结束后按实际耗时记录为当前span的子span
+pub fn record_query(info: &sea_orm::metric::Info<'_>) {
+    let end = SystemTime::now();
+    let tracer = global::tracer(TRACER_NAME);
+    let operation = info.statement.sql.split_whitespace().next().unwrap_or("query").to_ascii_uppercase();
+    let mut span = tracer.span_builder(operation)
+    .with_kind(SpanKind::Client)
+    .with_start_time(end.checked_sub(info.elapsed).unwrap_or(end))
+    .with_attributes([
+        KeyValue::new("db.system", "postgresql"),
+        KeyValue::new("db.statement", info.statement.sql.clone()),
+    ])
+    .start_with_context(&tracer, &Span::current().context());
+    if info.failed {
+        span.set_status(Status::error("query failed"));
+    }
+    span.end_with_timestamp(end);
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// 每个span输出一行JSON,用于没有collector的本地环境
+struct JsonLinesExporter {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesExporter {
+    fn new(writer: impl Write + Send + 'static) -> Self {
+        Self { writer: Mutex::new(Box::new(writer)) }
+    }
+}
+
+impl fmt::Debug for JsonLinesExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("JsonLinesExporter")
+    }
+}
+
+#[derive(Serialize)]
+struct SpanRecord<'a> {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: &'a str,
+    kind: String,
+    // unix时间戳(微秒)
+    start_us: u128,
+    duration_us: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    attributes: BTreeMap<&'a str, String>,
+}
+
+impl<'a> From<&'a SpanData> for SpanRecord<'a> {
+    fn from(span: &'a SpanData) -> Self {
+        Self {
+            trace_id: span.span_context.trace_id().to_string(),
+            span_id: span.span_context.span_id().to_string(),
+            parent_span_id: (span.parent_span_id != SpanId::INVALID).then(|| span.parent_span_id.to_string()),
+            name: &span.name,
+            kind: format!("{:?}", span.span_kind).to_ascii_lowercase(),
+            start_us: span.start_time.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros(),
+            duration_us: span.end_time.duration_since(span.start_time).unwrap_or_default().as_micros(),
+            error: match &span.status {
+                Status::Error { description } => Some(description.as_ref()),
+                _ => None,
+            },
+            attributes: span.attributes.iter().map(|attribute| (attribute.key.as_str(), attribute.value.to_string())).collect(),
+        }
+    }
+}
+
+impl SpanExporter for JsonLinesExporter {
+    fn export(&self, batch: Vec<SpanData>) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = batch.iter().try_for_each(|span| {
+            serde_json::to_writer(&mut *writer, &SpanRecord::from(span))?;
+            writer.write_all(b"\n")
+        })
+        .and_then(|()| writer.flush())
+        .map_err(|err| OTelSdkError::InternalFailure(err.to_string()));
+        std::future::ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use amqprs::{FieldTable, FieldValue};
+    use opentelemetry::{global, trace::TracerProvider as _};
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::{consumer_span, JsonLinesExporter};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 消息头中的traceparent成为处理消息的span的父span
+    #[test]
+    fn test_consumer_span_continues_trace() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let buffer = Buffer::default();
+        let provider = SdkTracerProvider::builder().with_simple_exporter(JsonLinesExporter::new(buffer.clone())).build();
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let mut headers = FieldTable::new();
+        headers.insert("traceparent".try_into().unwrap(), FieldValue::S("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".try_into().unwrap()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = consumer_span("account_bound_consumer", Some(&headers));
+            span.in_scope(|| tracing::info!("handling"));
+        });
+        provider.shutdown().unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let record: serde_json::Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(record["trace_id"], "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(record["parent_span_id"], "b7ad6b7169203331");
+        assert_eq!(record["name"], "account_bound_consumer process");
+        assert_eq!(record["kind"], "consumer");
+        assert_eq!(record["attributes"]["messaging.system"], "rabbitmq");
+    }
+}
@@ -1,25 +1,37 @@
 // 修改头像、修改昵称
 
-use axum::{http::StatusCode, Json};
+use axum::{extract::State, Json};
 
-use crate::{application::query_service::account_query_service, infrastructure::jwt::Claims};
+use crate::{application::query_service::account_query_service, error::ApiError, infrastructure::jwt::Claims, state::AppState};
 
-use super::dto::account::AccountInfo;
+use super::dto::{account::AccountInfo, ApiResult};
 
 /// 获取用户账户信息
-pub async fn get_account_info(claims: Claims) -> Result<Json<AccountInfo>, (StatusCode, String)> {
-    //map_err(|err|(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()));
-    let result = account_query_service::get_account_info(&claims.pubkey).await;
-    if result.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, result.err().unwrap().to_string()))
-    }
-    Ok(Json(result.unwrap()))
+#[utoipa::path(
+    get,
+    path = "/account_info",
+    tag = "account",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, body = AccountInfo),
+        (status = 401, description = "未登录或账户不存在", body = ApiResult),
+    )
+)]
+pub async fn get_account_info(State(state): State<AppState>, claims: Claims) -> Result<Json<AccountInfo>, ApiError> {
+    let account = account_query_service::get_account_info(&state, &claims.pubkey).await?;
+    Ok(Json(account))
 }
 
-pub async fn get_authors() -> Result<Json<Vec<AccountInfo>>, (StatusCode, String)> {
-    let authors = account_query_service::get_authors().await;
-    if authors.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, authors.err().unwrap().to_string()))
-    }
-    Ok(Json(authors.unwrap()))
-}
+/// 所有创作者
+#[utoipa::path(
+    get,
+    path = "/authors",
+    tag = "account",
+    responses(
+        (status = 200, body = Vec<AccountInfo>),
+    )
+)]
+pub async fn get_authors(State(state): State<AppState>) -> Result<Json<Vec<AccountInfo>>, ApiError> {
+    let authors = account_query_service::get_authors(&state).await?;
+    Ok(Json(authors))
+}
\ No newline at end of file
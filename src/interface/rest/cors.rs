@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use axum::{extract::{Request, State}, http::{HeaderName, Method}, middleware::Next, response::Response};
+use tower::{Layer, ServiceExt};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::settings::{CorsConfig, CorsPolicy, RuntimeHandle};
+
+/// 使用跨域策略的服务,分别对应配置[runtime.cors]下的api、assets和medias
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Listener {
+    Api,
+    Assets,
+    Medias,
+}
+
+impl Listener {
+    fn policy(self, config: &CorsConfig) -> &CorsPolicy {
+        match self {
+            Listener::Api => &config.api,
+            Listener::Assets => &config.assets,
+            Listener::Medias => &config.medias,
+        }
+    }
+}
+
+/// 按当前配置处理跨域请求,重新加载配置后立即生效
+pub async fn cors(State((listener, runtime)): State<(Listener, RuntimeHandle)>, request: Request, next: Next) -> Response {
+    let config = runtime.current();
+    let cors = layer(listener.policy(&config.cors));
+    cors.layer(next).oneshot(request).await.unwrap_or_else(|err| match err {})
+}
+
+fn layer(policy: &CorsPolicy) -> CorsLayer {
+    let origins = policy.clone();
+    // "*"时回显预检请求中的方法和请求头,与携带凭据的请求兼容
+    let methods = if policy.allowed_methods.iter().any(|method| method == "*") {
+        AllowMethods::mirror_request()
+    } else {
+        AllowMethods::list(policy.allowed_methods.iter().filter_map(|method| method.parse::<Method>().ok()))
+    };
+    let headers = if policy.allowed_headers.iter().any(|header| header == "*") {
+        AllowHeaders::mirror_request()
+    } else {
+        AllowHeaders::list(policy.allowed_headers.iter().filter_map(|header| header.parse::<HeaderName>().ok()))
+    };
+    CorsLayer::new()
+    .allow_origin(AllowOrigin::predicate(move |origin, _| origin.to_str().is_ok_and(|origin| origins.allows_origin(origin))))
+    .allow_methods(methods)
+    .allow_headers(headers)
+    .expose_headers(policy.exposed_headers.iter().filter_map(|header| header.parse::<HeaderName>().ok()).collect::<Vec<_>>())
+    .allow_credentials(policy.allow_credentials)
+    .max_age(Duration::from_secs(policy.max_age_secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use axum::{body::Body, http::{header::{ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN}, Method, Request, Response}};
+    use tower::{service_fn, Layer, ServiceExt};
+
+    use crate::settings::CorsConfig;
+
+    use super::layer;
+
+    fn send(request: Request<Body>) -> Response<Body> {
+        let mut policy = CorsConfig::default().medias;
+        policy.allowed_origins = vec!["https://*.bassinet.app".to_owned()];
+        policy.allowed_headers = vec!["range".to_owned(), "authorization".to_owned()];
+        policy.allow_credentials = true;
+        let service = layer(&policy).layer(service_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) }));
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(service.oneshot(request)).unwrap()
+    }
+
+    #[test]
+    fn test_preflight() {
+        let request = Request::builder().method(Method::OPTIONS).uri("/medias/a.mp4")
+        .header(ORIGIN, "https://app.bassinet.app")
+        .header(ACCESS_CONTROL_REQUEST_METHOD, "GET")
+        .header(ACCESS_CONTROL_REQUEST_HEADERS, "range")
+        .body(Body::empty()).unwrap();
+        let response = send(request);
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.bassinet.app");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_HEADERS], "range,authorization");
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "600");
+    }
+
+    #[test]
+    fn test_exposed_headers_and_rejected_origin() {
+        let request = Request::get("/medias/a.mp4").header(ORIGIN, "https://app.bassinet.app").body(Body::empty()).unwrap();
+        let response = send(request);
+        assert_eq!(response.headers()[ACCESS_CONTROL_EXPOSE_HEADERS], "content-range,accept-ranges,content-length");
+
+        let request = Request::get("/medias/a.mp4").header(ORIGIN, "https://bassinet.app.evil.example").body(Body::empty()).unwrap();
+        assert!(!send(request).headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
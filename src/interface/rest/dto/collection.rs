@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// 专辑
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CollectionDTO {
     // 名称
     pub title: String,
@@ -11,6 +12,10 @@ pub struct CollectionDTO {
     pub is_public: u32,
     pub request_id: String,
     pub icon_path: String,
+    // 分类
+    pub category_id: Option<String>,
+    // 标签
+    pub tags: Option<Vec<String>>,
 }
 
 // ///图集
@@ -25,16 +30,19 @@ pub struct CollectionDTO {
 // }
 
 ///图文
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ArticleDTO {
     // 标题
     pub title: String,
     // 专辑ID(uuid)
     pub collection_id: String,
     pub description: String,
+    // 内容，"<!-- more -->"之前的部分为NFT上架后的免费试读部分
     pub content: String,
     // 文档类型，目前只支持Markdown
     pub content_type: String,
+    // 是否公开，默认公开
+    pub is_public: Option<u32>,
     pub request_id: String,
 }
 
@@ -101,24 +109,33 @@ pub struct ArticleDTO {
 //     pub request_id: String,
 // }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CollectionListDTO {
     pub collections: Vec<CollectionSimpleDTO>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CollectionSimpleDTO {
     pub id: String,
     pub title: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CollectionPageDTOList {
-    pub dtos: Vec<CollectionPageDTO>,
-    pub page_info: PageInfo,
+/// 搜索结果分面统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchFacets {
+    pub categories: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FacetCount {
+    // 分类id,标签没有id
+    pub id: Option<String>,
+    pub name: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CollectionPageDTO {
     pub id: String,
     pub title: String,
@@ -127,10 +144,26 @@ pub struct CollectionPageDTO {
     pub listing: u8,
     pub created_time: u64,
     pub icon_url: Option<String>,
-    pub nft: Option<NftInfo>
+    pub nft: Option<NftInfo>,
+    // 关键字搜索时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<SearchHighlight>,
+}
+
+/// 搜索结果高亮
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHighlight {
+    // 相关度
+    pub rank: f64,
+    // 高亮的标题
+    pub title: String,
+    // 命中片段
+    pub snippet: Option<String>,
+    // 命中的专辑项
+    pub item_id: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct NftInfo {
     pub id: String,
     pub package_id: String,
@@ -147,16 +180,8 @@ pub struct NftInfo {
     pub coin_admin_cap_id: String
 }
 
-#[derive(Debug, Serialize)]
-pub struct PageInfo {
-    #[serde(rename="totalItems")]
-    pub total: u64,
-    #[serde(rename="totalPages")]
-    pub pages: u64,
-}
-
 /// 专辑信息(专辑本身信息和包含的图文信息)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CollectionInfoDTO {
     pub id: String,
     pub title: String,
@@ -165,21 +190,31 @@ pub struct CollectionInfoDTO {
     pub listing: u8,
     pub created_time: u64,
     pub icon_url: Option<String>,
+    // 上架后封面固定到IPFS的地址
+    pub icon_ipfs: Option<IpfsLinkDTO>,
     pub nft: Option<NftInfo>,
+    pub category_id: Option<String>,
+    pub tags: Vec<String>,
     // pub articles: Vec<ArticleInfoDTO>,
     pub items: Vec<CollectionItemInfoDTO>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CollectionSimpleInfoDTO {
     pub id: String,
     pub title: String,
     pub description: String,
     pub collection_url: String,
+    // 发布NFT时使用的元数据地址
+    pub metadata_url: String,
+    // 每个NFT的元数据地址,{edition}替换为NFT编号
+    pub token_metadata_url_template: String,
+    // 元数据已固定到IPFS时返回
+    pub metadata_ipfs: Option<IpfsLinkDTO>,
 }
 
 ///图文
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArticleInfoDTO {
     pub id: String,
     // 标题
@@ -187,14 +222,17 @@ pub struct ArticleInfoDTO {
     // 专辑ID(uuid)
     pub collection_id: String,
     pub description: String,
+    // 未解锁时为试读部分
     pub content: String,
     // 文档类型，目前只支持Markdown
     pub content_type: String,
     pub created_time: u64,
+    // 需持有NFT才能查看全文
+    pub locked: bool,
 }
 
 /// 专辑项
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CollectionItemInfoDTO {
     pub id: String,
     // 标题
@@ -209,4 +247,29 @@ pub struct CollectionItemInfoDTO {
     // 文档类型，目前只支持Markdown
     pub content_type: String,
     pub created_time: u64,
+    // 需持有NFT才能查看,图文只返回试读部分
+    pub locked: bool,
+    // 上架后固定到IPFS的地址,锁定时不返回
+    pub ipfs: Option<IpfsLinkDTO>,
+}
+
+/// IPFS上不可变的内容,可在NFT元数据中引用
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpfsLinkDTO {
+    // CIDv1
+    pub cid: String,
+    // ipfs://{cid}
+    pub uri: String,
+    // {gateway_url}/ipfs/{cid}
+    pub gateway_url: String,
+}
+
+impl IpfsLinkDTO {
+    pub fn new(cid: String, gateway_url: &str) -> Self {
+        Self {
+            uri: format!("ipfs://{}", cid),
+            gateway_url: format!("{}/ipfs/{}", gateway_url.trim_end_matches('/'), cid),
+            cid,
+        }
+    }
 }
\ No newline at end of file
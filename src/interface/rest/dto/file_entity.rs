@@ -1,6 +1,7 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FileEntityDTO {
     pub id: String,
     pub name: String,
@@ -10,7 +11,15 @@ pub struct FileEntityDTO {
     pub url: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct MultiFileEntityDTO {
     pub files: Vec<FileEntityDTO>,
-}
\ No newline at end of file
+}
+
+/// 上传文件表单(multipart/form-data),仅用于接口文档
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct UploadFileForm {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
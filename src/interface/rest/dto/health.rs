@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::supervisor::TaskHealth;
+
+/// 单个依赖的检查结果
+#[derive(Debug, Serialize)]
+pub struct CheckDTO {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // 检查耗时(毫秒)
+    pub elapsed_ms: u64,
+}
+
+/// readiness检查结果
+#[derive(Debug, Serialize)]
+pub struct ReadinessDTO {
+    // ok或unavailable
+    pub status: &'static str,
+    pub checks: BTreeMap<&'static str, CheckDTO>,
+    pub tasks: BTreeMap<&'static str, TaskHealth>,
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// 文件分片
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChunkListDTO {
     pub file_hash: String,
     pub chunk_number: i32,
@@ -10,18 +11,18 @@ pub struct ChunkListDTO {
     pub total_chunks: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChunkInfoDTO {
     pub upload_status: u8,
     pub chunk_sign_arr: Vec<u8>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MediaDTO {
     pub file_hash: String
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AddVideoPayload {
     pub request_id: String,
     pub title: String,
@@ -30,4 +31,20 @@ pub struct AddVideoPayload {
     pub video_path: String,
     pub collection_id: String,
     pub file_hash: String
-}
\ No newline at end of file
+}
+
+/// 上传视频分片表单(multipart/form-data),仅用于接口文档
+#[allow(dead_code)]
+#[derive(ToSchema)]
+#[schema(rename_all = "camelCase")]
+pub struct ChunkUploadForm {
+    pub file_name: String,
+    pub total_chunks: i32,
+    // 分片序号,从0开始
+    pub chunk_number: i32,
+    pub chunk_size: i32,
+    // 整个文件的md5
+    pub md5: String,
+    #[schema(value_type = String, format = Binary)]
+    pub chunk: Vec<u8>,
+}
@@ -1,21 +1,94 @@
+use self::collection::SearchFacets;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{domain::model::valueobject::page::{PageCursor, PageRequest, PageResult}, error::ApiError};
 
 pub mod logon;
 pub mod collection;
 pub mod file_entity;
 pub mod account;
 pub mod media;
+pub mod tag;
+pub mod health;
+pub mod nft;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResult {
     pub error_code: u32,
     pub message: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PageQueryArgs {
     pub page: Option<u32>,
     pub page_size: Option<u32>,
+    // 上一页返回的nextCursor,有游标时忽略page
+    pub cursor: Option<String>,
+    // 是否返回总数,默认只在按页码查询时返回
+    pub with_total: Option<bool>,
     pub keyword: Option<String>,
     pub author: Option<String>,
+    // 包含某类专辑项,article,video
+    pub category: Option<String>,
+    // 平台分类
+    pub category_id: Option<String>,
+    pub tag: Option<String>,
+    // 是否已上架NFT
+    pub listed: Option<bool>,
+    // 创建时间范围,unix timestamp(秒)
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+impl PageQueryArgs {
+    pub fn page_request(&self) -> Result<PageRequest, ApiError> {
+        let cursor = self.cursor.as_ref().map(|cursor| PageCursor::decode(cursor)).transpose()
+        .map_err(|_| ApiError::InvalidCursor)?;
+        let with_total = self.with_total.unwrap_or(cursor.is_none());
+        Ok(PageRequest::new(self.page, self.page_size, cursor, with_total))
+    }
+}
+
+/// 分页返回
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PageDTOList<T> {
+    pub dtos: Vec<T>,
+    pub page_info: PageInfo,
+    // 搜索时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SearchFacets>,
+}
+
+impl<T> PageDTOList<T> {
+    pub fn new(page: PageResult<T>, request: &PageRequest) -> Self {
+        Self {
+            page_info: PageInfo {
+                total: page.total,
+                pages: page.total.map(|total| total.div_ceil(request.limit)),
+                next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+                has_more: page.has_more,
+            },
+            dtos: page.items,
+            facets: None,
+        }
+    }
+
+    pub fn with_facets(mut self, facets: SearchFacets) -> Self {
+        self.facets = Some(facets);
+        self
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PageInfo {
+    #[serde(rename="totalItems", skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(rename="totalPages", skip_serializing_if = "Option::is_none")]
+    pub pages: Option<u64>,
+    #[serde(rename="nextCursor")]
+    pub next_cursor: Option<String>,
+    #[serde(rename="hasMore")]
+    pub has_more: bool,
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// NFT元数据,同时包含ERC-721元数据标准和Sui Display常用的字段
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NftMetadataDTO {
+    pub name: String,
+    pub description: String,
+    // 封面已固定到IPFS时为ipfs://{cid},否则为assets地址
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    pub attributes: Vec<NftAttributeDTO>,
+    // Sui Display: image_url, link, project_url, creator
+    pub image_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_url: Option<String>,
+    pub creator: String,
+}
+
+/// 元数据属性,如limit、minting_price、rewards_quantity
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct NftAttributeDTO {
+    pub trait_type: String,
+    pub value: NftAttributeValue,
+}
+
+impl NftAttributeDTO {
+    pub fn new(trait_type: &str, value: impl Into<NftAttributeValue>) -> Self {
+        Self { trait_type: trait_type.to_owned(), value: value.into() }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum NftAttributeValue {
+    Number(u64),
+    Text(String),
+}
+
+impl From<u64> for NftAttributeValue {
+    fn from(value: u64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<String> for NftAttributeValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
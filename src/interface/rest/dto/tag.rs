@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// 分类
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CategoryDTO {
+    pub id: String,
+    pub slug: String,
+    pub name: String,
+}
+
+/// 标签及使用次数
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagCountDTO {
+    pub name: String,
+    pub count: u64,
+}
+
+/// 设置专辑标签
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CollectionTagsPayload {
+    pub tags: Vec<String>,
+    pub request_id: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagQueryArgs {
+    // 标签前缀
+    pub prefix: Option<String>,
+    pub limit: Option<u32>,
+}
\ No newline at end of file
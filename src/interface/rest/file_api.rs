@@ -1,35 +1,44 @@
-use std::{io, path::Path, sync::Arc};
-use axum::{extract::{Multipart, State}, http::StatusCode, response::IntoResponse, BoxError, Json};
-use tokio_util::io::StreamReader;
-use tokio::{fs::{self, File}, io::{AsyncWriteExt, BufWriter}};
-use futures::{Stream, TryFutureExt, TryStreamExt};
+use std::{io, path::Path, time::Instant};
+use axum::{extract::{Multipart, State}, BoxError, Json};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use axum::body::Bytes;
 
-use crate::{application::{command_service::{chunk_list_application_service, file_application_service}, query_service::{chunk_list_query_service}}, domain::{command::file_command::{AddChunkListCommand, AddFileCommand}, repository::chunk_list_repository}, infrastructure::{image_util::image_type, jwt::Claims}, ServerConfig};
+use crate::{application::{command_service::{chunk_list_application_service, file_application_service}, query_service::{chunk_list_query_service}}, domain::command::file_command::{AddChunkListCommand, AddFileCommand}, error::ApiError, infrastructure::{image_util::image_type, jwt::Claims, metrics, storage::{self, Volume}}, state::AppState};
 
-use super::dto::{file_entity::{FileEntityDTO, MultiFileEntityDTO}, media::{ChunkListDTO, MediaDTO}};
+use super::dto::{file_entity::{FileEntityDTO, MultiFileEntityDTO, UploadFileForm}, media::{ChunkListDTO, ChunkUploadForm, MediaDTO}, ApiResult};
 
 /// 上传文件
-pub async fn upload_file(State(state): State<Arc<ServerConfig>>, _: Claims, mut multipart: Multipart) -> Result<Json<MultiFileEntityDTO>, (StatusCode, String)> {
+#[utoipa::path(
+    post,
+    path = "/upload",
+    tag = "file",
+    security(("bearer_auth" = [])),
+    request_body(content = UploadFileForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = MultiFileEntityDTO),
+        (status = 400, body = ApiResult),
+        (status = 401, body = ApiResult),
+        (status = 429, description = "上传过于频繁", body = ApiResult),
+    )
+)]
+pub async fn upload_file(State(state): State<AppState>, _: Claims, mut multipart: Multipart) -> Result<Json<MultiFileEntityDTO>, ApiError> {
     let mut dtos = Vec::new();
-    while let Some(field) = multipart.next_field().await.unwrap() {
-        let name = field.name().unwrap().to_string();
-        let file_name = field.file_name().unwrap().to_string();
-        let content_type = field.content_type().unwrap().to_string();
+    while let Some(field) = multipart.next_field().await.map_err(|_| ApiError::InvalidParameter)? {
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field.file_name().ok_or(ApiError::InvalidParameter)?.to_string();
+        let content_type = field.content_type().unwrap_or_default().to_string();
         // let data = field.bytes().await.unwrap();
-        let extension = Path::new(&file_name).extension();
-        let ext = if extension.is_none() {
-            "".to_owned()
-        }else {
-            String::from(extension.unwrap().to_str().unwrap())
-        };
+        let ext = Path::new(&file_name).extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_owned();
         println!(
             "`{name}` (`{file_name}`: `{content_type}`: `{ext}`)"
         );
         let mut file_path = String::new();
         file_path.push_str(uuid::Uuid::new_v4().to_string().as_str());
         file_path.push_str(ext.as_str());
-        stream_to_file(&file_path, field, &state).await?;
+        stream_to_storage(&state, "file", "", &file_path, field).await?;
 
         let command = AddFileCommand {
             mime: content_type,
@@ -39,32 +48,24 @@ pub async fn upload_file(State(state): State<Arc<ServerConfig>>, _: Claims, mut
             path: file_path.to_string(),
             hash: Option::None,
         };
-        dtos.push(file_application_service::add_file(command).await.unwrap());
+        dtos.push(file_application_service::add_file(&state, command).await?);
     }
     Ok(Json(MultiFileEntityDTO { files: dtos }))
 }
 
-async fn stream_to_file<S, E>(path: &str, stream: S, config: &ServerConfig) -> Result<(), (StatusCode, String)> 
-where S: Stream<Item=Result<Bytes, E>>,
+/// 上传的文件保存在assets下,key_prefix为空或以/结尾
+async fn stream_to_storage<S, E>(state: &AppState, kind: &'static str, key_prefix: &str, file_name: &str, stream: S) -> Result<(), ApiError>
+where S: Stream<Item=Result<Bytes, E>> + Send,
       E: Into<BoxError>,
 {
-    if !path_is_valid(path) {
-        return Err((StatusCode::BAD_REQUEST, "Invalid path".to_owned()));
+    if !path_is_valid(file_name) {
+        return Err(ApiError::InvalidParameter);
     }
-    async {
-        // Convert the stream into an `AsyncRead`
-        let body_with_io_error = stream.map_err(io::Error::other);
-        let body_reader = StreamReader::new(body_with_io_error);
-        futures::pin_mut!(body_reader);
-
-        let path = std::path::Path::new(&config.assets_path).join(path);
-        let mut file = BufWriter::new(File::create(path).await?);
-
-        tokio::io::copy(&mut body_reader, &mut file).await?;
-
-        Ok::<_, io::Error>(())
-    }.await
-    .map_err(|err|(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+    let key = format!("{}{}", key_prefix, file_name);
+    let body = stream.map_err(io::Error::other).boxed();
+    let bytes = state.storage.put(Volume::Assets, &key, body).await?;
+    metrics::upload_bytes(kind, bytes);
+    Ok(())
 }
 
 fn path_is_valid(path: &str) -> bool {
@@ -80,32 +81,45 @@ fn path_is_valid(path: &str) -> bool {
     components.count() == 1
 }
 
-pub async fn upload_icon_file(State(state): State<Arc<ServerConfig>>, _: Claims, mut multipart: Multipart) -> Result<Json<FileEntityDTO>, (StatusCode, String)> {
-    let field = multipart.next_field().await.unwrap().unwrap();
+/// 上传专辑封面
+#[utoipa::path(
+    post,
+    path = "/upload_icon",
+    tag = "file",
+    security(("bearer_auth" = [])),
+    request_body(content = UploadFileForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = FileEntityDTO),
+        (status = 400, body = ApiResult),
+        (status = 401, body = ApiResult),
+        (status = 429, description = "上传过于频繁", body = ApiResult),
+        (status = 415, description = "不支持的图片格式", body = ApiResult),
+    )
+)]
+pub async fn upload_icon_file(State(state): State<AppState>, _: Claims, mut multipart: Multipart) -> Result<Json<FileEntityDTO>, ApiError> {
+    let field = multipart.next_field().await.map_err(|_| ApiError::InvalidParameter)?.ok_or(ApiError::InvalidParameter)?;
     // let name = field.name().unwrap().to_string();
-    let file_name = field.file_name().unwrap().to_string();
-    let content_type = field.content_type().unwrap().to_string();
+    let file_name = field.file_name().ok_or(ApiError::InvalidParameter)?.to_string();
+    let content_type = field.content_type().unwrap_or_default().to_string();
     // let data = field.bytes().await.unwrap();
-    let extension = Path::new(&file_name).extension();
-    if extension.is_none() {
-        return Err((StatusCode::INSUFFICIENT_STORAGE, "请上传图片格式文件".to_owned()))
-    }
-    let ext = extension.unwrap().to_str().unwrap();
+    let ext = Path::new(&file_name).extension()
+    .and_then(|extension| extension.to_str())
+    .ok_or(ApiError::UnsupportedMediaType)?;
     let image_type  = image_type(ext);
     if image_type.is_none() {
-        return Err((StatusCode::INSUFFICIENT_STORAGE, "请上传图片格式文件".to_owned()))
+        return Err(ApiError::UnsupportedMediaType)
     }
     // println!(
     //     "`{name}` (`{file_name}`: `{content_type}`: `{ext}`)"
     // );
     if !content_type.contains("image") {
-        return Err((StatusCode::INSUFFICIENT_STORAGE, "请上传图片格式文件".to_owned()))
+        return Err(ApiError::UnsupportedMediaType)
     }
     let mut file_path = String::new();
     file_path.push_str(uuid::Uuid::new_v4().to_string().as_str());
     file_path.push_str(".");
     file_path.push_str(ext);
-    stream_to_icon_file(&file_path, field, &state).await?;
+    stream_to_storage(&state, "icon", "icons/", &file_path, field).await?;
 
     let command = AddFileCommand {
         mime: content_type,
@@ -115,55 +129,45 @@ pub async fn upload_icon_file(State(state): State<Arc<ServerConfig>>, _: Claims,
         path: file_path.to_string(),
         hash: Option::None,
     };
-    let mut dto = file_application_service::add_file(command).await.unwrap();
-    let url_prefix = state.assets_http_addr.clone();
+    let mut dto = file_application_service::add_file(&state, command).await?;
+    let url_prefix = state.config.server.assets_http_addr.clone();
     dto.url = Some(url_prefix + "/icons/" + &dto.path);
     Ok(Json(dto))
 }
 
-async fn stream_to_icon_file<S, E>(path: &str, stream: S, config: &ServerConfig) -> Result<(), (StatusCode, String)> 
-where S: Stream<Item=Result<Bytes, E>>,
-      E: Into<BoxError>,
-{
-    if !path_is_valid(path) {
-        return Err((StatusCode::BAD_REQUEST, "Invalid path".to_owned()));
-    }
-    async {
-        // Convert the stream into an `AsyncRead`
-        let body_with_io_error = stream.map_err(io::Error::other);
-        let body_reader = StreamReader::new(body_with_io_error);
-        futures::pin_mut!(body_reader);
-
-        let path = std::path::Path::new(&config.assets_path).join("icons").join(path);
-        let mut file = BufWriter::new(File::create(path).await?);
-
-        tokio::io::copy(&mut body_reader, &mut file).await?;
-
-        Ok::<_, io::Error>(())
-    }.await
-    .map_err(|err|(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
-}
-
 /// 上传视频文件
-pub async fn upload_video_chunks(State(state): State<Arc<ServerConfig>>, _: Claims, mut multipart: Multipart) -> impl IntoResponse {
+#[utoipa::path(
+    post,
+    path = "/upload_media_chunks",
+    tag = "file",
+    security(("bearer_auth" = [])),
+    request_body(content = ChunkUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "分片已保存"),
+        (status = 400, body = ApiResult),
+        (status = 401, body = ApiResult),
+        (status = 429, description = "上传过于频繁", body = ApiResult),
+        (status = 415, body = ApiResult),
+    )
+)]
+pub async fn upload_video_chunks(State(state): State<AppState>, _: Claims, mut multipart: Multipart) -> Result<(), ApiError> {
     let mut file_name = String::new();
     let mut total_chunks = 0;
     let mut chunk_number = 0;
     let mut chunk_size = 0;
     let mut md5 = String::new();
-    let mut chunk_data = Vec::new();
+    let mut chunk_data = Bytes::new();
     let mut content_type = String::new();
 
     while let Some(field) = match multipart.next_field().await {
         Ok(f) => f,
         Err(_) => {
-            return StatusCode::BAD_REQUEST
+            return Err(ApiError::InvalidParameter)
         }
     } {
         let field_name = field.name().unwrap_or_default().to_string();
-        let type_opt = field.content_type();
-        if type_opt.is_some() {
-            content_type = field.content_type().unwrap().to_string();
+        if let Some(field_content_type) = field.content_type() {
+            content_type = field_content_type.to_string();
             println!("content_type:{}", &content_type);
         }
         match field_name.as_str() {
@@ -172,45 +176,33 @@ pub async fn upload_video_chunks(State(state): State<Arc<ServerConfig>>, _: Clai
             "chunkNumber" => chunk_number = field.text().await.unwrap_or_default().parse().unwrap_or(0),
             "chunkSize" => chunk_size = field.text().await.unwrap_or_default().parse().unwrap_or(0),
             "md5" => md5 = field.text().await.unwrap_or_default(),
-            "chunk" => chunk_data = field.bytes().await.unwrap_or_else(|_| Vec::new().into()).to_vec(),
+            "chunk" => chunk_data = field.bytes().await.unwrap_or_default(),
             _ => {}
         }
     }
     println!("final content_type:{}", &content_type);
     
     if file_name.is_empty() || md5.is_empty() || chunk_data.is_empty() {
-        return StatusCode::BAD_REQUEST
+        return Err(ApiError::InvalidParameter)
     }
 
-    let extension = Path::new(&file_name).extension();
-    if extension.is_none() {
-        return StatusCode::INSUFFICIENT_STORAGE
-    }
-    let ext = extension.unwrap().to_str().unwrap();
+    let ext = Path::new(&file_name).extension()
+    .and_then(|extension| extension.to_str())
+    .ok_or(ApiError::UnsupportedMediaType)?;
     if ext != "mkv" && ext != "mp4" {
-        return StatusCode::INSUFFICIENT_STORAGE
+        return Err(ApiError::UnsupportedMediaType)
     }
 
     let is_valid_md5 = is_valid_md5(&md5);
     if !is_valid_md5 {
-        return StatusCode::BAD_REQUEST
+        return Err(ApiError::InvalidParameter)
     }
     // if !content_type.contains("video") {
     //     return (StatusCode::INSUFFICIENT_STORAGE, "请上传视频格式文件".to_owned())
     // }
-    let temp_dir = format!("{}/{}", state.medias_path, md5);
-    fs::create_dir_all(&temp_dir).unwrap_or_else(|_| {}).await;
-    let chunk_path = format!("{}/chunk_{}", temp_dir, chunk_number);
-    let file_path = Path::new(&chunk_path);
-    if file_path.exists() {
-        if file_path.is_dir() {
-            let _ = fs::remove_dir_all(file_path).await;
-        }else {
-            let _ = fs::remove_file(file_path).await;
-        }
-    }
-    let mut file = File::create(&chunk_path).await.unwrap();
-    let _ = file.write_all(&chunk_data).await;
+    let chunk_key = format!("{}/chunk_{}", md5, chunk_number);
+    let bytes = storage::put_bytes(state.storage.as_ref(), Volume::Medias, &chunk_key, chunk_data).await?;
+    metrics::upload_bytes("chunk", bytes);
     let command = AddChunkListCommand{
         file_hash: md5,
         chunk_number: chunk_number,
@@ -218,8 +210,8 @@ pub async fn upload_video_chunks(State(state): State<Arc<ServerConfig>>, _: Clai
         file_name: file_name,
         total_chunks: total_chunks,
     };
-    let _ = chunk_list_application_service::add_chunk_list(command).await;
-    StatusCode::OK
+    chunk_list_application_service::add_chunk_list(&state, command).await?;
+    Ok(())
 }
 
 fn is_valid_md5(md5: &String) -> bool {
@@ -234,21 +226,32 @@ fn is_valid_md5(md5: &String) -> bool {
 }
 
 /// 合并上传文件
-pub async fn merge_chunk_list(State(state): State<Arc<ServerConfig>>, _: Claims, Json(payload): Json<MediaDTO>) -> Result<String, (StatusCode, String)> {
+#[utoipa::path(
+    post,
+    path = "/merge_chunks",
+    tag = "file",
+    security(("bearer_auth" = [])),
+    request_body = MediaDTO,
+    responses(
+        (status = 200, description = "合并后的文件路径", body = String, content_type = "text/plain"),
+        (status = 400, body = ApiResult),
+        (status = 401, body = ApiResult),
+        (status = 404, body = ApiResult),
+        (status = 409, description = "分片不完整", body = ApiResult),
+    )
+)]
+pub async fn merge_chunk_list(State(state): State<AppState>, _: Claims, Json(payload): Json<MediaDTO>) -> Result<String, ApiError> {
     let md5 = payload.file_hash;
     let is_valid_md5 = is_valid_md5(&md5);
     if !is_valid_md5 {
-        return Err((StatusCode::BAD_REQUEST, "Invalid parameter".to_owned()))
+        return Err(ApiError::InvalidParameter)
     }
 
-    let chunks = chunk_list_repository::query_chunk_list(&md5).await;
-    if chunks.is_empty() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, "未知文件".to_owned()))
-    }
-    let chunk = chunks.get(0).unwrap();
+    let chunks = state.chunk_list_repository.query_chunk_list(&md5).await?;
+    let chunk = chunks.first().ok_or(ApiError::FileNotFound)?;
     let total_chunks = chunk.total_chunks;
     if total_chunks != chunks.len() as i32 {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Chunk不完整".to_owned()))
+        return Err(ApiError::IncompleteChunks)
     }
     // let file_path = Path::new(&state.medias_path).join(&md5);
     // let mut cnt = 0;
@@ -269,33 +272,58 @@ pub async fn merge_chunk_list(State(state): State<Arc<ServerConfig>>, _: Claims,
     // if chunks.get(0).unwrap().total_chunks != cnt {
     //     return Err((StatusCode::INTERNAL_SERVER_ERROR, "Chunk不完整".to_owned()))
     // }
-    let temp_dir = format!("{}/{}", &state.medias_path, &md5);
     let file_name = chunk.file_name.clone();
     let path = Path::new(&file_name);
-    let extension = path.extension().unwrap().to_str().unwrap();
+    let extension = path.extension().and_then(|extension| extension.to_str()).ok_or(ApiError::UnsupportedMediaType)?;
     let target_name = md5.clone() + "." + extension;
-    let output_path = format!("{}/{}", &temp_dir, &target_name);
-    let output = Path::new(&output_path);
-    if !output.exists() {
-        let mut output_file = File::create(&output_path).await.unwrap();
-        for chunk_number in 0..total_chunks {
-            let chunk_path = format!("{}/chunk_{}", &temp_dir, chunk_number);
-            let chunk_data = fs::read(&chunk_path).await;
-            let _ = output_file.write_all(&chunk_data.unwrap()).await;
+    let output_key = format!("{}/{}", &md5, &target_name);
+    if state.storage.size(Volume::Medias, &output_key).await?.is_none() {
+        let started = Instant::now();
+        let chunk_keys: Vec<String> = (0..total_chunks).map(|chunk_number| format!("{}/chunk_{}", &md5, chunk_number)).collect();
+        for chunk_key in &chunk_keys {
+            if state.storage.size(Volume::Medias, chunk_key).await?.is_none() {
+                return Err(ApiError::IncompleteChunks)
+            }
         }
+        // 按顺序读取分片写入目标文件,不把整个文件读入内存
+        let storage = state.storage.clone();
+        let body = stream::iter(chunk_keys)
+        .then(move |chunk_key| {
+            let storage = storage.clone();
+            async move {
+                let chunk = storage.get(Volume::Medias, &chunk_key, None).await.map_err(io::Error::other)?;
+                chunk.map(|chunk| chunk.body).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, chunk_key))
+            }
+        })
+        .try_flatten()
+        .boxed();
+        state.storage.put(Volume::Medias, &output_key, body).await?;
+        metrics::chunk_merge(started.elapsed());
     }
     // fs::remove_dir_all(temp_dir).await?;
     Ok(format!("{}/{}", &md5, &target_name))
 }
 
 /// 检查上传分片
-pub async fn check_chunks(_: Claims, Json(payload): Json<MediaDTO>) -> Result<Json<Vec<ChunkListDTO>>, (StatusCode, String)> {
+#[utoipa::path(
+    post,
+    path = "/check_chunks",
+    tag = "file",
+    security(("bearer_auth" = [])),
+    request_body = MediaDTO,
+    responses(
+        (status = 200, description = "已上传的分片", body = Vec<ChunkListDTO>),
+        (status = 400, body = ApiResult),
+        (status = 401, body = ApiResult),
+    )
+)]
+pub async fn check_chunks(State(state): State<AppState>, _: Claims, Json(payload): Json<MediaDTO>) -> Result<Json<Vec<ChunkListDTO>>, ApiError> {
     let md5 = payload.file_hash;
     let is_valid_md5 = is_valid_md5(&md5);
     if !is_valid_md5 {
-        return Err((StatusCode::BAD_REQUEST, "Invalid parameter".to_owned()))
+        return Err(ApiError::InvalidParameter)
     }
-    let dtos = chunk_list_query_service::query_chunk_list(&md5).await;
+    let dtos = chunk_list_query_service::query_chunk_list(&state, &md5).await?;
     Ok(Json(dtos))
     // if dtos.is_empty() {
     //     return Ok(Json(ChunkInfoDTO{
@@ -322,7 +350,7 @@ pub async fn check_chunks(_: Claims, Json(payload): Json<MediaDTO>) -> Result<Js
 //     if !is_valid_md5 {
 //         return Err((StatusCode::BAD_REQUEST, "Invalid parameter".to_owned()))
 //     }
-//     let dtos = chunk_list_query_service::query_chunk_list(&md5).await;
+//     let dtos = chunk_list_query_service::query_chunk_list(&state, &md5).await;
 //     Ok(Json(dtos))
 //     // if dtos.is_empty() {
 //     //     return Ok(Json(ChunkInfoDTO{
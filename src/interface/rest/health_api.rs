@@ -0,0 +1,78 @@
+//! 探活、就绪检查和Prometheus指标,挂在根路径下,不属于v1接口
+
+use std::time::{Duration, Instant};
+
+use axum::{extract::{MatchedPath, Request, State}, http::StatusCode, middleware::Next, response::{IntoResponse, Response}, Json};
+use futures::future::join_all;
+
+use crate::{infrastructure::{health::HealthCheck, metrics, telemetry}, state::AppState, supervisor::{TaskHealth, TaskState}};
+
+use super::dto::health::{CheckDTO, ReadinessDTO};
+
+/// 单个依赖检查的超时时间
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 进程存活即返回200
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// 数据库、Redis、Sui fullnode均可用且后台任务都在运行(或已正常结束)时返回200,否则返回503
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let checks = join_all(state.health_checks.iter().map(|(name, check)| async move {
+        (*name, run_check(check.as_ref()).await)
+    })).await;
+    let tasks = state.tasks.snapshot();
+    let ready = checks.iter().all(|(_, check)| check.ok) && tasks.values().all(task_ready);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let dto = ReadinessDTO {
+        status: if ready { "ok" } else { "unavailable" },
+        checks: checks.into_iter().collect(),
+        tasks,
+    };
+    (status, Json(dto))
+}
+
+async fn run_check(check: &dyn HealthCheck) -> CheckDTO {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, check.check()).await {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(format!("{:#}", err)),
+        Err(_) => Some(format!("timed out after {:?}", CHECK_TIMEOUT)),
+    };
+    CheckDTO { ok: error.is_none(), error, elapsed_ms: started.elapsed().as_millis() as u64 }
+}
+
+/// 正在运行且没有上报连接断开;正常结束的任务不影响readiness,停止过程中的任务视为未就绪
+fn task_ready(task: &TaskHealth) -> bool {
+    match task.state {
+        TaskState::Running => task.connected != Some(false),
+        TaskState::Finished => true,
+        TaskState::Restarting | TaskState::Stopped => false,
+    }
+}
+
+/// Prometheus格式的指标,连接池使用情况在采集时更新
+pub async fn prometheus_metrics(State(state): State<AppState>) -> String {
+    for (name, check) in state.health_checks.iter() {
+        if let Some(status) = check.pool_status() {
+            metrics::pool(name, status);
+        }
+    }
+    metrics::install().render()
+}
+
+/// 记录接口耗时并以路由模板命名请求的span,需要以route_layer挂载才能取到匹配的路由模板
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_owned());
+    if let Some(route) = &route {
+        telemetry::record_route(&method, route);
+    }
+    let response = next.run(request).await;
+    if let Some(route) = route {
+        metrics::http_request(method.as_str(), &route, response.status().as_u16(), started.elapsed());
+    }
+    response
+}
@@ -0,0 +1,75 @@
+use axum::{extract::Request, http::{header::ACCEPT_LANGUAGE, HeaderMap}, middleware::Next, response::Response};
+
+/// 接口返回信息的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Language {
+    /// 按Accept-Language的权重选择支持的语言,如"en-US,en;q=0.9,zh;q=0.8"
+    pub fn from_accept_language(value: &str) -> Option<Self> {
+        let mut candidates: Vec<(&str, f32)> = value.split(',')
+        .filter_map(|part| {
+            let mut pieces = part.split(';');
+            let tag = pieces.next()?.trim();
+            let quality = pieces.find_map(|piece| piece.trim().strip_prefix("q="))
+            .map(|quality| quality.trim().parse::<f32>().unwrap_or(0.0))
+            .unwrap_or(1.0);
+            Some((tag, quality))
+        })
+        .filter(|(tag, quality)| !tag.is_empty() && *quality > 0.0)
+        .collect();
+        // 稳定排序,同权重时保持原有顺序
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.into_iter().find_map(|(tag, _)| {
+            let primary = tag.split('-').next().unwrap_or_default().to_ascii_lowercase();
+            match primary.as_str() {
+                "zh" => Some(Language::Zh),
+                "en" => Some(Language::En),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers.get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Language::from_accept_language)
+        .unwrap_or_default()
+    }
+}
+
+tokio::task_local! {
+    static LANGUAGE: Language;
+}
+
+/// 当前请求的语言,不在请求上下文中时为默认语言
+pub fn current_language() -> Language {
+    LANGUAGE.try_with(|language| *language).unwrap_or_default()
+}
+
+/// 根据Accept-Language设置当前请求的语言
+pub async fn language_layer(request: Request, next: Next) -> Response {
+    let language = Language::from_headers(request.headers());
+    LANGUAGE.scope(language, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Language;
+
+    #[test]
+    fn test_from_accept_language() {
+        assert_eq!(Language::from_accept_language("en-US,en;q=0.9,zh;q=0.8"), Some(Language::En));
+        assert_eq!(Language::from_accept_language("zh-CN,zh;q=0.9,en;q=0.8"), Some(Language::Zh));
+        assert_eq!(Language::from_accept_language("fr;q=1.0, en;q=0.5, zh;q=0.7"), Some(Language::Zh));
+        assert_eq!(Language::from_accept_language("EN"), Some(Language::En));
+        assert_eq!(Language::from_accept_language("zh;q=0, en;q=0.1"), Some(Language::En));
+        assert_eq!(Language::from_accept_language("fr, de"), None);
+        assert_eq!(Language::from_accept_language("*"), None);
+        assert_eq!(Language::from_accept_language(""), None);
+    }
+}
\ No newline at end of file
@@ -1,13 +1,25 @@
-use axum::{http::StatusCode, Json};
+use axum::{extract::State, Json};
 use jsonwebtoken::{encode, Header};
 // use hex::FromHex;
-use crate::{application::command_service::account_application_service, domain::repository::account_repository, infrastructure::jwt::{AuthBody, AuthError, Claims, KEYS}, interface::rest::validate::validate_signature, utils};
+use crate::{application::command_service::account_application_service, error::ApiError, infrastructure::jwt::{AuthBody, Claims}, interface::rest::{rate_limit::{guard_signature, ClientIp}, validate::validate_signature}, state::AppState, utils};
 // use ed25519_dalek::{Signature, VerifyingKey};
-use super::dto::logon::{SignInPayload, SignUpPayload};
+use super::dto::{logon::{SignInPayload, SignUpPayload}, ApiResult};
 
 /// 用户登录
-pub async fn sign_in(Json(payload): Json<SignInPayload>) -> Result<Json<AuthBody>, (StatusCode, String)> {
-    tracing::debug!("{}", serde_json::to_string(&payload).unwrap());
+#[utoipa::path(
+    post,
+    path = "/signin",
+    tag = "account",
+    request_body = SignInPayload,
+    responses(
+        (status = 200, body = AuthBody),
+        (status = 400, description = "请求参数错误或请求id已过期", body = ApiResult),
+        (status = 401, description = "签名错误或账户不存在", body = ApiResult),
+        (status = 429, description = "请求过于频繁或签名错误次数过多", body = ApiResult),
+    )
+)]
+pub async fn sign_in(State(state): State<AppState>, client: ClientIp, Json(payload): Json<SignInPayload>) -> Result<Json<AuthBody>, ApiError> {
+    tracing::debug!("{:?}", serde_json::to_string(&payload));
 
     // if payload.pub_key.is_empty() {
     //     return Err((StatusCode::BAD_REQUEST, "Missing credentials".to_owned()));
@@ -38,16 +50,12 @@ pub async fn sign_in(Json(payload): Json<SignInPayload>) -> Result<Json<AuthBody
     //     return Err((StatusCode::UNAUTHORIZED, "Wrong credentials".to_owned()));
     // }
 
-    let validate_result = validate_signature(&payload.pub_key, &payload.request_id, &payload.sig);
-    if validate_result.is_err() {
-        let (code, message) = validate_result.err().unwrap();
-        return Err((code, message));
-    }
+    guard_signature(&state, client, &payload.pub_key, || validate_signature(&payload.pub_key, &payload.request_id, &payload.sig)).await?;
 
     // 数据库查询账户
-    let accounts = account_repository::find_by_pubkey(&payload.pub_key).await;
-    if accounts.is_empty() {
-        return Err((StatusCode::UNAUTHORIZED, "Invalid Account".to_owned()));
+    let account = state.account_repository.get_account_by(&payload.pub_key).await?;
+    if account.is_none() {
+        return Err(ApiError::UnknownAccount);
     }
     
     // 登录成功，返回登录成功信息
@@ -57,20 +65,29 @@ pub async fn sign_in(Json(payload): Json<SignInPayload>) -> Result<Json<AuthBody
         exp: utils::current_seconds() + 24 * 60 * 60
     };
     // Create the authorization token
-    let token = encode(&Header::default(), &claims, &KEYS.encoding)
-        .map_err(|_| AuthError::TokenCreation);
-
-    if token.is_err() {
-        return Err( (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error".to_owned()));
-    }
+    let token = encode(&Header::default(), &claims, &state.jwt_keys.encoding)
+        .map_err(|_| ApiError::TokenCreation)?;
 
     // Send the authorized token
-    Ok(Json(AuthBody::new(token.unwrap())))
+    Ok(Json(AuthBody::new(token)))
 }
 
 /// 用户注册
-pub async fn sign_up(Json(payload): Json<SignUpPayload>) -> Result<Json<AuthBody>, (StatusCode, String)> {
-    tracing::debug!("{}", serde_json::to_string(&payload).unwrap());
+#[utoipa::path(
+    post,
+    path = "/signup",
+    tag = "account",
+    request_body = SignUpPayload,
+    responses(
+        (status = 200, body = AuthBody),
+        (status = 400, description = "请求参数错误或请求id已过期", body = ApiResult),
+        (status = 401, description = "签名错误", body = ApiResult),
+        (status = 409, description = "账户已存在", body = ApiResult),
+        (status = 429, description = "请求过于频繁或签名错误次数过多", body = ApiResult),
+    )
+)]
+pub async fn sign_up(State(state): State<AppState>, client: ClientIp, Json(payload): Json<SignUpPayload>) -> Result<Json<AuthBody>, ApiError> {
+    tracing::debug!("{:?}", serde_json::to_string(&payload));
 
     // if payload.pub_key.is_empty() {
     //     return Err((StatusCode::BAD_REQUEST, "Missing credentials".to_owned()));
@@ -101,17 +118,10 @@ pub async fn sign_up(Json(payload): Json<SignUpPayload>) -> Result<Json<AuthBody
     //     return Err((StatusCode::UNAUTHORIZED, "Wrong credentials".to_owned()));
     // }
 
-    let validate_result = validate_signature(&payload.pub_key, &payload.request_id, &payload.sig);
-    if validate_result.is_err() {
-        let (code, message) = validate_result.err().unwrap();
-        return Err((code, message));
-    }
+    guard_signature(&state, client, &payload.pub_key, || validate_signature(&payload.pub_key, &payload.request_id, &payload.sig)).await?;
 
     // 账户信息校验并入库
-    let account_id = account_application_service::register_account(&payload).await;
-    if account_id.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, account_id.err().unwrap().to_string()));
-    }
+    account_application_service::register_account(&state, &payload).await?;
     
     // 注册成功，返回登录成功信息
     let claims = Claims {
@@ -120,13 +130,9 @@ pub async fn sign_up(Json(payload): Json<SignUpPayload>) -> Result<Json<AuthBody
         exp: utils::current_seconds() + 24 * 60 * 60
     };
     // Create the authorization token
-    let token = encode(&Header::default(), &claims, &KEYS.encoding)
-        .map_err(|_| AuthError::TokenCreation);
-
-    if token.is_err() {
-        return Err( (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error".to_owned()));
-    }
+    let token = encode(&Header::default(), &claims, &state.jwt_keys.encoding)
+        .map_err(|_| ApiError::TokenCreation)?;
 
     // Send the authorized token
-    Ok(Json(AuthBody::new(token.unwrap())))
+    Ok(Json(AuthBody::new(token)))
 }
\ No newline at end of file
@@ -1,8 +1,7 @@
-use axum::{http::StatusCode, response::IntoResponse};
-use redis::{AsyncCommands, RedisError};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use uuid::Uuid;
 
-use crate::infrastructure::redis_connection;
+use crate::state::AppState;
 
 pub mod dto;
 pub mod assembler;
@@ -13,16 +12,31 @@ pub mod account_api;
 pub mod validate;
 pub mod file_api;
 pub mod media_api;
+pub mod tag_api;
+pub mod i18n;
+pub mod cors;
+pub mod health_api;
+pub mod openapi;
+pub mod rate_limit;
+pub mod storage_api;
+pub mod v1;
 
-pub async fn request_id() -> impl IntoResponse {
-    // let pool = redis_connection::get_redis_pool();
-    // let mut connection = pool.get().await.expect("Failed to get connection");
-    let mut connection = redis_connection::get_redis_connection().await;
+/// 获取请求id,60秒内有效,用于签名和防重复提交
+#[utoipa::path(
+    get,
+    path = "/request_id",
+    tag = "account",
+    responses(
+        (status = 200, body = String, content_type = "text/plain"),
+        (status = 429, description = "请求过于频繁", body = dto::ApiResult),
+    )
+)]
+pub async fn request_id(State(state): State<AppState>) -> impl IntoResponse {
     let uuid = Uuid::new_v4().to_string();
 
-    let _ : Result<(), RedisError>= connection
-    .set_ex(&uuid, 1 as u64, 60)
-    .await;
-    
+    if let Err(e) = state.cache.set_ex(&uuid, "1", 60).await {
+        tracing::error!("保存request id失败: {}", e);
+    }
+
     (StatusCode::OK, uuid)
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+use axum::Json;
+use utoipa::{openapi::{security::{HttpAuthScheme, HttpBuilder, SecurityScheme}, OpenApi as OpenApiDocument}, Modify, OpenApi};
+
+use super::{account_api, file_api, logon_api, my_collection_api, public_collection_api, tag_api};
+
+/// 接口文档,由各接口的`#[utoipa::path]`和DTO生成
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Bassinet API"),
+    servers((url = "/api/v1")),
+    paths(
+        file_api::upload_file,
+        file_api::upload_video_chunks,
+        file_api::merge_chunk_list,
+        file_api::check_chunks,
+        file_api::upload_icon_file,
+        logon_api::sign_up,
+        logon_api::sign_in,
+        account_api::get_account_info,
+        account_api::get_authors,
+        my_collection_api::create_collection,
+        my_collection_api::get_my_collections,
+        my_collection_api::get_my_collection_info_by_id,
+        my_collection_api::get_simple_collections,
+        my_collection_api::create_article,
+        my_collection_api::add_video,
+        tag_api::set_collection_tags,
+        tag_api::get_related_collections,
+        tag_api::get_categories,
+        tag_api::get_tags,
+        public_collection_api::get_author_collections,
+        public_collection_api::search_collections,
+        public_collection_api::get_collection_info_by_id,
+        public_collection_api::get_collection_simple_by_id,
+        public_collection_api::get_collection_metadata,
+        public_collection_api::get_token_metadata,
+        public_collection_api::get_image,
+        public_collection_api::get_thumbnail,
+        public_collection_api::get_article_by_id,
+        public_collection_api::get_video_by_id,
+        super::request_id,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "account", description = "账户与登录"),
+        (name = "my_collection", description = "我的专辑"),
+        (name = "collection", description = "公开专辑"),
+        (name = "tag", description = "分类与标签"),
+        (name = "file", description = "文件上传"),
+    )
+)]
+pub struct ApiDoc;
+
+/// `jwt::Claims`使用的Bearer认证,token由登录/注册接口返回
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// OpenAPI文档
+pub async fn openapi_json() -> Json<OpenApiDocument> {
+    Json(ApiDoc::openapi())
+}
@@ -1,144 +1,236 @@
-use std::{path::Path as FilePath, sync::Arc};
+use std::path::Path as FilePath;
 
-use axum::{body::Body, extract::{Path, Query, State}, http::{header::{CONTENT_TYPE}, StatusCode}, response::{IntoResponse, Response}, Json};
-use tokio::fs::File;
-use tokio_util::io::ReaderStream;
+use chrono::DateTime;
+use axum::{body::Body, extract::{Path, Query, State}, http::header::CONTENT_TYPE, response::Response, Json};
+use uuid::Uuid;
 
-use crate::{application::query_service::{account_query_service, collection_query_service}, domain::repository::collection_repository, infrastructure::{image_util::{image_type, make_thumbnail}, jwt::Claims}, ServerConfig};
+use crate::{application::query_service::{account_query_service, collection_query_service, nft_metadata_query_service}, domain::{repository::search_repository::CollectionSearchCriteria, service::access_policy::Viewer}, error::ApiError, infrastructure::{image_util::{image_type, store_thumbnail, thumbnail_key}, jwt::Claims, storage::{self, Volume}}, state::AppState};
 
-use super::dto::{collection::{ArticleInfoDTO, CollectionInfoDTO, CollectionItemInfoDTO, CollectionPageDTOList, CollectionSimpleInfoDTO, PageInfo}, PageQueryArgs};
+use super::dto::{collection::{ArticleInfoDTO, CollectionInfoDTO, CollectionItemInfoDTO, CollectionPageDTO, CollectionSimpleInfoDTO}, nft::NftMetadataDTO, ApiResult, PageDTOList, PageQueryArgs};
 
 /// 获取专辑详细信息,包括专辑包括的所有内容(目前只有图文)
-pub async fn get_collection_info_by_id(State(config): State<Arc<ServerConfig>>, Path(collection_id): Path<String>) -> Result<Json<CollectionInfoDTO>, (StatusCode, String)> {
-    let response_result = collection_query_service::get_collection_by_id(&collection_id, &config.assets_http_addr, &config.medias_http_addr).await;
-    if response_result.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, response_result.err().unwrap().to_string()));
-    }
-    Ok(Json(response_result.unwrap()))
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}",
+    tag = "collection",
+    security((), ("bearer_auth" = [])),
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    responses(
+        (status = 200, description = "未持有NFT时专辑项为锁定状态", body = CollectionInfoDTO),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_collection_info_by_id(State(state): State<AppState>, claims: Option<Claims>, Path(collection_id): Path<String>) -> Result<Json<CollectionInfoDTO>, ApiError> {
+    let viewer = account_query_service::get_viewer(&state, claims.as_ref().map(|claims| &claims.pubkey)).await;
+    let config = &state.config.server;
+    let collection = collection_query_service::get_collection_by_id(&state, &collection_id, &viewer, &config.assets_http_addr, &config.medias_http_addr).await?;
+    Ok(Json(collection))
 }
 
 /// 某创作者的专辑(分页查询)
-pub async fn get_author_collections(State(config): State<Arc<ServerConfig>>, Path(author_id): Path<String>, Query(args): Query<PageQueryArgs>) -> Result<Json<CollectionPageDTOList>, (StatusCode, String)> {
-    let page = if args.page.is_none() || args.page.unwrap() < 1 {1} else {args.page.unwrap()};
-    let page_size = if args.page_size.is_none() || args.page_size.unwrap() < 1 {10} else {args.page_size.unwrap()};
-    let page_data = collection_query_service::get_author_collections(author_id.clone(), page as u64, page_size as u64, &config.assets_http_addr).await;
-    let total = page_data.1;
-    Ok(Json(CollectionPageDTOList{
-        dtos: page_data.0,
-        page_info: PageInfo{
-            total: total,
-            pages: (total + page_size as u64 - 1) / page_size as u64
-        }
-    }))
+#[utoipa::path(
+    get,
+    path = "/author/{author_id}/collections",
+    tag = "collection",
+    params(("author_id" = String, Path, description = "创作者账户ID(uuid)"), PageQueryArgs),
+    responses(
+        (status = 200, body = PageDTOList<CollectionPageDTO>),
+        (status = 400, body = ApiResult),
+    )
+)]
+pub async fn get_author_collections(State(state): State<AppState>, Path(author_id): Path<String>, Query(args): Query<PageQueryArgs>) -> Result<Json<PageDTOList<CollectionPageDTO>>, ApiError> {
+    let author_id = Uuid::parse_str(&author_id).map_err(|_| ApiError::UnknownAuthor)?;
+    let request = args.page_request()?;
+    let page_data = collection_query_service::get_author_collections(&state, author_id, &request, &state.config.server.assets_http_addr).await?;
+    Ok(Json(PageDTOList::new(page_data, &request)))
 }
 
 /// 搜索专辑(分页查询)
-pub async fn search_collections(State(config): State<Arc<ServerConfig>>, Query(args): Query<PageQueryArgs>) -> Result<Json<CollectionPageDTOList>, (StatusCode, String)> {
-    let page = if args.page.is_none() || args.page.unwrap() < 1 {1} else {args.page.unwrap()};
-    let page_size = if args.page_size.is_none() || args.page_size.unwrap() < 1 {10} else {args.page_size.unwrap()};
-    let page_data = collection_query_service::search_collections(args.keyword.clone(), args.author.clone(), page as u64, page_size as u64, &config.assets_http_addr).await;
-    let total = page_data.1;
-    Ok(Json(CollectionPageDTOList { dtos: page_data.0, page_info: PageInfo { total: total, pages: (total + page_size as u64 - 1) / page_size as u64 } }))
+#[utoipa::path(
+    get,
+    path = "/collections",
+    tag = "collection",
+    params(PageQueryArgs),
+    responses(
+        (status = 200, description = "包含分面统计", body = PageDTOList<CollectionPageDTO>),
+        (status = 400, body = ApiResult),
+    )
+)]
+pub async fn search_collections(State(state): State<AppState>, Query(args): Query<PageQueryArgs>) -> Result<Json<PageDTOList<CollectionPageDTO>>, ApiError> {
+    let request = args.page_request()?;
+    let category_id = args.category_id.as_ref().map(|category_id| Uuid::parse_str(category_id)).transpose()
+    .map_err(|_| ApiError::UnknownCategory)?;
+    let author = args.author.as_ref().map(|author| Uuid::parse_str(author)).transpose()
+    .map_err(|_| ApiError::UnknownAuthor)?;
+    let criteria = CollectionSearchCriteria {
+        keyword: args.keyword.clone(),
+        author,
+        category: args.category.clone(),
+        category_id,
+        tag: args.tag.clone(),
+        listed: args.listed,
+        created_from: args.from.and_then(|from| DateTime::from_timestamp(from, 0)).map(|from| from.naive_utc()),
+        created_to: args.to.and_then(|to| DateTime::from_timestamp(to, 0)).map(|to| to.naive_utc()),
+    };
+    let page_data = collection_query_service::search_collections(&state, &criteria, &request, &state.config.server.assets_http_addr).await?;
+    let facets = collection_query_service::search_facets(&state, &criteria).await?;
+    Ok(Json(PageDTOList::new(page_data, &request).with_facets(facets)))
 }
 
 /// 获取文章详情
-pub async fn get_article_by_id(Path(article_id): Path<String>) -> Result<Json<ArticleInfoDTO>, (StatusCode, String)> {
-    // TODO 鉴权
-    let article = collection_query_service::get_article_by_id(article_id).await;
-    if article.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, article.err().unwrap().to_string()));
-    }
-    Ok(Json(article.unwrap()))
+#[utoipa::path(
+    get,
+    path = "/articles/{article_id}",
+    tag = "collection",
+    security((), ("bearer_auth" = [])),
+    params(("article_id" = String, Path, description = "图文ID(uuid)")),
+    responses(
+        (status = 200, description = "未持有NFT时只返回试读部分", body = ArticleInfoDTO),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_article_by_id(State(state): State<AppState>, claims: Option<Claims>, Path(article_id): Path<String>) -> Result<Json<ArticleInfoDTO>, ApiError> {
+    let viewer = account_query_service::get_viewer(&state, claims.as_ref().map(|claims| &claims.pubkey)).await;
+    let article = collection_query_service::get_article_by_id(&state, article_id, &viewer).await?;
+    Ok(Json(article))
 }
 
 /// 获取视频详情
-pub async fn get_video_by_id(State(config): State<Arc<ServerConfig>>, claims:Claims, Path(video_id): Path<String>) -> Result<Json<CollectionItemInfoDTO>, (StatusCode, String)> {
-    // TODO 鉴权
-    let account = account_query_service::get_account_info(&claims.pubkey).await;
-    if account.is_err() {
-        return Err((StatusCode::BAD_REQUEST, "未知账户".to_owned()))
-    }
-    let video = collection_query_service::get_video_by_id(video_id, &config.medias_http_addr, account.unwrap()).await;
-    if video.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, video.err().unwrap().to_string()));
-    }
-    Ok(Json(video.unwrap()))
+#[utoipa::path(
+    get,
+    path = "/videos/{video_id}",
+    tag = "collection",
+    security(("bearer_auth" = [])),
+    params(("video_id" = String, Path, description = "视频ID(uuid)")),
+    responses(
+        (status = 200, body = CollectionItemInfoDTO),
+        (status = 401, body = ApiResult),
+        (status = 403, body = ApiResult),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_video_by_id(State(state): State<AppState>, claims:Claims, Path(video_id): Path<String>) -> Result<Json<CollectionItemInfoDTO>, ApiError> {
+    let account = account_query_service::get_account_info(&state, &claims.pubkey).await?;
+    let viewer = Viewer::new(account.account_id, account.wallet_address);
+    let video = collection_query_service::get_video_by_id(&state, video_id, &state.config.server.medias_http_addr, &viewer).await?;
+    Ok(Json(video))
 }
 
 /// 获取集合简要信息
-pub async fn get_collection_simple_by_id(State(config): State<Arc<ServerConfig>>, Path(collection_id): Path<String>) -> Result<Json<CollectionSimpleInfoDTO>, (StatusCode, String)> {
-    let response_result: Result<CollectionSimpleInfoDTO, anyhow::Error> = collection_query_service::get_collection_simple_info_by_id(&collection_id, &config.assets_http_addr).await;
-    if response_result.is_err() {
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, response_result.err().unwrap().to_string()));
-    }
-    Ok(Json(response_result.unwrap()))
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}/simpleinfo",
+    tag = "collection",
+    security((), ("bearer_auth" = [])),
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    responses(
+        (status = 200, body = CollectionSimpleInfoDTO),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_collection_simple_by_id(State(state): State<AppState>, claims: Option<Claims>, Path(collection_id): Path<String>) -> Result<Json<CollectionSimpleInfoDTO>, ApiError> {
+    let viewer = account_query_service::get_viewer(&state, claims.as_ref().map(|claims| &claims.pubkey)).await;
+    let collection = collection_query_service::get_collection_simple_info_by_id(&state, &collection_id, &viewer, &state.config.server.assets_http_addr).await?;
+    Ok(Json(collection))
 }
 
-/// 获取专辑图片
-pub async fn get_image(State(config): State<Arc<ServerConfig>>, Path(collection_id): Path<String>) -> impl IntoResponse {
-    let collection = collection_repository::get_by_id(&collection_id).await;
-    if collection.is_none() {
-        return StatusCode::NOT_FOUND.into_response()
-    }
-    let collection = collection.unwrap();
+/// 已上架专辑的NFT元数据,兼容ERC-721元数据标准和Sui Display
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}/metadata",
+    tag = "collection",
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    responses(
+        (status = 200, body = NftMetadataDTO),
+        (status = 404, description = "专辑不存在或未上架NFT", body = ApiResult),
+    )
+)]
+pub async fn get_collection_metadata(State(state): State<AppState>, Path(collection_id): Path<String>) -> Result<Json<NftMetadataDTO>, ApiError> {
+    let metadata = nft_metadata_query_service::collection_metadata(&state, &collection_id).await?;
+    Ok(Json(metadata))
+}
 
-    let file_path = config.assets_path.clone() + &collection.icon_url.unwrap();
-    let path = FilePath::new(&file_path);
-    let extension = path.extension();
-    if extension.is_none() {
-        return StatusCode::NOT_FOUND.into_response()
-    }
-    let extension = extension.unwrap().to_str().unwrap();
-    let image_type = image_type(extension);
-    if image_type.is_none() {
-        return StatusCode::NOT_FOUND.into_response()
-    }
-    let file =  File::open(&file_path).await;
-    if file.is_err() {
-        return StatusCode::NOT_FOUND.into_response()
-    }
-    let file = file.unwrap();
-    let stream = ReaderStream::new(file);
-    Response::builder()
-    .header(CONTENT_TYPE, image_type.unwrap())
-    .body(Body::from_stream(stream))
-    .unwrap()
+/// 某个NFT的元数据
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}/tokens/{edition}/metadata",
+    tag = "collection",
+    params(
+        ("collection_id" = String, Path, description = "专辑ID(uuid)"),
+        ("edition" = u64, Path, description = "NFT编号,从1开始,不超过limit"),
+    ),
+    responses(
+        (status = 200, body = NftMetadataDTO),
+        (status = 404, description = "专辑不存在、未上架NFT或编号超出范围", body = ApiResult),
+    )
+)]
+pub async fn get_token_metadata(State(state): State<AppState>, Path((collection_id, edition)): Path<(String, u64)>) -> Result<Json<NftMetadataDTO>, ApiError> {
+    let metadata = nft_metadata_query_service::token_metadata(&state, &collection_id, edition).await?;
+    Ok(Json(metadata))
+}
+
+/// 获取专辑图片
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}/image",
+    tag = "collection",
+    security((), ("bearer_auth" = [])),
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    responses(
+        (status = 200, description = "专辑封面", content_type = "image/*", body = Vec<u8>),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_image(State(state): State<AppState>, claims: Option<Claims>, Path(collection_id): Path<String>) -> Result<Response, ApiError> {
+    let viewer = account_query_service::get_viewer(&state, claims.as_ref().map(|claims| &claims.pubkey)).await;
+    let icon_url = collection_query_service::get_collection_icon_url(&state, &collection_id, &viewer).await?;
+    let (icon_key, image_type) = icon_key(icon_url)?;
+    let icon = state.storage.get(Volume::Assets, &icon_key, None).await?.ok_or(ApiError::FileNotFound)?;
+    image_response(Body::from_stream(icon.body), image_type)
 }
 
 /// 获取专辑缩略图
-pub async fn get_thumbnail(State(config): State<Arc<ServerConfig>>, Path(collection_id): Path<String>) -> impl IntoResponse {
-    let collection = collection_repository::get_by_id(&collection_id).await;
-    if collection.is_none() {
-        return StatusCode::NOT_FOUND.into_response()
-    }
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}/thumbnail",
+    tag = "collection",
+    security((), ("bearer_auth" = [])),
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    responses(
+        (status = 200, description = "专辑封面缩略图", content_type = "image/*", body = Vec<u8>),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_thumbnail(State(state): State<AppState>, claims: Option<Claims>, Path(collection_id): Path<String>) -> Result<Response, ApiError> {
+    let viewer = account_query_service::get_viewer(&state, claims.as_ref().map(|claims| &claims.pubkey)).await;
+    let icon_url = collection_query_service::get_collection_icon_url(&state, &collection_id, &viewer).await?;
+    let (icon_key, image_type) = icon_key(icon_url)?;
+    let thumbnail_key = thumbnail_key(&icon_key).ok_or(ApiError::FileNotFound)?;
+    let body = match state.storage.get(Volume::Assets, &thumbnail_key, None).await? {
+        Some(thumbnail) => Body::from_stream(thumbnail.body),
+        // 缩略图不存在，生成缩略图
+        None => Body::from(store_thumbnail(state.storage.as_ref(), &icon_key).await?.ok_or(ApiError::FileNotFound)?),
+    };
+    image_response(body, image_type)
+}
 
-    let file_path = config.assets_path.clone() + &collection.unwrap().icon_url.unwrap();
-    let path = FilePath::new(&file_path);
-    let file_stem = path.file_stem().unwrap();
-    let extension = path.extension().unwrap();
-    let image_type = image_type(extension.to_str().unwrap());
-    if image_type.is_none() {
-        return StatusCode::NOT_FOUND.into_response()
-    }
-    let thumbnail_file_path = config.assets_path.clone() + "/" + &collection_id + "/" + file_stem.to_str().unwrap() + "_thumb" + "." + extension.to_str().unwrap();
-    let file =  File::open(thumbnail_file_path).await;
-    // 缩略图不存在，生成缩略图
-    if file.is_err() {
-        let thumb = make_thumbnail(path).await;
-        if thumb.is_none() {
-           return StatusCode::NOT_FOUND.into_response()
-        }
-        let stream = ReaderStream::new(File::open(thumb.unwrap()).await.unwrap());
-        return 
-        Response::builder()
-        .header(CONTENT_TYPE, image_type.unwrap())
-        .body(Body::from_stream(stream))
-        .unwrap()
+/// icon_url形如/{collection_id}/{file_name},返回封面在存储中的key和图片类型
+fn icon_key(icon_url: Option<String>) -> Result<(String, &'static str), ApiError> {
+    let icon_key = icon_url.unwrap_or_default().trim_start_matches('/').to_owned();
+    if !storage::is_valid_key(&icon_key) {
+        return Err(ApiError::FileNotFound)
     }
-    let stream = ReaderStream::new(file.unwrap());
-    Response::builder()
-    .header(CONTENT_TYPE, image_type.unwrap())
-    .body(Body::from_stream(stream))
-    .unwrap()
-    
+    let image_type = FilePath::new(&icon_key).extension()
+    .and_then(|extension| extension.to_str())
+    .and_then(image_type)
+    .ok_or(ApiError::FileNotFound)?;
+    Ok((icon_key, image_type))
+}
+
+fn image_response(body: Body, image_type: &str) -> Result<Response, ApiError> {
+    let response = Response::builder()
+    .header(CONTENT_TYPE, image_type)
+    .body(body)
+    .map_err(anyhow::Error::from)?;
+    Ok(response)
 }
\ No newline at end of file
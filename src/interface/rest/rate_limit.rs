@@ -0,0 +1,241 @@
+use std::{convert::Infallible, net::{IpAddr, SocketAddr}, sync::Arc, task::{Context, Poll}};
+
+use axum::{extract::{ConnectInfo, FromRef, FromRequestParts, Request}, http::{header::AUTHORIZATION, request::Parts, HeaderMap}, response::{IntoResponse, Response}};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::{error::ApiError, infrastructure::{jwt::{self, Keys}, rate_limit::{RateLimiter, RatePolicy, Throttle}}, settings::{RateLimitConfig, RouteLimits, RuntimeHandle}, state::AppState};
+
+/// 限流的接口分组,各组的策略见配置[runtime.rate_limits]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    RequestId,
+    Logon,
+    Upload,
+}
+
+impl RouteGroup {
+    fn name(self) -> &'static str {
+        match self {
+            RouteGroup::RequestId => "request_id",
+            RouteGroup::Logon => "logon",
+            RouteGroup::Upload => "upload",
+        }
+    }
+
+    fn limits(self, config: &RateLimitConfig) -> &RouteLimits {
+        match self {
+            RouteGroup::RequestId => &config.request_id,
+            RouteGroup::Logon => &config.logon,
+            RouteGroup::Upload => &config.upload,
+        }
+    }
+}
+
+/// 按客户端地址和登录账户限流,超出时返回429和Retry-After
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<dyn RateLimiter>,
+    runtime: RuntimeHandle,
+    keys: Arc<Keys>,
+    group: RouteGroup,
+}
+
+impl RateLimitLayer {
+    pub fn new(state: &AppState, group: RouteGroup) -> Self {
+        Self { limiter: state.rate_limiter.clone(), runtime: state.runtime.clone(), keys: state.jwt_keys.clone(), group }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit { inner, limiter: self.limiter.clone(), runtime: self.runtime.clone(), keys: self.keys.clone(), group: self.group }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<dyn RateLimiter>,
+    runtime: RuntimeHandle,
+    keys: Arc<Keys>,
+    group: RouteGroup,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // 使用已经poll_ready的实例
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limiter = self.limiter.clone();
+        let config = self.runtime.current();
+        let keys = self.keys.clone();
+        let group = self.group;
+        Box::pin(async move {
+            let config = &config.rate_limits;
+            if config.enabled {
+                let limits = group.limits(config);
+                let client_ip = client_ip(request.headers(), request.extensions().get::<ConnectInfo<SocketAddr>>(), config);
+                let account = limits.per_account.and_then(|_| bearer_account(&keys, request.headers()));
+                let checks = [
+                    (limits.per_ip, client_ip.map(|ip| format!("{}:ip:{}", group.name(), ip))),
+                    (limits.per_account, account.map(|account| format!("{}:account:{}", group.name(), account))),
+                ];
+                for (policy, key) in checks {
+                    if let (Some(policy), Some(key)) = (policy, key) {
+                        if let Err(err) = throttle(limiter.as_ref(), &key, policy, 1).await {
+                            return Ok(err.into_response())
+                        }
+                    }
+                }
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+/// 客户端地址,配置了client_ip_header时以可信代理写入的地址为准
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    RuntimeHandle: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = RuntimeHandle::from_ref(state).current();
+        Ok(ClientIp(client_ip(&parts.headers, parts.extensions.get::<ConnectInfo<SocketAddr>>(), &config.rate_limits)))
+    }
+}
+
+fn client_ip(headers: &HeaderMap, connect_info: Option<&ConnectInfo<SocketAddr>>, config: &RateLimitConfig) -> Option<IpAddr> {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let Some(header) = &config.client_ip_header else {
+        return peer
+    };
+    // 每层代理在末尾追加上一跳的地址,多个同名请求头按顺序拼接
+    let hops: Vec<&str> = headers.get_all(header.as_str()).iter()
+    .filter_map(|value| value.to_str().ok())
+    .flat_map(|value| value.split(','))
+    .map(str::trim)
+    .collect();
+    // 请求头缺失或格式错误时按对端地址限流,不跳过限流
+    config.trusted_proxies.checked_sub(1)
+    .and_then(|skip| hops.iter().rev().nth(skip))
+    .and_then(|hop| hop.parse().ok())
+    .or(peer)
+}
+
+/// token无效时不按账户限流,由接口返回401
+fn bearer_account(keys: &Keys, headers: &HeaderMap) -> Option<String> {
+    let token = headers.get(AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")?;
+    jwt::decode_token(keys, token).ok().map(|claims| claims.pubkey)
+}
+
+/// 限流器不可用时放行,避免Redis故障导致接口全部不可用
+async fn throttle(limiter: &dyn RateLimiter, key: &str, policy: RatePolicy, cost: u32) -> Result<(), ApiError> {
+    match limiter.acquire(key, policy, cost).await {
+        Ok(Throttle::Allowed) => Ok(()),
+        Ok(Throttle::RetryAfter(wait)) => Err(ApiError::TooManyRequests(wait.as_millis().div_ceil(1000).max(1) as u64)),
+        Err(err) => {
+            tracing::warn!("rate limiter unavailable: {:#}", err);
+            Ok(())
+        }
+    }
+}
+
+/// 登录和注册前检查签名失败次数,签名错误时扣减令牌,令牌用完后同一地址或公钥的请求都返回429
+pub async fn guard_signature(state: &AppState, client: ClientIp, pub_key: &str, verify: impl FnOnce() -> Result<bool, ApiError>) -> Result<bool, ApiError> {
+    let limiter = state.rate_limiter.as_ref();
+    let config = state.runtime.current();
+    if !config.rate_limits.enabled {
+        return verify()
+    }
+    let limits = &config.rate_limits.signature_failure;
+    let checks: Vec<(RatePolicy, String)> = [
+        (limits.per_ip, client.0.map(|ip| format!("signature_failure:ip:{}", ip))),
+        (limits.per_account, (!pub_key.is_empty()).then(|| format!("signature_failure:account:{}", pub_key))),
+    ].into_iter()
+    .filter_map(|(policy, key)| Some((policy?, key?)))
+    .collect();
+    for (policy, key) in &checks {
+        throttle(limiter, key, *policy, 0).await?;
+    }
+    let verified = verify();
+    if matches!(verified, Err(ApiError::WrongCredentials)) {
+        for (policy, key) in &checks {
+            // 本次的错误照常返回,令牌用完后从下一次请求开始拒绝
+            let _ = throttle(limiter, key, *policy, 1).await;
+        }
+    }
+    verified
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::{IpAddr, Ipv4Addr, SocketAddr}, sync::Arc};
+
+    use axum::{extract::ConnectInfo, http::HeaderMap};
+
+    use crate::{error::ApiError, settings::{AppConfig, RateLimitConfig}, state::{fakes::InMemory, AppState}};
+
+    use super::{client_ip, guard_signature, ClientIp};
+
+    #[test]
+    fn test_client_ip() {
+        let peer = ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000)));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", "203.0.113.7".parse().unwrap());
+        let mut config = RateLimitConfig::default();
+        assert_eq!(client_ip(&headers, Some(&peer), &config), Some(IpAddr::from([10, 0, 0, 1])));
+        config.client_ip_header = Some("x-real-ip".to_owned());
+        assert_eq!(client_ip(&headers, Some(&peer), &config), Some(IpAddr::from([203, 0, 113, 7])));
+        // 没有请求头时使用对端地址
+        assert_eq!(client_ip(&HeaderMap::new(), Some(&peer), &config), Some(IpAddr::from([10, 0, 0, 1])));
+
+        // 客户端伪造的地址在左侧,取代理追加的地址
+        config.client_ip_header = Some("x-forwarded-for".to_owned());
+        headers.insert("x-forwarded-for", "198.51.100.1, 198.51.100.2".parse().unwrap());
+        headers.append("x-forwarded-for", "203.0.113.9, 192.0.2.10".parse().unwrap());
+        assert_eq!(client_ip(&headers, Some(&peer), &config), Some(IpAddr::from([192, 0, 2, 10])));
+        config.trusted_proxies = 2;
+        assert_eq!(client_ip(&headers, Some(&peer), &config), Some(IpAddr::from([203, 0, 113, 9])));
+        config.trusted_proxies = 5;
+        assert_eq!(client_ip(&headers, Some(&peer), &config), Some(IpAddr::from([10, 0, 0, 1])));
+    }
+
+    #[tokio::test]
+    async fn test_guard_signature() {
+        let state = AppState::in_memory(AppConfig::for_test(), Arc::new(InMemory::default()));
+        let client = ClientIp(Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+        let policy = RateLimitConfig::default().signature_failure.per_ip.unwrap();
+
+        for _ in 0..policy.burst {
+            let result = guard_signature(&state, client, "pub-key", || Err(ApiError::WrongCredentials)).await;
+            assert!(matches!(result, Err(ApiError::WrongCredentials)));
+        }
+        // 失败次数用完后,正确的签名也被拒绝
+        let result = guard_signature(&state, client, "pub-key", || Ok(true)).await;
+        assert!(matches!(result, Err(ApiError::TooManyRequests(_))));
+        // 其他地址的其他公钥不受影响
+        let other = ClientIp(Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))));
+        assert!(guard_signature(&state, other, "other-key", || Ok(true)).await.is_ok());
+    }
+}
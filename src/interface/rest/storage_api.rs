@@ -0,0 +1,111 @@
+use std::{ops::Range, path::Path as FilePath, time::Duration};
+
+use axum::{body::Body, extract::{Path, State}, http::{header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE}, HeaderMap, StatusCode}, response::Response};
+
+use crate::{error::ApiError, infrastructure::{image_util::image_type, storage::{self, Volume}}, state::AppState};
+
+/// 下载assets下的文件
+pub async fn get_asset(State(state): State<AppState>, Path(key): Path<String>, headers: HeaderMap) -> Result<Response, ApiError> {
+    get_object(&state, Volume::Assets, &key, &headers).await
+}
+
+/// 下载medias下的文件,支持Range请求用于视频拖动播放
+pub async fn get_media(State(state): State<AppState>, Path(key): Path<String>, headers: HeaderMap) -> Result<Response, ApiError> {
+    get_object(&state, Volume::Medias, &key, &headers).await
+}
+
+async fn get_object(state: &AppState, volume: Volume, key: &str, headers: &HeaderMap) -> Result<Response, ApiError> {
+    if !storage::is_valid_key(key) {
+        return Err(ApiError::FileNotFound)
+    }
+    // 后端可以直接提供下载时重定向到预签名地址
+    let expires = Duration::from_secs(state.config.storage.presign_expiry_secs);
+    if let Some(url) = state.storage.presign(volume, key, expires).await? {
+        let response = Response::builder()
+        .status(StatusCode::TEMPORARY_REDIRECT)
+        .header(LOCATION, url)
+        .body(Body::empty())
+        .map_err(anyhow::Error::from)?;
+        return Ok(response)
+    }
+
+    let size = state.storage.size(volume, key).await?.ok_or(ApiError::FileNotFound)?;
+    let range = match headers.get(RANGE).and_then(|value| value.to_str().ok()) {
+        Some(value) => match parse_range(value, size) {
+            Some(range) => Some(range),
+            None => {
+                let response = Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{}", size))
+                .body(Body::empty())
+                .map_err(anyhow::Error::from)?;
+                return Ok(response)
+            }
+        },
+        None => None,
+    };
+    let partial = range.is_some();
+    let object = state.storage.get(volume, key, range).await?.ok_or(ApiError::FileNotFound)?;
+    let content_type = FilePath::new(key).extension()
+    .and_then(|extension| extension.to_str())
+    .and_then(content_type)
+    .unwrap_or("application/octet-stream");
+    let mut response = Response::builder()
+    .header(CONTENT_TYPE, content_type)
+    .header(ACCEPT_RANGES, "bytes")
+    .header(CONTENT_LENGTH, object.range.end - object.range.start);
+    if partial {
+        response = response
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(CONTENT_RANGE, format!("bytes {}-{}/{}", object.range.start, object.range.end - 1, object.size));
+    }
+    let response = response.body(Body::from_stream(object.body)).map_err(anyhow::Error::from)?;
+    Ok(response)
+}
+
+fn content_type(extension: &str) -> Option<&'static str> {
+    match extension.to_lowercase().as_str() {
+        "mp4" => Some("video/mp4"),
+        "mkv" => Some("video/x-matroska"),
+        "json" => Some("application/json"),
+        extension => image_type(extension),
+    }
+}
+
+/// 解析单个范围:bytes=a-b、bytes=a-或bytes=-n,范围无法满足时返回None
+fn parse_range(value: &str, size: u64) -> Option<Range<u64>> {
+    let (start, end) = value.strip_prefix("bytes=")?.trim().split_once('-')?;
+    let range = if start.is_empty() {
+        // 最后n个字节
+        let suffix = end.parse::<u64>().ok()?;
+        if suffix == 0 {
+            return None
+        }
+        size.saturating_sub(suffix)..size
+    } else {
+        let start = start.parse::<u64>().ok()?;
+        let end = match end {
+            "" => size,
+            end => end.parse::<u64>().ok()?.saturating_add(1).min(size),
+        };
+        start..end
+    };
+    (range.start < range.end).then_some(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_range;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some(0..100));
+        assert_eq!(parse_range("bytes=900-", 1000), Some(900..1000));
+        assert_eq!(parse_range("bytes=900-2000", 1000), Some(900..1000));
+        assert_eq!(parse_range("bytes=-100", 1000), Some(900..1000));
+        assert_eq!(parse_range("bytes=-2000", 1000), Some(0..1000));
+        for value in ["bytes=1000-", "bytes=5-2", "bytes=-0", "bytes=0-1,5-6", "items=0-1", "bytes=a-b"] {
+            assert_eq!(parse_range(value, 1000), None, "{}", value);
+        }
+    }
+}
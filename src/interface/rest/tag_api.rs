@@ -0,0 +1,77 @@
+use axum::{extract::{Path, Query, State}, Json};
+
+use crate::{application::{command_service::collection_application_service, query_service::tag_query_service}, domain::command::collection_command::SetCollectionTagsCommand, error::ApiError, infrastructure::jwt::Claims, interface::rest::validate::validate_request_id, state::AppState};
+
+use super::dto::{collection::CollectionPageDTO, tag::{CategoryDTO, CollectionTagsPayload, TagCountDTO, TagQueryArgs}, ApiResult};
+
+/// 所有分类
+#[utoipa::path(
+    get,
+    path = "/categories",
+    tag = "tag",
+    responses(
+        (status = 200, body = Vec<CategoryDTO>),
+    )
+)]
+pub async fn get_categories(State(state): State<AppState>) -> Result<Json<Vec<CategoryDTO>>, ApiError> {
+    let categories = tag_query_service::get_categories(&state).await?;
+    Ok(Json(categories))
+}
+
+/// 常用标签,可按前缀过滤
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tag",
+    params(TagQueryArgs),
+    responses(
+        (status = 200, body = Vec<TagCountDTO>),
+    )
+)]
+pub async fn get_tags(State(state): State<AppState>, Query(args): Query<TagQueryArgs>) -> Result<Json<Vec<TagCountDTO>>, ApiError> {
+    let limit = args.limit.filter(|limit| *limit >= 1).map_or(20, |limit| limit.min(100));
+    let tags = tag_query_service::get_popular_tags(&state, args.prefix, limit as u64).await?;
+    Ok(Json(tags))
+}
+
+/// 设置我的专辑标签
+#[utoipa::path(
+    put,
+    path = "/my_collections/{collection_id}/tags",
+    tag = "tag",
+    security(("bearer_auth" = [])),
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    request_body = CollectionTagsPayload,
+    responses(
+        (status = 200, description = "规范化后的标签", body = Vec<String>),
+        (status = 400, description = "标签不合法", body = ApiResult),
+        (status = 401, body = ApiResult),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn set_collection_tags(State(state): State<AppState>, claims: Claims, Path(collection_id): Path<String>, Json(payload): Json<CollectionTagsPayload>) -> Result<Json<Vec<String>>, ApiError> {
+    validate_request_id(&payload.request_id)?;
+    let command = SetCollectionTagsCommand {
+        collection_id: collection_id,
+        tags: payload.tags,
+        pub_key: claims.pubkey,
+    };
+    let tags = collection_application_service::set_collection_tags(&state, command).await?;
+    Ok(Json(tags))
+}
+
+/// 相关专辑
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_id}/related",
+    tag = "tag",
+    params(("collection_id" = String, Path, description = "专辑ID(uuid)")),
+    responses(
+        (status = 200, body = Vec<CollectionPageDTO>),
+        (status = 404, body = ApiResult),
+    )
+)]
+pub async fn get_related_collections(State(state): State<AppState>, Path(collection_id): Path<String>) -> Result<Json<Vec<CollectionPageDTO>>, ApiError> {
+    let collections = tag_query_service::get_related_collections(&state, &collection_id, 10, &state.config.server.assets_http_addr).await?;
+    Ok(Json(collections))
+}
\ No newline at end of file
@@ -0,0 +1,64 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response, routing::{get, post, put}, Router};
+
+use crate::state::AppState;
+
+use super::{account_api, file_api, logon_api, my_collection_api, public_collection_api, rate_limit::{RateLimitLayer, RouteGroup}, request_id, tag_api};
+
+/// v1接口前缀
+pub const PREFIX: &str = "/api/v1";
+
+/// 无前缀的旧路径开始弃用的时间(RFC 9745,unix timestamp)
+const DEPRECATED_AT: &str = "@1792368000";
+/// 无前缀的旧路径停止服务的时间(RFC 8594)
+const SUNSET_AT: &str = "Fri, 30 Apr 2027 00:00:00 GMT";
+
+/// v1接口路由,路径不含版本前缀
+///
+/// 新版本在v2模块中单独组装路由,DTO有变化的接口使用新的handler,其余直接复用v1的handler
+pub fn router(state: &AppState) -> Router<AppState> {
+    let limit = |group| RateLimitLayer::new(state, group);
+    Router::new()
+    .route("/upload", post(file_api::upload_file).route_layer(limit(RouteGroup::Upload)))
+    .route("/upload_media_chunks", post(file_api::upload_video_chunks).route_layer(limit(RouteGroup::Upload)))
+    .route("/merge_chunks", post(file_api::merge_chunk_list))
+    .route("/check_chunks", post(file_api::check_chunks))
+    .route("/upload_icon", post(file_api::upload_icon_file).route_layer(limit(RouteGroup::Upload)))
+    .route("/signup", post(logon_api::sign_up).route_layer(limit(RouteGroup::Logon)))
+    .route("/signin", post(logon_api::sign_in).route_layer(limit(RouteGroup::Logon)))
+    .route("/account_info", get(account_api::get_account_info))
+    .route("/my_collections", post(my_collection_api::create_collection).get(my_collection_api::get_my_collections))
+    .route("/my_collections/{collection_id}", get(my_collection_api::get_my_collection_info_by_id))
+    .route("/my_collections/{collection_id}/tags", put(tag_api::set_collection_tags))
+    .route("/simple_collections", get(my_collection_api::get_simple_collections))
+    .route("/author/{author_id}/collections", get(public_collection_api::get_author_collections))
+    .route("/collections", get(public_collection_api::search_collections))
+    .route("/collections/{collection_id}", get(public_collection_api::get_collection_info_by_id))
+    .route("/collections/{collection_id}/simpleinfo", get(public_collection_api::get_collection_simple_by_id))
+    .route("/collections/{collection_id}/metadata", get(public_collection_api::get_collection_metadata))
+    .route("/collections/{collection_id}/tokens/{edition}/metadata", get(public_collection_api::get_token_metadata))
+    .route("/collections/{collection_id}/image", get(public_collection_api::get_image))
+    .route("/collections/{collection_id}/thumbnail", get(public_collection_api::get_thumbnail))
+    .route("/collections/{collection_id}/related", get(tag_api::get_related_collections))
+    .route("/categories", get(tag_api::get_categories))
+    .route("/tags", get(tag_api::get_tags))
+    // .route("/collections/{collection_id}/medias/{media_id}/viewing_key", get(media_api::get_viewing_key))
+    .route("/authors", get(account_api::get_authors))
+    .route("/articles", post(my_collection_api::create_article))
+    .route("/articles/{article_id}", get(public_collection_api::get_article_by_id))
+    .route("/videos", post(my_collection_api::add_video))
+    .route("/videos/{video_id}", get(public_collection_api::get_video_by_id))
+    .route("/request_id", get(request_id).route_layer(limit(RouteGroup::RequestId)))
+}
+
+/// 无前缀的旧路径暂时保留给已发布的客户端,响应中加入弃用时间、停止服务时间和新路径
+pub async fn legacy_alias(request: Request, next: Next) -> Response {
+    let successor = format!("<{}{}>; rel=\"successor-version\"", PREFIX, request.uri().path());
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static(DEPRECATED_AT));
+    headers.insert("sunset", HeaderValue::from_static(SUNSET_AT));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert("link", link);
+    }
+    response
+}
@@ -1,45 +1,69 @@
-use axum::http::StatusCode;
 use hex::FromHex;
 use ed25519_dalek::{Signature, VerifyingKey};
 
+use crate::error::ApiError;
+
 /// 校验request_idrequest_id
-pub fn validate_request_id(request_id: &String) -> Result<bool, (StatusCode, String)> {
+pub fn validate_request_id(request_id: &String) -> Result<bool, ApiError> {
     if request_id.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Missing request_id".to_owned()));
+        return Err(ApiError::MissingRequestId);
     }
     // TODO Redis校验
     Ok(true)
 }
 
 /// 校验签名
-pub fn validate_signature(pub_key: &String, request_id: &String, signature: &String) -> Result<bool, (StatusCode, String)> {
+pub fn validate_signature(pub_key: &String, request_id: &String, signature: &String) -> Result<bool, ApiError> {
     if pub_key.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Missing credentials".to_owned()));
+        return Err(ApiError::MissingCredentials);
     }
     if signature.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Missing credentials".to_owned()));
+        return Err(ApiError::MissingCredentials);
     }
     if request_id.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Missing request_id".to_owned()));
+        return Err(ApiError::MissingRequestId);
     }
 
-    let pub_key_result = <[u8;32]>::from_hex(&pub_key);
-    if pub_key_result.is_err() {
-        return Err((StatusCode::UNAUTHORIZED, "Wrong credentials".to_owned()));
-    }
-    let verifying_key = VerifyingKey::from_bytes(&pub_key_result.unwrap()).unwrap();
+    let pub_key_bytes = <[u8;32]>::from_hex(&pub_key).map_err(|_| ApiError::WrongCredentials)?;
+    // 非法的曲线点返回错误而不是panic
+    let verifying_key = VerifyingKey::from_bytes(&pub_key_bytes).map_err(|_| ApiError::WrongCredentials)?;
 
-    let signature_result = <[u8;64]>::from_hex(&signature);
-    if signature_result.is_err() {
-        return Err((StatusCode::UNAUTHORIZED, "Wrong credentials".to_owned()));
-    }
-    let signature = Signature::from_bytes(&signature_result.unwrap());
+    let signature_bytes = <[u8;64]>::from_hex(&signature).map_err(|_| ApiError::WrongCredentials)?;
+    let signature = Signature::from_bytes(&signature_bytes);
 
     let verify_result = verifying_key.verify_strict(request_id.as_bytes(), &signature);
 
     // 校验错误
     if verify_result.is_err() {
-        return Err((StatusCode::UNAUTHORIZED, "Wrong credentials".to_owned()));
+        return Err(ApiError::WrongCredentials);
     }
     Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+    use proptest::prelude::*;
+
+    use crate::error::ApiError;
+
+    use super::validate_signature;
+
+    #[test]
+    fn test_validate_signature() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let pub_key = hex::encode(signing_key.verifying_key().as_bytes());
+        let request_id = "request-id".to_owned();
+        let signature = hex::encode(signing_key.sign(request_id.as_bytes()).to_bytes());
+        assert!(validate_signature(&pub_key, &request_id, &signature).is_ok());
+        assert!(matches!(validate_signature(&pub_key, &"other".to_owned(), &signature), Err(ApiError::WrongCredentials)));
+        assert!(matches!(validate_signature(&"".to_owned(), &request_id, &signature), Err(ApiError::MissingCredentials)));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_validate_signature_never_panics(pub_key in "[0-9a-fA-F]{64}|.*", request_id in ".*", signature in "[0-9a-fA-F]{128}|.*") {
+            prop_assert!(validate_signature(&pub_key, &request_id, &signature).is_err());
+        }
+    }
 }
\ No newline at end of file
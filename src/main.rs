@@ -1,22 +1,42 @@
 
-use std::{env, path::Path, sync::Arc};
-
-use axum::{body::Body, http::{Method, Request, Response, StatusCode}, response::IntoResponse, routing::{get, post}, Router};
-use config::{Config, File};
-use infrastructure::{messaging::{account_bound_consumer::account_bound_consumer, coin_published_consumer::coin_published_consumer, load_config, nft_published_consumer::nft_published_consumer}, redis_connection};
-use interface::rest::{account_api, file_api, logon_api::{sign_in, sign_up}, my_collection_api::{self}, public_collection_api, request_id};
-use redis::{AsyncCommands};
-use tower_http::{auth::AsyncRequireAuthorizationLayer, cors::{Any, CorsLayer}, services::ServeDir, trace::TraceLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use axum::{body::Body, http::{Request, Response, StatusCode}, middleware, routing::get, Router};
+use clap::{Parser, Subcommand};
+use application::{command_service::{ipfs_application_service, nft_index_application_service}, query_service::media_query_service};
+use infrastructure::{database_connection, migration::{self, MigrateAction}, messaging::{account_bound_consumer, coin_published_consumer, nft_published_consumer}, redis_connection, sui::nft_query::{self, SuiNftQuery}, telemetry::{self, Telemetry}};
+use error::ApiError;
+use interface::rest::{cors::{self, Listener}, health_api, i18n, openapi::{self, ApiDoc}, storage_api, v1};
+use serde::Deserialize;
+use settings::AppConfig;
+use state::AppState;
+use supervisor::Supervisor;
+use tls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+use tower_http::{auth::AsyncRequireAuthorizationLayer, classify::{ServerErrorsAsFailures, SharedClassifier}, trace::{DefaultOnRequest, TraceLayer}};
+use tracing::Span;
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter};
+use utoipa::OpenApi;
+use utoipa_scalar::{Scalar, Servable};
 
 mod infrastructure;
 mod domain;
 mod interface;
 mod application;
 mod utils;
-
-#[derive(Clone)]
+mod error;
+mod settings;
+mod state;
+mod supervisor;
+mod tls;
+#[cfg(test)]
+mod e2e;
+
+/// 服务地址和文件存储,对应配置文件的[server]
+#[derive(Clone, Debug, PartialEq, Deserialize)]
 pub struct ServerConfig {
+    pub web_addr: String,
     pub assets_path: String,
     pub assets_addr: String,
     pub assets_http_addr: String,
@@ -25,232 +45,520 @@ pub struct ServerConfig {
     pub medias_http_addr: String,
 }
 
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 数据库迁移
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// 生成自签名证书,用于本地测试HTTPS
+    GenerateCert {
+        /// 证书包含的域名,可以重复
+        #[arg(long = "domain", default_values = ["localhost"])]
+        domains: Vec<String>,
+        /// 输出cert.pem和key.pem的目录
+        #[arg(long, default_value = "conf/tls")]
+        out: PathBuf,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error>{
-    dotenvy::dotenv()?;
+    let cli = Cli::parse();
+    if let Some(Command::GenerateCert { domains, out }) = &cli.command {
+        tls::generate_self_signed(domains, out)?;
+        println!("wrote {} and {}", out.join("cert.pem").display(), out.join("key.pem").display());
+        return Ok(())
+    }
+    // 没有.env时只使用环境变量
+    dotenvy::dotenv().ok();
     // for (key, value) in env::vars() {
     //     println!("{key}: {value}");
     // }
+    let app_config = AppConfig::load()?;
+
     // initialize tracing
     // tracing_subscriber::fmt::init();
+    // 启动时RUST_LOG优先,重新加载配置时使用runtime.log_level
+    let (log_filter, log_filter_handle) = reload::Layer::new(
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&app_config.runtime.log_level)),
+    );
+    // 退出main时导出剩余的span
+    let traces = Telemetry::init(&app_config.telemetry)?;
     tracing_subscriber::registry()
-    .with(
-        tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| format!("{}=debug", env!("CARGO_CRATE_NAME")).into()),
-    ).with(tracing_subscriber::fmt::layer())
+    .with(log_filter)
+    .with(tracing_subscriber::fmt::layer())
+    .with(traces.layer())
     .init();
 
-    // rustls::crypto::ring::default_provider().install_default().expect("Failed to install rustls crypto provider");
-
-    // let path_buf = PathBuf::from_str("./conf").unwrap();
-    // let mut state = AcmeConfig::new(["bassinet.app"])
-    // .contact(["mailto:cmmymtzxl@126.com"])
-    // .cache_option(Some(DirCache::new(path_buf)))
-    // .directory_lets_encrypt(false)
-    // .state();
-
-    // let rustls_config = RustlsServerConfig::builder()
-    // .with_no_client_auth()
-    // .with_cert_resolver(state.resolver());
-
-    // let acceptor = state.axum_acceptor(Arc::new(rustls_config));
-
-    // tokio::spawn(async move{
-    //     loop {
-    //         match state.next().await.unwrap() {
-    //             Ok(ok) => tracing::info!("event: {:?}", ok),
-    //             Err(err) => tracing::error!("error: {:?}", err),
-    //         }
-    //     }
-    // });
-
-    // let rocksdb_dir_path = std::env::var("ROCKSDB_STORE_DIR_PATH").expect("ROCKSDB_STORE_DIR_PATH must be set");
-    // let db = RocksDB::init(rocksdb_dir_path.as_str());
-
-    // 读取配置
-    let settings = Config::builder()
-    .add_source(File::from(Path::new("conf/default.toml")))
-    .build()
-    .unwrap();
-
-    // build our application with a route
-    // web服务地址
-    let web_addr = settings.get_string("web_addr").unwrap();
-
-    // let addr = SocketAddr::from_str(&web_addr).unwrap();
-
-    // assets服务地址
-    let assets_addr = settings.get_string("assets_addr").unwrap();
-
-    let assets_path = settings.get_string("assets_path").unwrap();
-
-    // medias服务地址
-    let medias_addr = settings.get_string("medias_addr").unwrap();
-    let medias_path = settings.get_string("medias_path").unwrap();
-
-    let settings = Arc::new(settings);
-
-    let mq_config  = Arc::new(load_config().await);
-    tokio::join!(
-        // web service
-        http_web_serve(webservice_router(settings), &web_addr),
-        // https_web_serve(webservice_router(settings), addr, acceptor),
-        // static assets
-        assets_serve(using_serve_dir(&assets_path), &assets_addr),
-        medias_serve(using_media_dir(&medias_path), &medias_addr),
-        async {
-            let _ = account_bound_consumer(mq_config.clone()).await;
-        },
-        async {
-            let _ = coin_published_consumer(mq_config.clone()).await;
-        },
-        async {
-            let _ = nft_published_consumer(mq_config.clone()).await;
+    let mut db = database_connection::connect(&app_config.database).await?;
+    if traces.enabled() {
+        db.set_metric_callback(telemetry::record_query);
+    }
+    if let Some(Command::Migrate { action }) = cli.command {
+        return migration::run(&db, action).await
+    }
+    migration::migrate_pending(&db).await?;
+    let storage = infrastructure::storage::create(&app_config.storage, &app_config.server)?;
+    let pinner = Arc::new(infrastructure::ipfs::IpfsPinner::new(&app_config.ipfs)?);
+    let sui = Arc::new(SuiNftQuery::new(&app_config.sui));
+    let state = AppState::new(Arc::new(app_config.clone()), db, redis_connection::create_pool(&app_config.redis)?, storage, pinner, sui.clone());
+
+    infrastructure::metrics::install();
+    let server = &app_config.server;
+    let mq_config  = Arc::new(app_config.rabbitmq.clone());
+    let mut supervisor = Supervisor::new(supervisor::shutdown_on_signal(), state.tasks.clone());
+    rustls::crypto::ring::default_provider().install_default().map_err(|_| anyhow::anyhow!("failed to install rustls crypto provider"))?;
+    let tls = tls::load(&app_config.tls, &mut supervisor).await?;
+    // web service
+    let web = webservice_router(state.clone());
+    let web_addr = server.web_addr.clone();
+    let web_tls = tls.clone();
+    supervisor.spawn("web", move |shutdown| serve(web.clone(), web_addr.clone(), web_tls.clone(), shutdown));
+    // static assets
+    let assets = assets_router(state.clone()).layer(http_trace_layer());
+    let assets_addr = server.assets_addr.clone();
+    let assets_tls = tls.clone();
+    supervisor.spawn("assets", move |shutdown| serve(assets.clone(), assets_addr.clone(), assets_tls.clone(), shutdown));
+    let medias = medias_router(state.clone());
+    let medias_addr = server.medias_addr.clone();
+    let medias_tls = tls.clone();
+    supervisor.spawn("medias", move |shutdown| serve(medias.clone(), medias_addr.clone(), medias_tls.clone(), shutdown));
+    // HTTP请求重定向到HTTPS的web服务
+    if let (Some(_), Some(redirect_addr)) = (&tls, app_config.tls.redirect_addr.clone()) {
+        let web_port = server.web_addr.parse::<SocketAddr>().context("invalid server.web_addr")?.port();
+        let redirect = tls::redirect_router(web_port);
+        supervisor.spawn("https_redirect", move |shutdown| serve(redirect.clone(), redirect_addr.clone(), None, shutdown));
+    }
+    let reload_state = state.clone();
+    supervisor.spawn("config_reload", move |shutdown| {
+        let log_filter_handle = log_filter_handle.clone();
+        let state = reload_state.clone();
+        async move {
+            let reloaded = settings::reload_on_hangup(&state.config, &state.runtime, move |runtime| {
+                match EnvFilter::try_new(&runtime.log_level) {
+                    Ok(filter) => {
+                        if let Err(err) = log_filter_handle.reload(filter) {
+                            tracing::error!("failed to apply log level: {}", err);
+                        }
+                    }
+                    Err(err) => tracing::error!("invalid log level {}: {}", runtime.log_level, err),
+                }
+            });
+            tokio::select! {
+                result = reloaded => result,
+                _ = shutdown.cancelled() => Ok(()),
+            }
         }
-    );
+    });
+    let (mq, consumer_state) = (mq_config.clone(), state.clone());
+    supervisor.spawn(account_bound_consumer::TASK, move |shutdown| account_bound_consumer::account_bound_consumer(mq.clone(), consumer_state.clone(), shutdown));
+    let (mq, consumer_state) = (mq_config.clone(), state.clone());
+    supervisor.spawn(coin_published_consumer::TASK, move |shutdown| coin_published_consumer::coin_published_consumer(mq.clone(), consumer_state.clone(), shutdown));
+    let (mq, consumer_state) = (mq_config, state.clone());
+    supervisor.spawn(nft_published_consumer::TASK, move |shutdown| nft_published_consumer::nft_published_consumer(mq.clone(), consumer_state.clone(), shutdown));
+    let sui_interval = Duration::from_secs(app_config.sui.health_check_interval_secs);
+    supervisor.spawn(nft_query::TASK, move |shutdown| {
+        let sui = sui.clone();
+        async move { sui.monitor_endpoints(sui_interval, shutdown).await }
+    });
+    if app_config.indexer.enabled {
+        let indexer_state = state.clone();
+        supervisor.spawn(nft_index_application_service::TASK, move |shutdown| nft_index_application_service::run_indexer(indexer_state.clone(), shutdown));
+    }
+    if app_config.ipfs.enabled {
+        supervisor.spawn(ipfs_application_service::TASK, move |shutdown| ipfs_application_service::verify_pins(state.clone(), shutdown));
+    }
+    supervisor.run().await;
 
     Ok(())
 
 }
 
-fn webservice_router(settings: Arc<Config>) -> Router{
-    let server_config = Arc::new(ServerConfig {
-        assets_path: settings.get_string("assets_path").unwrap(),
-        assets_addr: settings.get_string("assets_addr").unwrap(),
-        assets_http_addr: settings.get_string("assets_http_addr").unwrap(),
-        medias_path: settings.get_string("medias_path").unwrap(),
-        medias_addr: settings.get_string("medias_addr").unwrap(),
-        medias_http_addr: settings.get_string("medias_http_addr").unwrap()
-    });
-
-    // let cors = CorsLayer::new()
-    // .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS])
-    // .allow_origin(Any)
-    // .allow_credentials(false);
-    let cors = CorsLayer::new()
-    .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS, Method::PUT, Method::DELETE, Method::HEAD])
-    .allow_origin(Any)
-    .allow_headers(Any);
-
-    // let cors = CorsLayer::new()
-    // .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap())
-    // .allow_methods([Method::GET, Method::POST]);
-
+fn webservice_router(state: AppState) -> Router{
+    let api_v1 = v1::router(&state);
     let app = Router::new()
-    .route("/upload", post(file_api::upload_file))
-    .route("/upload_media_chunks", post(file_api::upload_video_chunks))
-    .route("/merge_chunks", post(file_api::merge_chunk_list))
-    .route("/check_chunks", post(file_api::check_chunks))
-    .route("/upload_icon", post(file_api::upload_icon_file))
-    .route("/signup", post(sign_up))
-    .route("/signin", post(sign_in))
-    .route("/account_info", get(account_api::get_account_info))
-    .route("/my_collections", post(my_collection_api::create_collection).get(my_collection_api::get_my_collections))
-    .route("/my_collections/{collection_id}", get(my_collection_api::get_my_collection_info_by_id))
-    .route("/simple_collections", get(my_collection_api::get_simple_collections))
-    .route("/author/{author_id}/collections", get(public_collection_api::get_author_collections))
-    .route("/collections", get(public_collection_api::search_collections))
-    .route("/collections/{collection_id}", get(public_collection_api::get_collection_info_by_id))
-    .route("/collections/{collection_id}/simpleinfo", get(public_collection_api::get_collection_simple_by_id))
-    .route("/collections/{collection_id}/image", get(public_collection_api::get_image))
-    .route("/collections/{collection_id}/thumbnail", get(public_collection_api::get_thumbnail))
-    // .route("/collections/{collection_id}/medias/{media_id}/viewing_key", get(media_api::get_viewing_key))
-    .route("/authors", get(account_api::get_authors))
-    .route("/articles", post(my_collection_api::create_article))
-    .route("/articles/{article_id}", get(public_collection_api::get_article_by_id))
-    .route("/videos", post(my_collection_api::add_video))
-    .route("/videos/{video_id}", get(public_collection_api::get_video_by_id))
-    .route("/request_id", get(request_id))
-    // .layer(tower_http::cors::CorsLayer::permissive())
-    .layer(cors)
-    .with_state(server_config);
+    .nest(v1::PREFIX, api_v1.clone())
+    // 无前缀的旧路径,过渡期保留
+    .merge(api_v1.route_layer(middleware::from_fn(v1::legacy_alias)))
+    // 接口文档
+    .route("/openapi.json", get(openapi::openapi_json))
+    .merge(Scalar::with_url("/docs", ApiDoc::openapi()))
+    // 探活、就绪检查和指标
+    .route("/healthz", get(health_api::healthz))
+    .route("/readyz", get(health_api::readyz))
+    .route("/metrics", get(health_api::prometheus_metrics))
+    .route_layer(middleware::from_fn(health_api::track_requests))
+    .layer(middleware::from_fn_with_state((Listener::Api, state.runtime.clone()), cors::cors))
+    .with_state(state);
 
     // add a fallback service for handling routes to unknown paths
     let app = app.fallback(handler_404);
-    app
+    // 错误信息按Accept-Language本地化
+    app.layer(middleware::from_fn(i18n::language_layer)).layer(http_trace_layer())
 }
 
-fn using_serve_dir(assets_path: &str) -> Router {
-    // serve the file in the "assets" directory under `/assets`
-    Router::new().nest_service("/assets", ServeDir::new(assets_path))
-}
+type HttpTraceLayer = TraceLayer<SharedClassifier<ServerErrorsAsFailures>, fn(&Request<Body>) -> Span, DefaultOnRequest, fn(&Response<Body>, Duration, &Span)>;
 
-fn using_media_dir(medias_path: &str) -> Router {
-    Router::new().nest_service("/medias", ServeDir::new(&medias_path))
+/// 每个请求一个span,继续请求头traceparent中的链路
+fn http_trace_layer() -> HttpTraceLayer {
+    TraceLayer::new_for_http()
+    .make_span_with(telemetry::http_span as fn(&Request<Body>) -> Span)
+    .on_response(telemetry::record_response as fn(&Response<Body>, Duration, &Span))
 }
 
-// async fn https_web_serve(app: Router, addr: SocketAddr, acceptor: AxumAcceptor) {
-//     axum_server::bind(addr)
-//         .acceptor(acceptor)
-//         .serve(app.into_make_service())
-//         .await
-//         .unwrap();
-// }
+/// 图片等资源,从存储后端读取
+fn assets_router(state: AppState) -> Router {
+    let runtime = state.runtime.clone();
+    Router::new().route("/assets/{*key}", get(storage_api::get_asset))
+    .with_state(state)
+    .layer(middleware::from_fn_with_state((Listener::Assets, runtime), cors::cors))
+}
 
-async fn http_web_serve(app: Router, addr: &str) {
+/// 收到停止信号后不再接受新连接,等待处理中的请求完成后返回;配置了TLS时提供HTTPS
+async fn serve(app: Router, addr: String, tls: Option<TlsAcceptor>, shutdown: CancellationToken) -> Result<(), anyhow::Error> {
+    if let Some(tls) = tls {
+        let addr = addr.parse::<SocketAddr>().with_context(|| format!("invalid address {}", addr))?;
+        tracing::debug!("listening on https://{}", addr);
+        return tls::serve(app, addr, tls, shutdown).await
+    }
     // let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    tracing::debug!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    let listener = tokio::net::TcpListener::bind(&addr).await.with_context(|| format!("failed to bind {}", addr))?;
+    tracing::debug!("listening on {}", listener.local_addr()?);
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+    Ok(())
 }
 
-async fn assets_serve(app: Router, addr: &str) {
-    // let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    tracing::debug!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app.layer(TraceLayer::new_for_http()).into_make_service())
-        .await
-        .unwrap();
+/// 音视频资源,需要带有效的viewingKey才能访问
+fn medias_router(state: AppState) -> Router {
+    // 跨域处理在鉴权之外,预检请求不带viewingKey
+    let runtime = state.runtime.clone();
+    Router::new().route("/medias/{*key}", get(storage_api::get_media))
+    .with_state(state.clone())
+    .layer(AsyncRequireAuthorizationLayer::new(move |request:Request<Body>| {
+        let state = state.clone();
+        async move {
+            // tracing::debug!("access media resource auth");
+            let viewing_key = request.uri().query().and_then(|query| {
+                query.split('&').find_map(|param| param.strip_prefix("viewingKey=")).map(str::to_owned)
+            });
+            // 校验viewing_key是否有效,且是为请求的文件签发的
+            let media_key = request.uri().path().strip_prefix("/medias/");
+            if let (Some(viewing_key), Some(media_key)) = (viewing_key, media_key) {
+                if media_query_service::is_valid_viewing_key(&state, &viewing_key, media_key).await {
+                    return Ok(request)
+                }
+            }
+            let unauthorized_response = Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .body(Body::empty())
+                .unwrap();
+            Err(unauthorized_response)
+        }
+    }))
+    .layer(middleware::from_fn_with_state((Listener::Medias, runtime), cors::cors))
+    .layer(http_trace_layer())
 }
 
-/// 音视频资源
-async fn medias_serve(app: Router, addr: &str) {
-    let cors = CorsLayer::new()
-    .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS, Method::PUT, Method::DELETE, Method::HEAD])
-    .allow_origin(Any)
-    .allow_headers(Any);
+async fn handler_404() -> ApiError {
+    ApiError::NotFound
+}
 
-    // let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    tracing::debug!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app.layer(TraceLayer::new_for_http()).layer(cors).layer(AsyncRequireAuthorizationLayer::new(|request:Request<Body>| async move {
-        // tracing::debug!("access media resource auth");
-        // Ok(request)
-        let query = request.uri().query();
-        let unauthorized_response = Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .body(Body::empty())
-            .unwrap();
-        if query.is_some() {
-            let keys: Vec<&str> = query.unwrap().split("&").collect();
-            for key in keys.into_iter() {
-                let params: Vec<_> = key.split("=").collect();
-                let key = params.get(0);
-                if key.is_some() && *key.unwrap() == "viewingKey" {
-                    let value = params.get(1);
-                    if value.is_some() {
-                        // 校验viewing_key是否有效
-                        let viewing_key = &("viewing_key_".to_owned() + *value.unwrap());
-                        let mut redis_connection = redis_connection::get_redis_connection().await;
-                        let exists = redis_connection.get(viewing_key).await;
-                        if exists.is_ok() {
-                            let kay_value: Option<u64> = exists.unwrap();
-                            if kay_value.is_some() {
-                                return Ok(request)
-                            }
-                        }
-                    }
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, extract::ConnectInfo, http::{header::{ACCEPT_LANGUAGE, ALLOW, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER}, Method, Request, StatusCode}, Router};
+    use http_body_util::BodyExt;
+    use proptest::prelude::*;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use utoipa::{openapi::{path::PathItem, security::SecurityRequirement}, OpenApi};
+
+    use std::{net::SocketAddr, sync::{atomic::Ordering, Arc}};
+
+    use crate::{domain::model::valueobject::page::PageCursor, infrastructure::{self, messaging::account_bound_consumer}, interface::rest::{openapi::ApiDoc, v1}, settings::AppConfig, state::{fakes::InMemory, AppState}};
+
+    use super::webservice_router;
+
+    /// 需要登录的接口
+    const PROTECTED_ROUTES: &[(&str, &str)] = &[
+        ("POST", "/upload"),
+        ("POST", "/upload_media_chunks"),
+        ("POST", "/merge_chunks"),
+        ("POST", "/check_chunks"),
+        ("POST", "/upload_icon"),
+        ("GET", "/account_info"),
+        ("GET", "/my_collections"),
+        ("POST", "/my_collections"),
+        ("GET", "/my_collections/{id}"),
+        ("PUT", "/my_collections/{id}/tags"),
+        ("GET", "/simple_collections"),
+        ("POST", "/articles"),
+        ("POST", "/videos"),
+        ("GET", "/videos/{id}"),
+    ];
+
+    /// 带id的公开接口,id格式错误时不访问存储直接返回
+    const PUBLIC_ID_ROUTES: &[&str] = &[
+        "/collections/{id}",
+        "/collections/{id}/simpleinfo",
+        "/collections/{id}/metadata",
+        "/collections/{id}/image",
+        "/collections/{id}/thumbnail",
+        "/collections/{id}/related",
+        "/articles/{id}",
+        "/author/{id}/collections",
+    ];
+
+    fn router() -> Router {
+        webservice_router(AppState::in_memory(AppConfig::for_test(), Arc::new(InMemory::default())))
+    }
+
+    fn encode(value: &str) -> String {
+        value.bytes().map(|byte| {
+            if byte.is_ascii_alphanumeric() {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        }).collect()
+    }
+
+    fn send(router: &Router, request: Request<Body>) -> (StatusCode, Vec<u8>) {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let response = router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes().to_vec();
+            (status, body)
+        })
+    }
+
+    fn documented_methods(item: &PathItem) -> Vec<&'static str> {
+        [
+            ("GET", item.get.is_some()),
+            ("POST", item.post.is_some()),
+            ("PUT", item.put.is_some()),
+            ("DELETE", item.delete.is_some()),
+            ("PATCH", item.patch.is_some()),
+        ].into_iter().filter(|(_, documented)| *documented).map(|(method, _)| method).collect()
+    }
+
+    /// 路径参数统一替换为{id},便于和测试用的路由表比较
+    fn normalize_path(path: &str) -> String {
+        path.split('/').map(|segment| if segment.starts_with('{') { "{id}" } else { segment }).collect::<Vec<_>>().join("/")
+    }
+
+    fn malformed_id() -> impl Strategy<Value = String> {
+        ".{1,64}".prop_filter("not a uuid", |id| Uuid::parse_str(id).is_err())
+    }
+
+    #[test]
+    fn test_error_body_localized() {
+        let router = router();
+        let request = Request::get(format!("{}/collections?cursor=zz", v1::PREFIX)).header(ACCEPT_LANGUAGE, "en-US,en;q=0.9").body(Body::empty()).unwrap();
+        let (status, body) = send(&router, request);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], 1003);
+        assert_eq!(body["message"], "Invalid cursor");
+
+        let request = Request::get("/no/such/route").body(Body::empty()).unwrap();
+        let (status, body) = send(&router, request);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], 1000);
+        assert_eq!(body["message"], "接口不存在");
+    }
+
+    #[test]
+    fn test_legacy_alias_deprecated() {
+        let router = router();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+        let request = Request::get("/collections?cursor=zz").body(Body::empty()).unwrap();
+        let response = runtime.block_on(router.clone().oneshot(request)).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers().contains_key("deprecation"));
+        assert!(response.headers().contains_key("sunset"));
+        assert_eq!(response.headers()["link"], "</api/v1/collections>; rel=\"successor-version\"");
+
+        let request = Request::get(format!("{}/collections?cursor=zz", v1::PREFIX)).body(Body::empty()).unwrap();
+        let response = runtime.block_on(router.clone().oneshot(request)).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(!response.headers().contains_key("deprecation"));
+
+        // 未知路径不是旧接口
+        let request = Request::get("/no/such/route").body(Body::empty()).unwrap();
+        let response = runtime.block_on(router.oneshot(request)).unwrap();
+        assert!(!response.headers().contains_key("deprecation"));
+    }
+
+    #[test]
+    fn test_readiness_and_metrics() {
+        infrastructure::metrics::install();
+        let store = Arc::new(InMemory::default());
+        let state = AppState::in_memory(AppConfig::for_test(), store.clone());
+        let router = webservice_router(state.clone());
+
+        let (status, body) = send(&router, Request::get("/healthz").body(Body::empty()).unwrap());
+        assert_eq!((status, body.as_slice()), (StatusCode::OK, b"ok".as_slice()));
+        let (status, body) = send(&router, Request::get("/readyz").body(Body::empty()).unwrap());
+        assert_eq!(status, StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["checks"]["database"]["ok"], true);
+
+        // 依赖不可用
+        store.unavailable.store(true, Ordering::SeqCst);
+        let (status, body) = send(&router, Request::get("/readyz").body(Body::empty()).unwrap());
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["status"], "unavailable");
+        assert_eq!(body["checks"]["redis"]["error"], "unavailable");
+
+        // 消费者未连接
+        store.unavailable.store(false, Ordering::SeqCst);
+        state.tasks.set_connected(account_bound_consumer::TASK, false);
+        let (status, body) = send(&router, Request::get("/readyz").body(Body::empty()).unwrap());
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["tasks"][account_bound_consumer::TASK]["connected"], false);
+
+        // 按路由模板记录耗时
+        send(&router, Request::get(format!("{}/collections/{}", v1::PREFIX, Uuid::nil())).body(Body::empty()).unwrap());
+        let (status, body) = send(&router, Request::get("/metrics").body(Body::empty()).unwrap());
+        assert_eq!(status, StatusCode::OK);
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains("http_request_duration_seconds"), "{}", body);
+        assert!(body.contains("route=\"/api/v1/collections/{id}\""), "{}", body);
+    }
+
+    #[test]
+    fn test_request_id_rate_limited() {
+        let router = router();
+        let request = |ip: [u8; 4]| {
+            let mut request = Request::get(format!("{}/request_id", v1::PREFIX)).body(Body::empty()).unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from((ip, 4000))));
+            request
+        };
+        let burst = AppConfig::for_test().runtime.rate_limits.request_id.per_ip.unwrap().burst;
+        for _ in 0..burst {
+            assert_eq!(send(&router, request([192, 0, 2, 1])).0, StatusCode::OK);
+        }
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let response = runtime.block_on(router.clone().oneshot(request([192, 0, 2, 1]))).unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers()[RETRY_AFTER].to_str().unwrap().parse::<u64>().unwrap() >= 1);
+        // 其他地址不受影响
+        assert_eq!(send(&router, request([192, 0, 2, 2])).0, StatusCode::OK);
+    }
+
+    /// 文档中的每个路径都必须由路由提供,且方法一致;用不支持的方法请求时路由返回405和Allow头,不会执行接口
+    #[test]
+    fn test_openapi_matches_router() {
+        let router = router();
+        let spec = ApiDoc::openapi();
+        let server = spec.servers.as_ref().and_then(|servers| servers.first()).map(|server| server.url.as_str());
+        assert_eq!(server, Some(v1::PREFIX));
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        for (path, item) in &spec.paths.paths {
+            let uri = format!("{}{}", v1::PREFIX, normalize_path(path).replace("{id}", &Uuid::nil().to_string()));
+            let request = Request::builder().method(Method::TRACE).uri(uri).body(Body::empty()).unwrap();
+            let response = runtime.block_on(router.clone().oneshot(request)).unwrap();
+            assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "{} is documented but not routed", path);
+
+            let mut routed: Vec<String> = response.headers().get(ALLOW)
+            .and_then(|allow| allow.to_str().ok())
+            .unwrap_or_default()
+            .split(',')
+            .map(|method| method.trim().to_owned())
+            .filter(|method| !method.is_empty() && method != "HEAD")
+            .collect();
+            routed.sort();
+            let mut documented = documented_methods(item);
+            documented.sort();
+            assert_eq!(routed, documented, "methods of {} differ between router and spec", path);
+        }
+
+        // 测试用的路由表也必须全部写进文档,需要登录的接口必须声明bearer认证
+        let documented = |method: &str, path: &str| spec.paths.paths.iter()
+        .find(|(documented_path, _)| normalize_path(documented_path) == path)
+        .and_then(|(_, item)| match method {
+            "GET" => item.get.as_ref(),
+            "POST" => item.post.as_ref(),
+            "PUT" => item.put.as_ref(),
+            _ => None,
+        });
+        let bearer = SecurityRequirement::new("bearer_auth", Vec::<String>::new());
+        for (method, path) in PROTECTED_ROUTES {
+            let operation = documented(method, path).unwrap_or_else(|| panic!("{} {} is not documented", method, path));
+            assert_eq!(operation.security.as_deref(), Some(std::slice::from_ref(&bearer)), "{} {} must require bearer auth", method, path);
+        }
+        for path in PUBLIC_ID_ROUTES {
+            assert!(documented("GET", path).is_some(), "GET {} is not documented", path);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_protected_routes_reject_bad_tokens(route in 0..PROTECTED_ROUTES.len(), id in malformed_id(), token in proptest::option::of("[!-~]{0,200}"), body in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let (method, path) = PROTECTED_ROUTES[route];
+            let mut request = Request::builder()
+            .method(Method::from_bytes(method.as_bytes()).unwrap())
+            .uri(format!("{}{}", v1::PREFIX, path.replace("{id}", &encode(&id))))
+            .header(CONTENT_TYPE, "application/json");
+            if let Some(token) = token {
+                request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+            }
+            let (status, _) = send(&router(), request.body(Body::from(body)).unwrap());
+            prop_assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+
+        #[test]
+        fn prop_public_routes_reject_malformed_ids(route in 0..PUBLIC_ID_ROUTES.len(), id in malformed_id()) {
+            let uri = format!("{}{}", v1::PREFIX, PUBLIC_ID_ROUTES[route].replace("{id}", &encode(&id)));
+            let (status, _) = send(&router(), Request::get(uri).body(Body::empty()).unwrap());
+            prop_assert!(status.is_client_error(), "{}", status);
+        }
+
+        #[test]
+        fn prop_search_rejects_malformed_query(cursor in ".{1,64}", category_id in malformed_id(), author in malformed_id(), page in ".{0,16}") {
+            prop_assume!(PageCursor::decode(&cursor).is_err());
+            let router = router();
+            for query in [
+                format!("cursor={}", encode(&cursor)),
+                format!("category_id={}", encode(&category_id)),
+                format!("author={}", encode(&author)),
+                format!("page={}&category_id={}", encode(&page), encode(&category_id)),
+            ] {
+                let (status, _) = send(&router, Request::get(format!("{}/collections?{}", v1::PREFIX, query)).body(Body::empty()).unwrap());
+                prop_assert!(status.is_client_error(), "{} {}", query, status);
+            }
+        }
+
+        #[test]
+        fn prop_logon_rejects_malformed_payload(body in proptest::collection::vec(any::<u8>(), 0..256), request_id in ".*", pub_key in "[0-9a-f]{64}|.*", sig in "[0-9a-f]{128}|.*") {
+            let router = router();
+            let payload = serde_json::json!({"request_id": request_id, "pub_key": pub_key, "sig": sig, "nick_name": "papi"}).to_string();
+            for path in ["/api/v1/signin", "/api/v1/signup"] {
+                for body in [body.clone(), payload.clone().into_bytes()] {
+                    let request = Request::post(path).header(CONTENT_TYPE, "application/json").body(Body::from(body)).unwrap();
+                    let (status, _) = send(&router, request);
+                    prop_assert!(status.is_client_error(), "{} {}", path, status);
                 }
             }
         }
-        Err(unauthorized_response)
-    })).into_make_service())
-        .await
-        .unwrap();
+    }
 }
-
-async fn handler_404() -> impl IntoResponse {
-    (StatusCode::NOT_FOUND, "nothing to see here")
-}
\ No newline at end of file
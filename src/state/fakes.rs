@@ -0,0 +1,633 @@
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, ops::Range, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Local;
+use futures::{StreamExt, TryStreamExt};
+use sui_sdk::types::base_types::ObjectID;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{domain::{model::{entity::{account, bassinet_coin, bassinet_nft, category, chunk_list, collection, collection_item, file_entity, nft_token}, valueobject::{nft_event::{EventCursor, EventStream, NftEvent, NftEventPage}, page::{PageCursor, PageRequest, PageResult}}}, repository::{account_repository::AccountRepository, bassinet_coin_repository::BassinetCoinRepository, bassinet_nft_repository::BassinetNftRepository, category_repository::CategoryRepository, chunk_list_repository::ChunkListRepository, collection_repository::CollectionRepository, file_repository::FileRepository, nft_token_repository::NftTokenRepository, search_repository::{CollectionSearchCriteria, CollectionSearchRow, FacetRow, SearchRepository}, tag_repository::{TagCount, TagRepository}}, service::nft_ownership}, infrastructure::{cache::Cache, health::HealthCheck, ipfs::Pinner, jwt::Keys, rate_limit::{self, Bucket, RateLimiter, RatePolicy, Throttle}, storage::{self, ByteStream, Object, StorageBackend, Volume}, sui::{NftEventSource, NftQuery}}, settings::{AppConfig, RuntimeHandle}, supervisor::TaskRegistry, utils};
+
+use super::AppState;
+
+/// 内存中的存储、缓存和链上数据,实现AppState中的所有trait,测试中直接读写各字段准备数据和检查结果
+#[derive(Default)]
+pub struct InMemory {
+    pub accounts: Mutex<Vec<account::Model>>,
+    pub coins: Mutex<Vec<bassinet_coin::Model>>,
+    pub nfts: Mutex<Vec<bassinet_nft::Model>>,
+    pub categories: Mutex<Vec<category::Model>>,
+    pub chunks: Mutex<Vec<chunk_list::Model>>,
+    pub collections: Mutex<Vec<collection::Model>>,
+    pub items: Mutex<Vec<collection_item::Model>>,
+    pub files: Mutex<Vec<file_entity::Model>>,
+    // collection_id -> 标签
+    pub tags: Mutex<HashMap<Uuid, Vec<String>>>,
+    // 不过期
+    pub cache: Mutex<HashMap<String, String>>,
+    // 限流令牌桶
+    pub rate_buckets: Mutex<HashMap<String, Bucket>>,
+    // 文件存储
+    pub objects: Mutex<HashMap<(Volume, String), Bytes>>,
+    // IPFS节点上固定的内容 cid -> 内容
+    pub pins: Mutex<HashMap<String, Bytes>>,
+    // 链上持有的NFT (wallet_address, package_id)
+    pub owned_nfts: Mutex<Vec<(String, String)>>,
+    // 链上NFT对象的持有地址 object_id -> 地址
+    pub nft_owners: Mutex<HashMap<String, String>>,
+    // 链上的NFT事件 (package_id, 事件来源, 事件),按链上顺序
+    pub nft_events: Mutex<Vec<(String, EventStream, NftEvent)>>,
+    // 索引的NFT
+    pub tokens: Mutex<Vec<nft_token::Model>>,
+    pub event_cursors: Mutex<HashMap<(String, EventStream), EventCursor>>,
+    // 为true时所有依赖的健康检查和链上查询失败
+    pub unavailable: AtomicBool,
+}
+
+impl AppState {
+    /// 使用内存实现的状态
+    pub fn in_memory(config: AppConfig, store: Arc<InMemory>) -> Self {
+        Self {
+            runtime: RuntimeHandle::new(config.runtime.clone()),
+            jwt_keys: Arc::new(Keys::new(config.jwt.secret.as_bytes())),
+            config: Arc::new(config),
+            account_repository: store.clone(),
+            bassinet_coin_repository: store.clone(),
+            bassinet_nft_repository: store.clone(),
+            category_repository: store.clone(),
+            chunk_list_repository: store.clone(),
+            collection_repository: store.clone(),
+            file_repository: store.clone(),
+            search_repository: store.clone(),
+            tag_repository: store.clone(),
+            nft_token_repository: store.clone(),
+            cache: store.clone(),
+            rate_limiter: store.clone(),
+            storage: store.clone(),
+            pinner: store.clone(),
+            pin_requests: Arc::new(Notify::new()),
+            nft_query: store.clone(),
+            nft_events: store.clone(),
+            health_checks: Arc::new([("database", store.clone() as Arc<dyn HealthCheck>), ("redis", store.clone()), ("sui", store)]),
+            tasks: TaskRegistry::default(),
+        }
+    }
+}
+
+/// 测试用的专辑:有效、未公开、未上架,其余字段用结构体更新语法按需覆盖
+pub fn collection(author: Uuid) -> collection::Model {
+    collection::Model {
+        id: Uuid::new_v4(),
+        title: "papi".to_owned(),
+        description: String::new(),
+        created_time: Local::now().naive_utc(),
+        is_public: 0,
+        author,
+        listing: None,
+        icon_url: None,
+        package_id: None,
+        seq: 1,
+        status: 1,
+        category_id: None,
+    }
+}
+
+/// 测试用的专辑项:collection中有效、未公开的图文,作者与专辑相同
+pub fn item(collection: &collection::Model) -> collection_item::Model {
+    collection_item::Model {
+        id: Uuid::new_v4(),
+        collection_id: collection.id,
+        seq: 1,
+        title: None,
+        description: None,
+        created_time: Local::now().naive_utc(),
+        is_public: 0,
+        author: collection.author.to_string(),
+        category: "article".to_owned(),
+        content: None,
+        path: None,
+        hash: None,
+        ipfs: None,
+        status: Some(1),
+    }
+}
+
+/// 测试用的有效账户,未绑定钱包
+pub fn account(pub_key: &str) -> account::Model {
+    account::Model {
+        id: Uuid::new_v4(),
+        nick_name: None,
+        avatar: String::new(),
+        pub_key: Some(pub_key.to_owned()),
+        wallet_address: None,
+        created_time: Local::now().naive_utc(),
+        status: Some(1),
+    }
+}
+
+/// 测试用的专辑NFT:package_id为0xnft,限量100,铸造价格1000,奖励10
+pub fn nft(collection_id: Uuid) -> bassinet_nft::Model {
+    bassinet_nft::Model {
+        id: Uuid::new_v4(),
+        package_id: "0xnft".to_owned(),
+        collection_id,
+        description: None,
+        collection_url: None,
+        limit: Some(100),
+        minting_price: 1_000,
+        rewards_quantity: Some(10),
+        mint_id: None,
+        policy_id: None,
+        policy_cap_id: None,
+        coin_id: None,
+        coin_package_id: None,
+        coin_treasury_lock_id: None,
+        coin_admin_cap_id: None,
+        metadata_ipfs: None,
+    }
+}
+
+fn is_public(collection: &collection::Model) -> bool {
+    collection.status == 1 && (collection.is_public == 1 || collection.listing == Some(1))
+}
+
+fn page<T>(mut rows: Vec<T>, request: &PageRequest, cursor_of: impl Fn(&T) -> PageCursor) -> PageResult<T> {
+    let total = request.with_total.then_some(rows.len() as u64);
+    if let Some(cursor) = &request.cursor {
+        rows.retain(|row| {
+            let row = cursor_of(row);
+            (row.created_time, row.id) < (cursor.created_time, cursor.id)
+        });
+    }
+    let rows = rows.into_iter().skip(request.offset() as usize).take(request.limit as usize + 1).collect();
+    PageResult::from_rows(rows, request, total, cursor_of)
+}
+
+#[async_trait]
+impl AccountRepository for InMemory {
+    async fn get_account_by(&self, pub_key: &String) -> Result<Option<account::Model>, anyhow::Error> {
+        Ok(self.accounts.lock().unwrap().iter().find(|account| account.pub_key.as_ref() == Some(pub_key)).cloned())
+    }
+
+    async fn get_authors(&self) -> Result<Vec<account::Model>, anyhow::Error> {
+        let collections = self.collections.lock().unwrap();
+        let mut authors: Vec<account::Model> = self.accounts.lock().unwrap().iter()
+        .filter(|account| collections.iter().any(|collection| collection.author == account.id && is_public(collection)))
+        .cloned()
+        .collect();
+        authors.sort_by_key(|account| account.created_time);
+        Ok(authors)
+    }
+
+    async fn add_account(&self, account: account::Model) -> Result<(), anyhow::Error> {
+        self.accounts.lock().unwrap().push(account);
+        Ok(())
+    }
+
+    async fn bind_wallet(&self, account_id: &Uuid, wallet_address: String) -> Result<(), anyhow::Error> {
+        if let Some(account) = self.accounts.lock().unwrap().iter_mut().find(|account| account.id == *account_id) {
+            account.wallet_address = Some(wallet_address);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BassinetCoinRepository for InMemory {
+    async fn get_coin_by_package_id(&self, package_id: &String) -> Result<Option<bassinet_coin::Model>, anyhow::Error> {
+        Ok(self.coins.lock().unwrap().iter().find(|coin| coin.package_id == *package_id).cloned())
+    }
+
+    async fn get_coin_by_account_id(&self, account_id: &Uuid) -> Result<Option<bassinet_coin::Model>, anyhow::Error> {
+        Ok(self.coins.lock().unwrap().iter().find(|coin| coin.account_id == Some(*account_id)).cloned())
+    }
+
+    async fn add_coin(&self, coin: bassinet_coin::Model) -> Result<(), anyhow::Error> {
+        self.coins.lock().unwrap().push(coin);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BassinetNftRepository for InMemory {
+    async fn get_nft_by_collection_id(&self, collection_id: &Uuid) -> Result<Option<bassinet_nft::Model>, anyhow::Error> {
+        Ok(self.nfts.lock().unwrap().iter().find(|nft| nft.collection_id == *collection_id).cloned())
+    }
+
+    async fn get_nft_by_collection_ids(&self, collection_ids: &[Uuid]) -> Result<HashMap<Uuid, bassinet_nft::Model>, anyhow::Error> {
+        Ok(self.nfts.lock().unwrap().iter()
+        .filter(|nft| collection_ids.contains(&nft.collection_id))
+        .map(|nft| (nft.collection_id, nft.clone()))
+        .collect())
+    }
+
+    async fn get_all_nfts(&self) -> Result<Vec<bassinet_nft::Model>, anyhow::Error> {
+        Ok(self.nfts.lock().unwrap().clone())
+    }
+
+    async fn add_nft(&self, nft: bassinet_nft::Model) -> Result<(), anyhow::Error> {
+        self.nfts.lock().unwrap().push(nft);
+        Ok(())
+    }
+
+    async fn set_metadata_ipfs(&self, nft_id: &Uuid, cid: &str) -> Result<(), anyhow::Error> {
+        if let Some(nft) = self.nfts.lock().unwrap().iter_mut().find(|nft| nft.id == *nft_id) {
+            nft.metadata_ipfs = Some(cid.to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CategoryRepository for InMemory {
+    async fn get_categories(&self) -> Result<Vec<category::Model>, anyhow::Error> {
+        let mut categories: Vec<category::Model> = self.categories.lock().unwrap().iter().filter(|category| category.status == 1).cloned().collect();
+        categories.sort_by_key(|category| category.seq);
+        Ok(categories)
+    }
+
+    async fn get_category_by_id(&self, category_id: &Uuid) -> Result<Option<category::Model>, anyhow::Error> {
+        Ok(self.categories.lock().unwrap().iter().find(|category| category.id == *category_id && category.status == 1).cloned())
+    }
+}
+
+#[async_trait]
+impl ChunkListRepository for InMemory {
+    async fn add_chunk_list(&self, mut chunk_list: chunk_list::Model) -> Result<(), anyhow::Error> {
+        let mut chunks = self.chunks.lock().unwrap();
+        chunk_list.id = chunks.len() as i32 + 1;
+        chunks.push(chunk_list);
+        Ok(())
+    }
+
+    async fn query_chunk_list(&self, md5: &String) -> Result<Vec<chunk_list::Model>, anyhow::Error> {
+        let mut chunks: Vec<chunk_list::Model> = self.chunks.lock().unwrap().iter().filter(|chunk| chunk.file_hash == *md5).cloned().collect();
+        chunks.sort_by_key(|chunk| chunk.chunk_number);
+        Ok(chunks)
+    }
+
+    async fn get_chunk(&self, md5: &String, chunk_number: i32) -> Result<Option<chunk_list::Model>, anyhow::Error> {
+        Ok(self.chunks.lock().unwrap().iter().find(|chunk| chunk.file_hash == *md5 && chunk.chunk_number == chunk_number).cloned())
+    }
+}
+
+#[async_trait]
+impl CollectionRepository for InMemory {
+    async fn get_by_id(&self, collection_id: &String) -> Result<Option<collection::Model>, anyhow::Error> {
+        let Ok(collection_id) = Uuid::parse_str(collection_id) else {
+            return Ok(None)
+        };
+        Ok(self.collections.lock().unwrap().iter().find(|collection| collection.id == collection_id).cloned())
+    }
+
+    async fn get_by_author(&self, author_id: &Uuid) -> Result<Vec<collection::Model>, anyhow::Error> {
+        let mut collections: Vec<collection::Model> = self.collections.lock().unwrap().iter()
+        .filter(|collection| collection.author == *author_id && collection.status == 1)
+        .cloned()
+        .collect();
+        collections.sort_by(|a, b| b.created_time.cmp(&a.created_time));
+        Ok(collections)
+    }
+
+    async fn page_collections(&self, author_id: &Uuid, public_only: bool, request: &PageRequest) -> Result<PageResult<collection::Model>, anyhow::Error> {
+        let mut collections: Vec<collection::Model> = self.collections.lock().unwrap().iter()
+        .filter(|collection| collection.author == *author_id && (!public_only || is_public(collection)))
+        .cloned()
+        .collect();
+        collections.sort_by(|a, b| (b.created_time, b.id).cmp(&(a.created_time, a.id)));
+        Ok(page(collections, request, |collection| PageCursor::new(None, collection.created_time, collection.id)))
+    }
+
+    async fn create_collection(&self, collection: collection::Model, tags: &Vec<String>) -> Result<(), anyhow::Error> {
+        if !tags.is_empty() {
+            self.tags.lock().unwrap().insert(collection.id, tags.clone());
+        }
+        self.collections.lock().unwrap().push(collection);
+        Ok(())
+    }
+
+    async fn search_collection_by(&self, title: &String, author_id: &Uuid) -> Result<Vec<collection::Model>, anyhow::Error> {
+        Ok(self.collections.lock().unwrap().iter()
+        .filter(|collection| collection.author == *author_id && collection.title == *title && collection.status == 1)
+        .cloned()
+        .collect())
+    }
+
+    async fn mark_listed(&self, collection_id: &Uuid) -> Result<(), anyhow::Error> {
+        if let Some(collection) = self.collections.lock().unwrap().iter_mut().find(|collection| collection.id == *collection_id) {
+            collection.listing = Some(1);
+        }
+        Ok(())
+    }
+
+    async fn get_listed_collections(&self) -> Result<Vec<collection::Model>, anyhow::Error> {
+        Ok(self.collections.lock().unwrap().iter()
+        .filter(|collection| collection.listing == Some(1) && collection.status == 1)
+        .cloned()
+        .collect())
+    }
+
+    async fn create_collection_item(&self, collection_item: collection_item::Model) -> Result<(), anyhow::Error> {
+        self.items.lock().unwrap().push(collection_item);
+        Ok(())
+    }
+
+    async fn get_items_by(&self, collection_id: &Uuid) -> Result<Vec<collection_item::Model>, anyhow::Error> {
+        let mut items: Vec<collection_item::Model> = self.items.lock().unwrap().iter()
+        .filter(|item| item.collection_id == *collection_id && item.status == Some(1))
+        .cloned()
+        .collect();
+        items.sort_by(|a, b| b.created_time.cmp(&a.created_time));
+        Ok(items)
+    }
+
+    async fn get_item_by(&self, item_id: &String) -> Result<Option<collection_item::Model>, anyhow::Error> {
+        let Ok(item_id) = Uuid::parse_str(item_id) else {
+            return Ok(None)
+        };
+        Ok(self.items.lock().unwrap().iter().find(|item| item.id == item_id).cloned())
+    }
+
+    async fn set_item_ipfs(&self, item_id: &Uuid, cid: &str) -> Result<(), anyhow::Error> {
+        if let Some(item) = self.items.lock().unwrap().iter_mut().find(|item| item.id == *item_id) {
+            item.ipfs = Some(cid.to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FileRepository for InMemory {
+    async fn add_file(&self, file_entity: file_entity::Model) -> Result<(), anyhow::Error> {
+        self.files.lock().unwrap().push(file_entity);
+        Ok(())
+    }
+
+    async fn get_file_by_path(&self, path: &str) -> Result<Option<file_entity::Model>, anyhow::Error> {
+        Ok(self.files.lock().unwrap().iter().find(|file| file.path.as_deref() == Some(path) && file.status == Some(1)).cloned())
+    }
+
+    async fn set_file_ipfs(&self, file_id: &Uuid, cid: &str) -> Result<(), anyhow::Error> {
+        if let Some(file) = self.files.lock().unwrap().iter_mut().find(|file| file.id == *file_id) {
+            file.ipfs = Some(cid.to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// 只按标题关键字和条件过滤,不计算相关度,不统计分面
+#[async_trait]
+impl SearchRepository for InMemory {
+    async fn search_collections(&self, criteria: &CollectionSearchCriteria, request: &PageRequest) -> Result<PageResult<CollectionSearchRow>, anyhow::Error> {
+        let keyword = criteria.keyword.as_ref().map(|keyword| keyword.trim().to_lowercase()).filter(|keyword| !keyword.is_empty());
+        let collections = self.collections.lock().unwrap();
+        let tags = self.tags.lock().unwrap();
+        let mut rows: Vec<CollectionSearchRow> = collections.iter()
+        .filter(|collection| is_public(collection))
+        .filter(|collection| keyword.as_ref().is_none_or(|keyword| collection.title.to_lowercase().contains(keyword)))
+        .filter(|collection| criteria.author.is_none_or(|author| collection.author == author))
+        .filter(|collection| criteria.category_id.is_none_or(|category_id| collection.category_id == Some(category_id)))
+        .filter(|collection| criteria.tag.as_ref().is_none_or(|tag| tags.get(&collection.id).is_some_and(|names| names.contains(&tag.trim().to_lowercase()))))
+        .filter(|collection| criteria.listed.is_none_or(|listed| (collection.listing == Some(1)) == listed))
+        .map(|collection| CollectionSearchRow {
+            id: collection.id,
+            title: collection.title.clone(),
+            description: collection.description.clone(),
+            created_time: collection.created_time,
+            is_public: collection.is_public,
+            listing: collection.listing,
+            icon_url: collection.icon_url.clone(),
+            rank: 0.0,
+            title_highlight: collection.title.clone(),
+            snippet: None,
+            item_id: None,
+        })
+        .collect();
+        rows.sort_by(|a, b| (b.created_time, b.id).cmp(&(a.created_time, a.id)));
+        Ok(page(rows, request, |row| PageCursor::new(Some(row.rank), row.created_time, row.id)))
+    }
+
+    async fn search_facets(&self, _criteria: &CollectionSearchCriteria, _tag_limit: u64) -> Result<(Vec<FacetRow>, Vec<FacetRow>), anyhow::Error> {
+        Ok((Vec::new(), Vec::new()))
+    }
+}
+
+#[async_trait]
+impl TagRepository for InMemory {
+    async fn get_tags_by_collection(&self, collection_id: &Uuid) -> Result<Vec<String>, anyhow::Error> {
+        let mut tags = self.tags.lock().unwrap().get(collection_id).cloned().unwrap_or_default();
+        tags.sort();
+        Ok(tags)
+    }
+
+    async fn set_collection_tags(&self, collection_id: &Uuid, names: &Vec<String>) -> Result<(), anyhow::Error> {
+        self.tags.lock().unwrap().insert(*collection_id, names.clone());
+        Ok(())
+    }
+
+    async fn popular_tags(&self, prefix: Option<String>, limit: u64) -> Result<Vec<TagCount>, anyhow::Error> {
+        let prefix = prefix.map(|prefix| prefix.trim().to_lowercase()).unwrap_or_default();
+        let collections = self.collections.lock().unwrap();
+        let mut counts: HashMap<String, i64> = HashMap::new();
+        for (collection_id, names) in self.tags.lock().unwrap().iter() {
+            if !collections.iter().any(|collection| collection.id == *collection_id && is_public(collection)) {
+                continue;
+            }
+            for name in names.iter().filter(|name| name.starts_with(&prefix)) {
+                *counts.entry(name.clone()).or_default() += 1;
+            }
+        }
+        let mut tags: Vec<TagCount> = counts.into_iter().map(|(name, count)| TagCount { name, count }).collect();
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        tags.truncate(limit as usize);
+        Ok(tags)
+    }
+
+    async fn related_collections(&self, collection_id: &Uuid, limit: u64) -> Result<Vec<collection::Model>, anyhow::Error> {
+        let collections = self.collections.lock().unwrap();
+        let tags = self.tags.lock().unwrap();
+        let names = tags.get(collection_id).cloned().unwrap_or_default();
+        let mut related: Vec<(usize, collection::Model)> = collections.iter()
+        .filter(|collection| collection.id != *collection_id && is_public(collection))
+        .map(|collection| {
+            let shared = tags.get(&collection.id).map_or(0, |other| other.iter().filter(|name| names.contains(name)).count());
+            (shared, collection.clone())
+        })
+        .filter(|(shared, _)| *shared > 0)
+        .collect();
+        related.sort_by(|(a, a_collection), (b, b_collection)| b.cmp(a).then_with(|| b_collection.created_time.cmp(&a_collection.created_time)));
+        Ok(related.into_iter().take(limit as usize).map(|(_, collection)| collection).collect())
+    }
+}
+
+#[async_trait]
+impl Cache for InMemory {
+    async fn get(&self, key: &str) -> Result<Option<String>, anyhow::Error> {
+        Ok(self.cache.lock().unwrap().get(key).cloned())
+    }
+
+    async fn set_ex(&self, key: &str, value: &str, _seconds: u64) -> Result<(), anyhow::Error> {
+        self.cache.lock().unwrap().insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InMemory {
+    async fn acquire(&self, key: &str, policy: RatePolicy, cost: u32) -> Result<Throttle, anyhow::Error> {
+        let mut buckets = self.rate_buckets.lock().unwrap();
+        let (bucket, throttle) = rate_limit::take(buckets.get(key).copied(), utils::current_millis(), policy, cost);
+        buckets.insert(key.to_owned(), bucket);
+        Ok(throttle)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for InMemory {
+    async fn put(&self, volume: Volume, key: &str, body: ByteStream<'_>) -> Result<u64, anyhow::Error> {
+        let chunks: Vec<Bytes> = body.try_collect().await?;
+        let data = Bytes::from(chunks.concat());
+        let size = data.len() as u64;
+        self.objects.lock().unwrap().insert((volume, key.to_owned()), data);
+        Ok(size)
+    }
+
+    async fn get(&self, volume: Volume, key: &str, range: Option<Range<u64>>) -> Result<Option<Object>, anyhow::Error> {
+        let Some(data) = self.objects.lock().unwrap().get(&(volume, key.to_owned())).cloned() else {
+            return Ok(None)
+        };
+        let size = data.len() as u64;
+        let range = storage::clamp(range, size);
+        let body = data.slice(range.start as usize..range.end as usize);
+        Ok(Some(Object { body: futures::stream::once(async { Ok(body) }).boxed(), size, range }))
+    }
+
+    async fn size(&self, volume: Volume, key: &str) -> Result<Option<u64>, anyhow::Error> {
+        Ok(self.objects.lock().unwrap().get(&(volume, key.to_owned())).map(|data| data.len() as u64))
+    }
+
+    async fn delete(&self, volume: Volume, key: &str) -> Result<(), anyhow::Error> {
+        self.objects.lock().unwrap().remove(&(volume, key.to_owned()));
+        Ok(())
+    }
+
+    async fn copy(&self, volume: Volume, from: &str, to: &str) -> Result<(), anyhow::Error> {
+        let mut objects = self.objects.lock().unwrap();
+        let data = objects.get(&(volume, from.to_owned())).cloned().ok_or_else(|| anyhow::anyhow!("{} not found", from))?;
+        objects.insert((volume, to.to_owned()), data);
+        Ok(())
+    }
+
+    async fn presign(&self, _volume: Volume, _key: &str, _expires: Duration) -> Result<Option<String>, anyhow::Error> {
+        Ok(None)
+    }
+}
+
+/// CID由内容的哈希生成,相同的内容得到相同的CID
+#[async_trait]
+impl Pinner for InMemory {
+    async fn add(&self, body: ByteStream<'_>) -> Result<String, anyhow::Error> {
+        let chunks: Vec<Bytes> = body.try_collect().await?;
+        let data = Bytes::from(chunks.concat());
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let cid = format!("bafkfake{:016x}", hasher.finish());
+        self.pins.lock().unwrap().insert(cid.clone(), data);
+        Ok(cid)
+    }
+
+    async fn is_pinned(&self, cid: &str) -> Result<bool, anyhow::Error> {
+        Ok(self.pins.lock().unwrap().contains_key(cid))
+    }
+}
+
+#[async_trait]
+impl NftQuery for InMemory {
+    async fn get_any_bassinet_nft_by(&self, address: &String, package_id: &String) -> Result<Option<ObjectID>, anyhow::Error> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("unavailable"))
+        }
+        let owned = self.owned_nfts.lock().unwrap().iter().any(|(owner, package)| owner == address && package == package_id);
+        Ok(owned.then_some(ObjectID::ZERO))
+    }
+
+    async fn get_nft_owner(&self, object_id: &str) -> Result<Option<String>, anyhow::Error> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("unavailable"))
+        }
+        Ok(self.nft_owners.lock().unwrap().get(object_id).cloned())
+    }
+}
+
+#[async_trait]
+impl NftEventSource for InMemory {
+    async fn query_nft_events(&self, package_id: &str, stream: EventStream, cursor: Option<EventCursor>, limit: usize) -> Result<NftEventPage, anyhow::Error> {
+        let events: Vec<NftEvent> = self.nft_events.lock().unwrap().iter()
+        .filter(|(package, event_stream, _)| package == package_id && *event_stream == stream)
+        .map(|(_, _, event)| event.clone())
+        .collect();
+        let start = match cursor {
+            Some(cursor) => events.iter().position(|event| event.id == cursor).map_or(0, |position| position + 1),
+            None => 0,
+        };
+        let page: Vec<NftEvent> = events[start..].iter().take(limit).cloned().collect();
+        Ok(NftEventPage {
+            next_cursor: page.last().map(|event| event.id.clone()),
+            has_next_page: start + page.len() < events.len(),
+            events: page,
+        })
+    }
+}
+
+#[async_trait]
+impl NftTokenRepository for InMemory {
+    async fn get_event_cursor(&self, package_id: &str, stream: EventStream) -> Result<Option<EventCursor>, anyhow::Error> {
+        Ok(self.event_cursors.lock().unwrap().get(&(package_id.to_owned(), stream)).cloned())
+    }
+
+    async fn apply_nft_events(&self, package_id: &str, collection_id: &Uuid, stream: EventStream, events: &[NftEvent], cursor: &EventCursor) -> Result<usize, anyhow::Error> {
+        let mut tokens = self.tokens.lock().unwrap();
+        let mut updated = 0;
+        for event in events {
+            let position = tokens.iter().position(|token| token.object_id == event.object_id);
+            let Some(token) = nft_ownership::apply(position.map(|position| tokens[position].clone()), package_id, *collection_id, event) else {
+                continue
+            };
+            match position {
+                Some(position) => tokens[position] = token,
+                None => tokens.push(token),
+            }
+            updated += 1;
+        }
+        self.event_cursors.lock().unwrap().insert((package_id.to_owned(), stream), cursor.clone());
+        Ok(updated)
+    }
+
+    async fn owns_token(&self, owner: &str, package_id: &str) -> Result<bool, anyhow::Error> {
+        Ok(self.tokens.lock().unwrap().iter().any(|token| token.owner.as_deref() == Some(owner) && token.package_id == package_id && token.status == 1))
+    }
+
+    async fn get_unresolved_tokens(&self, package_id: &str) -> Result<Vec<nft_token::Model>, anyhow::Error> {
+        Ok(self.tokens.lock().unwrap().iter().filter(|token| token.package_id == package_id && token.owner_unresolved == 1).cloned().collect())
+    }
+
+    async fn update_token(&self, token: nft_token::Model) -> Result<(), anyhow::Error> {
+        let mut tokens = self.tokens.lock().unwrap();
+        if let Some(existing) = tokens.iter_mut().find(|existing| existing.object_id == token.object_id) {
+            *existing = token;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HealthCheck for InMemory {
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("unavailable"))
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use sea_orm::DatabaseConnection;
+use tokio::sync::Notify;
+
+use crate::{domain::repository::{account_repository::AccountRepository, bassinet_coin_repository::BassinetCoinRepository, bassinet_nft_repository::BassinetNftRepository, category_repository::CategoryRepository, chunk_list_repository::ChunkListRepository, collection_repository::CollectionRepository, file_repository::FileRepository, nft_token_repository::NftTokenRepository, search_repository::SearchRepository, tag_repository::TagRepository}, infrastructure::{cache::Cache, health::HealthCheck, ipfs::Pinner, jwt::Keys, rate_limit::RateLimiter, redis_connection::RedisPool, storage::StorageBackend, sui::{nft_query::SuiNftQuery, NftEventSource, NftQuery}}, settings::{AppConfig, RuntimeHandle}, supervisor::TaskRegistry};
+
+#[cfg(test)]
+pub mod fakes;
+
+/// 应用状态,通过axum State传给接口,再传给应用服务和消息消费者
+///
+/// 存储、缓存和链上查询都是trait对象,单元测试中替换为fakes中的内存实现
+#[derive(Clone)]
+pub struct AppState {
+    // 启动时的配置
+    pub config: Arc<AppConfig>,
+    // 可热更新的runtime配置
+    pub runtime: RuntimeHandle,
+    pub jwt_keys: Arc<Keys>,
+    pub account_repository: Arc<dyn AccountRepository>,
+    pub bassinet_coin_repository: Arc<dyn BassinetCoinRepository>,
+    pub bassinet_nft_repository: Arc<dyn BassinetNftRepository>,
+    pub category_repository: Arc<dyn CategoryRepository>,
+    pub chunk_list_repository: Arc<dyn ChunkListRepository>,
+    pub collection_repository: Arc<dyn CollectionRepository>,
+    pub file_repository: Arc<dyn FileRepository>,
+    pub search_repository: Arc<dyn SearchRepository>,
+    pub tag_repository: Arc<dyn TagRepository>,
+    // 链上索引的NFT持有者
+    pub nft_token_repository: Arc<dyn NftTokenRepository>,
+    // Redis
+    pub cache: Arc<dyn Cache>,
+    // 限流令牌桶
+    pub rate_limiter: Arc<dyn RateLimiter>,
+    // 资源和音视频文件
+    pub storage: Arc<dyn StorageBackend>,
+    // 上架NFT的专辑内容固定到IPFS
+    pub pinner: Arc<dyn Pinner>,
+    // 专辑上架后通知ipfs_pinner任务
+    pub pin_requests: Arc<Notify>,
+    // Sui fullnode
+    pub nft_query: Arc<dyn NftQuery>,
+    pub nft_events: Arc<dyn NftEventSource>,
+    // readiness检查的外部依赖
+    pub health_checks: Arc<[(&'static str, Arc<dyn HealthCheck>)]>,
+    // 后台任务状态
+    pub tasks: TaskRegistry,
+}
+
+impl AppState {
+    /// 所有存储共用一个数据库连接池,Sui查询共用一组fullnode客户端
+    pub fn new(config: Arc<AppConfig>, db: DatabaseConnection, redis: RedisPool, storage: Arc<dyn StorageBackend>, pinner: Arc<dyn Pinner>, sui: Arc<SuiNftQuery>) -> Self {
+        let db = Arc::new(db);
+        let redis = Arc::new(redis);
+        Self {
+            account_repository: db.clone(),
+            bassinet_coin_repository: db.clone(),
+            bassinet_nft_repository: db.clone(),
+            category_repository: db.clone(),
+            chunk_list_repository: db.clone(),
+            collection_repository: db.clone(),
+            file_repository: db.clone(),
+            search_repository: db.clone(),
+            tag_repository: db.clone(),
+            nft_token_repository: db.clone(),
+            cache: redis.clone(),
+            rate_limiter: redis.clone(),
+            storage,
+            pinner,
+            pin_requests: Arc::new(Notify::new()),
+            nft_query: sui.clone(),
+            nft_events: sui.clone(),
+            health_checks: Arc::new([("database", db as Arc<dyn HealthCheck>), ("redis", redis), ("sui", sui)]),
+            tasks: TaskRegistry::default(),
+            runtime: RuntimeHandle::new(config.runtime.clone()),
+            jwt_keys: Arc::new(Keys::new(config.jwt.secret.as_bytes())),
+            config,
+        }
+    }
+}
+
+impl FromRef<AppState> for Arc<Keys> {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt_keys.clone()
+    }
+}
+
+impl FromRef<AppState> for RuntimeHandle {
+    fn from_ref(state: &AppState) -> Self {
+        state.runtime.clone()
+    }
+}
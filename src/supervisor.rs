@@ -0,0 +1,250 @@
+//! 后台任务(HTTP服务、消息消费者、配置重新加载)的启动、重启和停止
+
+use std::{collections::BTreeMap, future::Future, sync::{Arc, Mutex, PoisonError}, time::{Duration, Instant}};
+
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+/// 第一次重启前的等待时间,之后每次翻倍
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// 最长重启间隔
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// 连续运行超过该时长后,重启间隔恢复为初始值
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// 收到停止信号后等待任务结束的最长时间,超时后中止剩余任务
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    // 出错后等待重启
+    Restarting,
+    // 运行期间正常返回,不再重启,不影响readiness
+    Finished,
+    // 收到停止信号后结束
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHealth {
+    pub state: TaskState,
+    // 累计重启次数
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    // 维持外部连接的任务(如消息消费者)上报的连接状态
+    pub connected: Option<bool>,
+}
+
+/// 各任务的运行状态,由Supervisor更新
+#[derive(Clone, Default)]
+pub struct TaskRegistry(Arc<Mutex<BTreeMap<&'static str, TaskHealth>>>);
+
+impl TaskRegistry {
+    pub fn snapshot(&self) -> BTreeMap<&'static str, TaskHealth> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// 任务上报外部连接状态,未连接的任务视为未就绪
+    pub fn set_connected(&self, name: &'static str, connected: bool) {
+        self.update(name, |health| health.connected = Some(connected));
+    }
+
+    fn update(&self, name: &'static str, update: impl FnOnce(&mut TaskHealth)) {
+        let mut tasks = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let health = tasks.entry(name).or_insert(TaskHealth { state: TaskState::Running, restarts: 0, last_error: None, connected: None });
+        update(health);
+    }
+}
+
+/// 运行一组长期任务,任务出错或panic时按退避间隔重启,收到停止信号后等待所有任务结束
+pub struct Supervisor {
+    shutdown: CancellationToken,
+    registry: TaskRegistry,
+    tasks: JoinSet<()>,
+}
+
+impl Supervisor {
+    pub fn new(shutdown: CancellationToken, registry: TaskRegistry) -> Self {
+        Self { shutdown, registry, tasks: JoinSet::new() }
+    }
+
+    /// 启动任务,task每次被调用都启动一个新的实例
+    ///
+    /// 实例收到停止信号后应尽快返回Ok;返回Ok后不再重启,返回错误或panic时重启
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let shutdown = self.shutdown.clone();
+        let registry = self.registry.clone();
+        registry.update(name, |health| health.state = TaskState::Running);
+        self.tasks.spawn(async move {
+            let mut backoff = INITIAL_BACKOFF;
+            loop {
+                registry.update(name, |health| health.state = TaskState::Running);
+                let started = Instant::now();
+                // 在单独的tokio任务中运行,panic只影响这个实例
+                let result = match tokio::spawn(task(shutdown.clone())).await {
+                    Ok(result) => result,
+                    Err(err) => Err(anyhow!("task panicked: {}", err)),
+                };
+                let err = match result {
+                    Ok(()) => {
+                        tracing::info!("{} stopped", name);
+                        let state = if shutdown.is_cancelled() { TaskState::Stopped } else { TaskState::Finished };
+                        registry.update(name, |health| health.state = state);
+                        return
+                    }
+                    Err(err) => err,
+                };
+                registry.update(name, |health| health.last_error = Some(format!("{:#}", err)));
+                if shutdown.is_cancelled() {
+                    tracing::error!("{} failed while shutting down: {:#}", name, err);
+                    registry.update(name, |health| health.state = TaskState::Stopped);
+                    return
+                }
+                if started.elapsed() >= STABLE_AFTER {
+                    backoff = INITIAL_BACKOFF;
+                }
+                tracing::error!("{} failed, restarting in {:?}: {:#}", name, backoff, err);
+                registry.update(name, |health| {
+                    health.state = TaskState::Restarting;
+                    health.restarts += 1;
+                });
+                tokio::select! {
+                    _ = shutdown.cancelled() => {
+                        registry.update(name, |health| health.state = TaskState::Stopped);
+                        return
+                    }
+                    _ = tokio::time::sleep(backoff) => {}
+                }
+                backoff = next_backoff(backoff);
+            }
+        });
+    }
+
+    /// 运行到收到停止信号(或所有任务都已结束),然后等待任务结束
+    pub async fn run(mut self) {
+        loop {
+            tokio::select! {
+                _ = self.shutdown.cancelled() => break,
+                next = self.tasks.join_next() => {
+                    if next.is_none() {
+                        return
+                    }
+                }
+            }
+        }
+        tracing::info!("shutting down, waiting for {} tasks", self.tasks.len());
+        let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+            while self.tasks.join_next().await.is_some() {}
+        }).await;
+        if drained.is_err() {
+            let running: Vec<_> = self.registry.snapshot().into_iter()
+            .filter(|(_, health)| !matches!(health.state, TaskState::Finished | TaskState::Stopped))
+            .map(|(name, _)| name)
+            .collect();
+            tracing::warn!("tasks {:?} did not stop within {:?}, aborting", running, SHUTDOWN_TIMEOUT);
+            self.tasks.abort_all();
+        }
+    }
+}
+
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(MAX_BACKOFF)
+}
+
+/// 收到Ctrl+C或SIGTERM时取消返回的token;停止过程中再次收到信号时立即退出进程
+pub fn shutdown_on_signal() -> CancellationToken {
+    let shutdown = CancellationToken::new();
+    let token = shutdown.clone();
+    tokio::spawn(async move {
+        if let Err(err) = terminate_signal().await {
+            tracing::error!("graceful shutdown disabled: {:#}", err);
+            return
+        }
+        tracing::info!("received shutdown signal");
+        token.cancel();
+        if terminate_signal().await.is_ok() {
+            tracing::warn!("received second shutdown signal, exiting immediately");
+            std::process::exit(1);
+        }
+    });
+    shutdown
+}
+
+#[cfg(unix)]
+async fn terminate_signal() -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).context("failed to listen for SIGTERM")?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result.context("failed to listen for Ctrl+C")?,
+        _ = terminate.recv() => {}
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn terminate_signal() -> anyhow::Result<()> {
+    tokio::signal::ctrl_c().await.context("failed to listen for Ctrl+C")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::{atomic::{AtomicU32, Ordering}, Arc}, time::Duration};
+
+    use anyhow::anyhow;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{next_backoff, Supervisor, TaskRegistry, TaskState, INITIAL_BACKOFF, MAX_BACKOFF};
+
+    #[test]
+    fn test_next_backoff() {
+        assert_eq!(next_backoff(INITIAL_BACKOFF), INITIAL_BACKOFF * 2);
+        assert_eq!(next_backoff(MAX_BACKOFF / 2 + INITIAL_BACKOFF), MAX_BACKOFF);
+        assert_eq!(next_backoff(MAX_BACKOFF), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_restart_and_shutdown() {
+        let shutdown = CancellationToken::new();
+        let registry = TaskRegistry::default();
+        let mut supervisor = Supervisor::new(shutdown.clone(), registry.clone());
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        // 第一次panic,第二次出错,之后运行到收到停止信号
+        supervisor.spawn("flaky", move |shutdown| {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match attempt {
+                    0 => panic!("boom"),
+                    1 => Err(anyhow!("connection lost")),
+                    _ => {
+                        shutdown.cancelled().await;
+                        Ok(())
+                    }
+                }
+            }
+        });
+        supervisor.spawn("finished", |_| async { Ok(()) });
+        let running = tokio::spawn(supervisor.run());
+
+        tokio::time::sleep(INITIAL_BACKOFF * 3 + Duration::from_millis(500)).await;
+        let tasks = registry.snapshot();
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(tasks["flaky"].state, TaskState::Running);
+        assert_eq!(tasks["flaky"].restarts, 2);
+        assert_eq!(tasks["flaky"].last_error.as_deref(), Some("connection lost"));
+        assert_eq!(tasks["finished"].state, TaskState::Finished);
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), running).await.unwrap().unwrap();
+        assert_eq!(registry.snapshot()["flaky"].state, TaskState::Stopped);
+    }
+}
@@ -0,0 +1,151 @@
+//! HTTPS:静态证书文件或ACME(TLS-ALPN-01)自动证书,以及HTTP到HTTPS的重定向
+
+use std::{fs, net::SocketAddr, path::Path, sync::Arc};
+
+use anyhow::{anyhow, Context};
+use axum::{extract::Request, http::{header::HOST, uri::PathAndQuery}, response::Redirect, Router};
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use futures::StreamExt;
+use rustls::{pki_types::{pem::PemObject, CertificateDer}, ClientConfig, RootCertStore};
+use tokio::sync::Mutex;
+use tokio_rustls_acme::{axum::AxumAcceptor, caches::DirCache, AcmeConfig};
+use tokio_util::sync::CancellationToken;
+
+use crate::{settings::{AcmeSettings, TlsConfig, TlsMode}, supervisor::Supervisor};
+
+/// 三个服务共用的TLS配置
+#[derive(Clone)]
+pub enum TlsAcceptor {
+    Files(RustlsConfig),
+    Acme(AxumAcceptor),
+}
+
+/// 按配置加载证书,ACME模式下启动申请和续期证书的任务;未启用时返回None
+pub async fn load(config: &TlsConfig, supervisor: &mut Supervisor) -> anyhow::Result<Option<TlsAcceptor>> {
+    match config.mode {
+        TlsMode::Off => Ok(None),
+        TlsMode::Files => {
+            let rustls_config = RustlsConfig::from_pem_file(&config.cert_path, &config.key_path).await
+            .with_context(|| format!("failed to load certificate {} and key {}", config.cert_path, config.key_path))?;
+            Ok(Some(TlsAcceptor::Files(rustls_config)))
+        }
+        TlsMode::Acme => {
+            let acme = acme_config(&config.acme)?;
+            let state = acme.state();
+            let acceptor = state.axum_acceptor(state.default_rustls_config());
+            // 证书申请和续期由state驱动,重启任务时继续使用同一个state
+            let state = Arc::new(Mutex::new(state));
+            supervisor.spawn("acme", move |shutdown| {
+                let state = state.clone();
+                async move {
+                    let mut state = state.lock().await;
+                    loop {
+                        tokio::select! {
+                            _ = shutdown.cancelled() => return Ok(()),
+                            event = state.next() => match event {
+                                Some(Ok(event)) => tracing::info!("acme: {:?}", event),
+                                Some(Err(err)) => tracing::error!("acme: {:?}", err),
+                                None => return Err(anyhow!("acme state stopped")),
+                            },
+                        }
+                    }
+                }
+            });
+            Ok(Some(TlsAcceptor::Acme(acceptor)))
+        }
+    }
+}
+
+fn acme_config(settings: &AcmeSettings) -> anyhow::Result<AcmeConfig<std::io::Error>> {
+    let config = match &settings.ca_file {
+        Some(ca_file) => AcmeConfig::new_with_client_tls_config(&settings.domains, Arc::new(client_config(ca_file)?)),
+        None => AcmeConfig::new(&settings.domains),
+    };
+    let config = config
+    .contact(settings.contact.iter().map(String::as_str))
+    .cache(DirCache::new(settings.cache_dir.clone()));
+    Ok(match &settings.directory_url {
+        Some(url) => config.directory(url),
+        None => config.directory_lets_encrypt(!settings.staging),
+    })
+}
+
+/// 只信任ca_file中的CA,用于访问Pebble等本地ACME服务
+fn client_config(ca_file: &str) -> anyhow::Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(ca_file).with_context(|| format!("failed to read {}", ca_file))? {
+        roots.add(cert.with_context(|| format!("invalid certificate in {}", ca_file))?)?;
+    }
+    Ok(ClientConfig::builder().with_root_certificates(roots).with_no_client_auth())
+}
+
+/// 使用TLS提供服务,收到停止信号后等待处理中的请求完成
+pub async fn serve(app: Router, addr: SocketAddr, acceptor: TlsAcceptor, shutdown: CancellationToken) -> anyhow::Result<()> {
+    let handle = Handle::new();
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = async {
+        match acceptor {
+            TlsAcceptor::Files(config) => axum_server::bind_rustls(addr, config).handle(handle.clone()).serve(service).await,
+            TlsAcceptor::Acme(acceptor) => axum_server::bind(addr).acceptor(acceptor).handle(handle.clone()).serve(service).await,
+        }
+    };
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => return result.with_context(|| format!("failed to serve {}", addr)),
+        _ = shutdown.cancelled() => handle.graceful_shutdown(None),
+    }
+    server.await.with_context(|| format!("failed to serve {}", addr))
+}
+
+/// 将所有请求重定向到https_port上的同一路径
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |request: Request| async move {
+        let host = request.headers().get(HOST).and_then(|host| host.to_str().ok()).unwrap_or_default();
+        let path = request.uri().path_and_query().map(PathAndQuery::as_str).unwrap_or("/");
+        Redirect::permanent(&https_url(host, https_port, path))
+    })
+}
+
+fn https_url(host: &str, https_port: u16, path: &str) -> String {
+    // 去掉Host中的端口,IPv6地址保留方括号
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') && port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    match https_port {
+        443 => format!("https://{}{}", host, path),
+        port => format!("https://{}:{}{}", host, port, path),
+    }
+}
+
+/// 生成自签名证书,用于本地测试
+pub fn generate_self_signed(domains: &[String], out_dir: &Path) -> anyhow::Result<()> {
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(domains.to_vec())?;
+    fs::create_dir_all(out_dir).with_context(|| format!("failed to create {}", out_dir.display()))?;
+    fs::write(out_dir.join("cert.pem"), cert.pem())?;
+    fs::write(out_dir.join("key.pem"), key_pair.serialize_pem())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_server::tls_rustls::RustlsConfig;
+
+    use super::{generate_self_signed, https_url};
+
+    #[test]
+    fn test_https_url() {
+        assert_eq!(https_url("bassinet.app", 443, "/api/v1/collections?page=2"), "https://bassinet.app/api/v1/collections?page=2");
+        assert_eq!(https_url("bassinet.app:80", 8443, "/"), "https://bassinet.app:8443/");
+        assert_eq!(https_url("[::1]:80", 443, "/"), "https://[::1]/");
+        assert_eq!(https_url("[::1]", 443, "/"), "https://[::1]/");
+    }
+
+    #[tokio::test]
+    async fn test_generate_self_signed() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let dir = tempfile::tempdir().unwrap();
+        generate_self_signed(&["localhost".to_owned()], dir.path()).unwrap();
+        RustlsConfig::from_pem_file(dir.path().join("cert.pem"), dir.path().join("key.pem")).await.unwrap();
+    }
+}
@@ -4,4 +4,10 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub fn current_seconds() -> u64 {
     let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
     time.as_secs()
-}
\ No newline at end of file
+}
+
+/// 当前unix timestamp(毫秒)
+pub fn current_millis() -> u64 {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    time.as_millis() as u64
+}